@@ -2,6 +2,7 @@ use clap::Args;
 use serde::Deserialize;
 
 use crate::db::prepare::{PrepFields, PrepValues};
+use crate::models::contact::parse_country;
 
 #[derive(Debug, Args, Deserialize, PartialEq)]
 pub struct Contact {
@@ -25,6 +26,21 @@ pub struct Contact {
 
     #[arg(long, short)]
     pub zip: Option<String>,
+
+    /// Two-letter ISO country code
+    #[arg(long, value_parser = parse_country)]
+    pub country: Option<String>,
+}
+
+impl Contact {
+    /// Checks the country of a contact read from JSON, which skips the
+    /// parser used on the command line.
+    pub fn check_country(&self) -> Result<(), anyhow::Error> {
+        if let Some(country) = &self.country {
+            parse_country(country).map_err(anyhow::Error::msg)?;
+        }
+        Ok(())
+    }
 }
 
 impl PrepFields for Contact {
@@ -51,6 +67,9 @@ impl PrepFields for Contact {
         if self.zip.is_some() {
             fnames.push("zip".to_string());
         }
+        if self.country.is_some() {
+            fnames.push("country".to_string());
+        }
         fnames
     }
 }
@@ -79,6 +98,9 @@ impl PrepValues for Contact {
         if self.zip.is_some() {
             values.push(self.zip.clone().into());
         }
+        if self.country.is_some() {
+            values.push(self.country.clone().into());
+        }
         values
     }
 }
@@ -0,0 +1,71 @@
+use chrono::Local;
+use clap::Subcommand;
+
+use crate::cli::create::CreateInvoice;
+use crate::commands::selectors::EntitySelector;
+use crate::db::InvoiceDB;
+use crate::db::prepare::PrepCreate;
+use crate::models::invoice::{InvoiceAttrs, InvoiceStage, PaidStatus, QuoteStatus};
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum ConvertCommands {
+    /// Create an invoice from a quote and mark the quote Accepted
+    Quote {
+        id: Option<i64>,
+        /// Convert a Declined or Expired quote anyway
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+pub fn handle_convert(cmd: &ConvertCommands, db: &InvoiceDB) -> Result<(), anyhow::Error> {
+    match cmd {
+        ConvertCommands::Quote { id, force } => {
+            let quote_id = match id {
+                Some(id) => *id,
+                None => EntitySelector::new(db, "invoices", "Select Quote", false).select_entity()?,
+            };
+            let quote = db.get_invoice(&quote_id)?;
+            if quote.attributes.stage != InvoiceStage::Quote {
+                return Err(anyhow::anyhow!("#{} is an invoice, not a quote", quote_id));
+            }
+            if let Some(invoice_id) = db.converted_from(&quote_id)? {
+                return Err(anyhow::anyhow!(
+                    "Quote #{} was already converted to invoice #{}",
+                    quote_id,
+                    invoice_id
+                ));
+            }
+            match quote.quote_status {
+                Some(status @ (QuoteStatus::Declined | QuoteStatus::Expired)) if !force => {
+                    return Err(anyhow::anyhow!(
+                        "Quote #{} is {}, use --force to convert it anyway",
+                        quote_id,
+                        status
+                    ));
+                }
+                _ => {}
+            }
+            let invoice = CreateInvoice {
+                template: quote.template.id,
+                date: Local::now().date_naive(),
+                attributes: InvoiceAttrs {
+                    show_methods: quote.attributes.show_methods,
+                    show_notes: quote.attributes.show_notes,
+                    stage: InvoiceStage::Invoice,
+                    status: PaidStatus::Waiting,
+                },
+                notes: quote.notes.clone(),
+                items: quote.items.iter().map(|line| line.to_item()).collect(),
+                discount: quote.discount.clone(),
+                source_quote: Some(quote_id),
+                expires: None,
+                currency: quote.currency,
+            };
+            let invoice_id = db.convert_quote(&quote_id, invoice.prepare())?;
+            println!("Quote #{} accepted and converted to invoice #{}", quote_id, invoice_id);
+            println!("{}", db.get_invoice(&invoice_id)?.summary());
+        }
+    }
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use chrono::NaiveDate;
 use clap::{Args, Subcommand};
@@ -7,8 +8,18 @@ use rust_decimal::Decimal;
 
 use crate::db::InvoiceDB;
 use crate::cli::contact::Contact;
+use crate::cli::edit::EditMethod;
 use crate::models::EntityUpdater;
-use crate::models::invoice::{InvoiceItem, InvoiceAttrs};
+use crate::models::invoice::{Discount, InvoiceItem, InvoiceAttrs};
+use crate::models::contact::parse_country;
+use crate::models::currency::{parse_currency, Currency};
+use crate::models::methods::{
+    parse_account_number, parse_bank_name, parse_beneficiary, parse_bic, parse_field, parse_iban, parse_instructions,
+    parse_network, parse_paypal, parse_postcode, parse_routing_number, parse_street, parse_town, parse_wallet,
+    MethodKind, Methods, ReferenceType, TEXT_FIELDS,
+};
+use crate::models::numbering::NumberPattern;
+use crate::models::recurring::Interval;
 use crate::db::prepare::{PrepUpdate, PrepCreate};
 
 #[derive(Subcommand, Debug, PartialEq)]
@@ -23,9 +34,11 @@ pub enum CreateCommands {
     /// Create payment terms
     Terms(CreateTerms),
     /// Create payment methods
-    Method { name: String },
+    Method(Box<CreateMethodArgs>),
     /// Create inventory items
     Item(CreateItem),
+    /// Create a named tax rate
+    Tax(CreateTax),
 }
 
 pub fn handle_create(create: &CreateCommands, db: &InvoiceDB) -> Result<i64, anyhow::Error> {
@@ -34,11 +47,23 @@ pub fn handle_create(create: &CreateCommands, db: &InvoiceDB) -> Result<i64, any
             Ok(entities) => {
                 if let Some(companies) = entities.company {
                     for company in companies {
+                        if let Some(pattern) = &company.number_pattern {
+                            NumberPattern::from_str(pattern).map_err(anyhow::Error::msg)?;
+                        }
+                        if let Some(contact) = &company.contact {
+                            contact.check_country()?;
+                        }
                         db.create_entry(company.prepare())?;
                     }
                 }
                 if let Some(clients) = entities.client {
-                    for client in clients {
+                    for mut client in clients {
+                        if let Some(contact) = &client.contact {
+                            contact.check_country()?;
+                        }
+                        if let Some(currency) = &client.currency {
+                            client.currency = Some(parse_currency(currency).map_err(anyhow::Error::msg)?);
+                        }
                         db.create_entry(client.prepare())?;
                     }
                 }
@@ -48,7 +73,8 @@ pub fn handle_create(create: &CreateCommands, db: &InvoiceDB) -> Result<i64, any
                     }
                 }
                 if let Some(methods) = entities.method {
-                    for method in methods {
+                    for mut method in methods {
+                        method.check()?;
                         db.create_entry(method.prepare())?;
                     }
                 }
@@ -57,19 +83,24 @@ pub fn handle_create(create: &CreateCommands, db: &InvoiceDB) -> Result<i64, any
                         db.create_entry(item.prepare())?;
                     }
                 }
+                if let Some(taxes) = entities.tax {
+                    for tax in taxes {
+                        db.create_entry(tax.prepare())?;
+                    }
+                }
                 Ok(0)
             }
             Err(e) => Err(anyhow::anyhow!("Failed to parse JSON: {}", e)),
         },
         CreateCommands::Company{ name: obj } => {
-            let create_company = CreateCompany { name: obj.to_string(), logo: None, contact: None };
+            let create_company = CreateCompany { name: obj.to_string(), logo: None, number_pattern: None, tax_id: None, contact: None };
             let id = db.create_entry(create_company.prepare())?;
             let entity = db.get_company(&id)?;
             db.update_entry(entity.update()?.prepare(), &id)?;
             Ok(id)
         }
         CreateCommands::Client { name: obj } => {
-            let create_client = CreateClient { name: obj.to_string(), contact: None };
+            let create_client = CreateClient { name: obj.to_string(), tax_id: None, currency: None, contact: None };
             let id = db.create_entry(create_client.prepare())?;
             let entity = db.get_client(&id)?;
             db.update_entry(entity.update()?.prepare(), &id)?;
@@ -79,17 +110,42 @@ pub fn handle_create(create: &CreateCommands, db: &InvoiceDB) -> Result<i64, any
             let id = db.create_entry(CreateTerms::prepare(obj))?;
             Ok(id)
         }
-        CreateCommands::Method { name: obj } => {
-            let create_method = CreateMethod { name: obj.to_string(), link: None, qr: None };
+        CreateCommands::Method(obj) => {
+            let mut create_method = CreateMethod {
+                name: obj.name.clone(),
+                link: obj.link.clone(),
+                qr: None,
+                kind: obj.kind.unwrap_or_default(),
+                iban: obj.iban.clone(),
+                bic: obj.bic.clone(),
+                beneficiary: obj.beneficiary.clone(),
+                reference_type: obj.reference_type,
+                street: obj.street.clone(),
+                postcode: obj.postcode.clone(),
+                town: obj.town.clone(),
+                country: obj.country.clone(),
+                bank_name: obj.bank_name.clone(),
+                account_number: obj.account_number.clone(),
+                routing_number: obj.routing_number.clone(),
+                paypal: obj.paypal.clone(),
+                wallet: obj.wallet.clone(),
+                network: obj.network.clone(),
+                instructions: obj.instructions.clone(),
+            };
+            create_method.apply(create_method.draft().update()?);
+            create_method.check()?;
             let id = db.create_entry(create_method.prepare())?;
-            let entity = db.get_method(&id)?;
-            db.update_entry(entity.update()?.prepare(), &id)?;
+            db.print_entry("methods", &id)?;
             Ok(id)
         }
         CreateCommands::Item(obj) => {
             let id = db.create_entry(CreateItem::prepare(obj))?;
             Ok(id)
         }
+        CreateCommands::Tax(obj) => {
+            let id = db.create_entry(CreateTax::prepare(obj))?;
+            Ok(id)
+        }
     }
 }
 
@@ -102,12 +158,16 @@ pub struct FromJSON {
 pub struct CreateCompany {
     pub name: String,
     pub logo: Option<PathBuf>,
+    pub number_pattern: Option<String>,
+    pub tax_id: Option<String>,
     pub contact: Option<Contact>,
 }
 
 #[derive(Debug, Deserialize, PartialEq)]
 pub struct CreateClient {
     pub name: String,
+    pub tax_id: Option<String>,
+    pub currency: Option<String>,
     pub contact: Option<Contact>,
 }
 
@@ -118,11 +178,169 @@ pub struct CreateTerms {
     pub due: u32,
 }
 
+#[derive(Debug, Args, PartialEq, Default)]
+pub struct CreateMethodArgs {
+    pub name: String,
+    /// Decides the fields the method has: sepa generates a payment QR code
+    /// per invoice, qrbill a Swiss QR-bill payment part
+    #[arg(long, value_enum)]
+    pub kind: Option<MethodKind>,
+    /// Where to pay, required for card
+    #[arg(long)]
+    pub link: Option<String>,
+    #[arg(long, value_parser = parse_iban)]
+    pub iban: Option<String>,
+    #[arg(long, value_parser = parse_bic)]
+    pub bic: Option<String>,
+    /// Account holder the transfer is made out to
+    #[arg(long, value_parser = parse_beneficiary)]
+    pub beneficiary: Option<String>,
+    /// QR-bill reference, defaults to qrr with a QR-IBAN and scor otherwise
+    #[arg(long, value_enum)]
+    pub reference_type: Option<ReferenceType>,
+    /// Account holder's street and number, for a QR-bill
+    #[arg(long, value_parser = parse_street)]
+    pub street: Option<String>,
+    #[arg(long, value_parser = parse_postcode)]
+    pub postcode: Option<String>,
+    #[arg(long, value_parser = parse_town)]
+    pub town: Option<String>,
+    #[arg(long, value_parser = parse_country)]
+    pub country: Option<String>,
+    #[arg(long, value_parser = parse_bank_name)]
+    pub bank_name: Option<String>,
+    /// Account number of a bank transfer
+    #[arg(long, value_parser = parse_account_number)]
+    pub account_number: Option<String>,
+    /// Routing number or sort code of a bank transfer
+    #[arg(long, value_parser = parse_routing_number)]
+    pub routing_number: Option<String>,
+    /// PayPal email address or paypal.me link
+    #[arg(long, value_parser = parse_paypal)]
+    pub paypal: Option<String>,
+    /// Crypto wallet address
+    #[arg(long, value_parser = parse_wallet)]
+    pub wallet: Option<String>,
+    /// Crypto asset the wallet takes, e.g. BTC
+    #[arg(long, value_parser = parse_network)]
+    pub network: Option<String>,
+    /// Shown with the method, e.g. where to pay cash
+    #[arg(long, value_parser = parse_instructions)]
+    pub instructions: Option<String>,
+}
+
 #[derive(Debug, Deserialize, PartialEq)]
 pub struct CreateMethod {
     pub name: String,
     pub link: Option<String>,
     pub qr: Option<PathBuf>,
+    #[serde(default)]
+    pub kind: MethodKind,
+    pub iban: Option<String>,
+    pub bic: Option<String>,
+    pub beneficiary: Option<String>,
+    pub reference_type: Option<ReferenceType>,
+    pub street: Option<String>,
+    pub postcode: Option<String>,
+    pub town: Option<String>,
+    pub country: Option<String>,
+    pub bank_name: Option<String>,
+    pub account_number: Option<String>,
+    pub routing_number: Option<String>,
+    pub paypal: Option<String>,
+    pub wallet: Option<String>,
+    pub network: Option<String>,
+    pub instructions: Option<String>,
+}
+
+impl CreateMethod {
+    /// Checks a method read from JSON, which skips the parsers used on the
+    /// command line: normalizes its fields and requires those its kind
+    /// needs. Links are only checked on card methods.
+    pub fn check(&mut self) -> Result<(), anyhow::Error> {
+        let kind = self.kind;
+        for field in TEXT_FIELDS {
+            if field == "link" && kind != MethodKind::Card {
+                continue;
+            }
+            if let Some(slot) = self.text_field(field) {
+                if let Some(value) = slot.as_deref() {
+                    *slot = Some(parse_field(field, value).map_err(anyhow::Error::msg)?);
+                }
+            }
+        }
+        let name = self.name.clone();
+        kind.require(&name, |field| self.text_field(field).is_none_or(|slot| slot.is_some()))
+    }
+    /// The method as it would be stored, to prompt for its other fields
+    /// before it is.
+    fn draft(&self) -> Methods {
+        Methods {
+            name: self.name.clone(),
+            link: self.link.clone(),
+            kind: self.kind,
+            iban: self.iban.clone(),
+            bic: self.bic.clone(),
+            beneficiary: self.beneficiary.clone(),
+            reference_type: self.reference_type,
+            street: self.street.clone(),
+            postcode: self.postcode.clone(),
+            town: self.town.clone(),
+            country: self.country.clone(),
+            bank_name: self.bank_name.clone(),
+            account_number: self.account_number.clone(),
+            routing_number: self.routing_number.clone(),
+            paypal: self.paypal.clone(),
+            wallet: self.wallet.clone(),
+            network: self.network.clone(),
+            instructions: self.instructions.clone(),
+            ..Default::default()
+        }
+    }
+    /// Takes the answers given when prompting on the `draft`.
+    fn apply(&mut self, mut edit: EditMethod) {
+        if let Some(name) = edit.name.take() {
+            self.name = name;
+        }
+        if let Some(kind) = edit.kind {
+            self.kind = kind;
+        }
+        if edit.reference_type.is_some() {
+            self.reference_type = edit.reference_type;
+        }
+        if edit.qr.is_some() {
+            self.qr = edit.qr.take();
+        }
+        for field in TEXT_FIELDS {
+            if let Some(value) = edit.text_field(field).and_then(Option::take) {
+                if let Some(slot) = self.text_field(field) {
+                    *slot = Some(value);
+                }
+            }
+        }
+    }
+    /// The text field named `field`, one of `TEXT_FIELDS`.
+    fn text_field(&mut self, field: &str) -> Option<&mut Option<String>> {
+        let slot = match field {
+            "link" => &mut self.link,
+            "iban" => &mut self.iban,
+            "bic" => &mut self.bic,
+            "beneficiary" => &mut self.beneficiary,
+            "street" => &mut self.street,
+            "postcode" => &mut self.postcode,
+            "town" => &mut self.town,
+            "country" => &mut self.country,
+            "bank_name" => &mut self.bank_name,
+            "account_number" => &mut self.account_number,
+            "routing_number" => &mut self.routing_number,
+            "paypal" => &mut self.paypal,
+            "wallet" => &mut self.wallet,
+            "network" => &mut self.network,
+            "instructions" => &mut self.instructions,
+            _ => return None,
+        };
+        Some(slot)
+    }
 }
 
 #[derive(Debug, Args, Deserialize, PartialEq)]
@@ -130,6 +348,48 @@ pub struct CreateItem {
     pub name: String,
     #[arg(long, short)]
     pub rate: Decimal,
+    /// Unit of measure, e.g. hours, days, each, km
+    #[arg(long, short)]
+    pub unit: Option<String>,
+}
+
+#[derive(Debug, Args, Deserialize, PartialEq)]
+pub struct CreateTax {
+    pub name: String,
+    /// Tax rate as a percentage, e.g. 20 for 20%
+    #[arg(long, short)]
+    pub rate: Decimal,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CreatePayment {
+    pub invoice: i64,
+    pub amount: Decimal,
+    /// Currency of the invoice, whose minor unit the amount is kept in.
+    pub currency: Currency,
+    pub date: NaiveDate,
+    pub method: Option<i64>,
+    pub reference: Option<String>,
+}
+
+/// A credit note before its number is allocated. Line quantities are negative.
+#[derive(Debug, PartialEq)]
+pub struct CreateCreditNote {
+    pub invoice: i64,
+    pub date: NaiveDate,
+    pub reason: Option<String>,
+    pub items: Vec<InvoiceItem>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CreateRecurring {
+    pub name: String,
+    pub template: i64,
+    pub items: Vec<InvoiceItem>,
+    pub interval: Interval,
+    pub day: Option<u32>,
+    pub next_run: NaiveDate,
+    pub end_date: Option<NaiveDate>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -139,6 +399,8 @@ pub struct CreateTemplate {
     pub client: i64,
     pub terms: i64,
     pub methods: Vec<i64>,
+    pub taxes: Vec<i64>,
+    pub layout: Option<String>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -148,4 +410,8 @@ pub struct CreateInvoice {
     pub attributes: InvoiceAttrs,
     pub notes: Option<String>,
     pub items: Vec<InvoiceItem>,
+    pub discount: Option<Discount>,
+    pub source_quote: Option<i64>,
+    pub expires: Option<NaiveDate>,
+    pub currency: Currency,
 }
@@ -0,0 +1,277 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use chrono::{Local, NaiveDate};
+use clap::{Args, Subcommand};
+use inquire::{CustomType, MultiSelect};
+use rust_decimal::Decimal;
+
+use crate::cli::create::CreateCreditNote;
+use crate::cli::generate::{open_viewer, require_tty};
+use crate::commands::output::{print_rows, OutputFormat};
+use crate::commands::selectors::EntitySelector;
+use crate::db::InvoiceDB;
+use crate::db::prepare::PrepCreate;
+use crate::models::invoice::{tax_details, Invoice, InvoiceLine, InvoiceStage};
+use crate::models::items::RATE_PLACES;
+use crate::models::prompt_optional;
+use crate::cli::settings::pdf_engine;
+use crate::render::{PdfEngine, TemplateEngine};
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum CreditCommands {
+    /// Issue a credit note against an invoice
+    Add(AddCredit),
+    /// List credit notes
+    List {
+        /// Only list the credit notes issued against this invoice
+        #[arg(long, short)]
+        invoice: Option<i64>,
+        /// Output format
+        #[arg(long, short, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// Render a credit note to HTML and PDF
+    Render {
+        id: Option<i64>,
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Produce the PDF with this engine instead of the pdf-engine setting
+        #[arg(long, value_enum)]
+        pdf_engine: Option<PdfEngine>,
+        /// Do not open the PDF afterwards, implied when not run from a terminal
+        #[arg(long)]
+        no_open: bool,
+    },
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct AddCredit {
+    /// Invoice ID, prompts when omitted
+    pub invoice: Option<i64>,
+    /// Credit an invoice line as LINE:QTY, where LINE is its position on the
+    /// invoice starting at 1 and QTY defaults to the full quantity. May be repeated
+    #[arg(long = "line", short, value_name = "LINE:QTY")]
+    pub lines: Vec<LineQuantity>,
+    /// Credit a fixed amount with no taxes
+    #[arg(long, short)]
+    pub amount: Option<Decimal>,
+    /// Credit note date (YYYY-MM-DD), defaults to today
+    #[arg(long, short)]
+    pub date: Option<NaiveDate>,
+    /// Why the credit was issued
+    #[arg(long, short)]
+    pub reason: Option<String>,
+}
+
+/// A `LINE:QTY` pair supplied with `--line`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineQuantity {
+    pub line: usize,
+    pub quantity: Option<Decimal>,
+}
+
+impl FromStr for LineQuantity {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (line, quantity) = match s.split_once(':') {
+            Some((line, quantity)) => (line, Some(quantity.trim().parse::<Decimal>())),
+            None => (s, None),
+        };
+        match (line.trim().parse::<usize>(), quantity.transpose()) {
+            (Ok(line), Ok(quantity)) if line > 0 => Ok(LineQuantity { line, quantity }),
+            _ => Err(format!("Invalid line, expected LINE:QTY: {}", s)),
+        }
+    }
+}
+
+pub fn handle_credit(cmd: &CreditCommands, db: &InvoiceDB, renderer: &TemplateEngine) -> Result<(), anyhow::Error> {
+    match cmd {
+        CreditCommands::Add(obj) => {
+            let credit = obj.prompt(db)?;
+            let invoice_id = credit.invoice;
+            let id = db.issue_credit_note(credit.prepare())?;
+            db.print_entry("credit_notes", &id)?;
+            let invoice = db.get_invoice(&invoice_id)?;
+            println!("Invoice #{} balance: {}", invoice_id, invoice.currency.format(invoice.balance()));
+        }
+        CreditCommands::List { invoice, format } => {
+            let credits = match invoice {
+                Some(invoice_id) => db.get_credit_notes(invoice_id)?,
+                None => db.get_table("credit_notes")?
+                    .iter()
+                    .map(|entry| db.get_credit_note(&entry.id))
+                    .collect::<Result<Vec<_>, _>>()?,
+            };
+            print_rows(&credits, *format)?;
+        }
+        CreditCommands::Render { id, output, pdf_engine: engine, no_open } => {
+            let id = match id {
+                Some(id) => *id,
+                None => EntitySelector::new(db, "credit_notes", "Select Credit Note", false).select_entity()?,
+            };
+            let credit = db.get_credit_note(&id)?;
+            let output = match output {
+                Some(output) => output.clone(),
+                None => PathBuf::from(format!("{}_{}.html", credit.reference(), credit.date)),
+            };
+            let render = renderer.render_credit_note(&credit)?;
+            renderer.to_file(&render, &output)?;
+            let page = db.page_setup(&credit.template.company, "credit_note.html")?;
+            let pdf = renderer.credit_note_pdf(&credit, &output, pdf_engine(db, *engine)?, &page)?;
+            open_viewer(&pdf, *no_open);
+        }
+    }
+    Ok(())
+}
+
+/// `quantity` units of the invoice line at `position` (starting at 1),
+/// negated. Line and invoice discounts are folded into the rate so the
+/// credit matches what was charged.
+fn credit_line(invoice: &Invoice, position: usize, quantity: Option<Decimal>) -> Result<InvoiceLine, anyhow::Error> {
+    let line = position
+        .checked_sub(1)
+        .and_then(|index| invoice.items.get(index))
+        .ok_or_else(|| anyhow::anyhow!("Invoice #{} has no line {}", invoice.id, position))?;
+    let quantity = quantity.unwrap_or(line.quantity);
+    if quantity <= Decimal::ZERO || quantity > line.quantity {
+        return Err(anyhow::anyhow!(
+            "Cannot credit {} of line {}, it has a quantity of {}",
+            quantity,
+            position,
+            line.quantity.normalize()
+        ));
+    }
+    // The credited share of what the line was invoiced for, after the
+    // invoice discount. The unit rate keeps enough places to come back to it.
+    let amount = invoice.currency.round(
+        line.subtotal(&invoice.currency) * invoice.discount_factor() * quantity / line.quantity,
+    );
+    let rate = (RATE_PLACES..=28)
+        .map(|places| (amount / quantity).round_dp(places))
+        .find(|rate| invoice.currency.round(rate * quantity) == amount)
+        .unwrap_or(amount / quantity);
+    Ok(InvoiceLine {
+        item: line.item,
+        name: line.name.clone(),
+        description: line.description.clone(),
+        rate,
+        quantity: -quantity,
+        unit: line.unit.clone(),
+        taxes: line.taxes.clone(),
+        discount: None,
+    })
+}
+
+impl AddCredit {
+    fn prompt(&self, db: &InvoiceDB) -> Result<CreateCreditNote, anyhow::Error> {
+        let interactive = self.lines.is_empty() && self.amount.is_none();
+        let invoice_id = match self.invoice {
+            Some(id) => id,
+            None => {
+                require_tty("invoice")?;
+                EntitySelector::new(db, "invoices", "Select Invoice", false).select_entity()?
+            }
+        };
+        let invoice = db.get_invoice(&invoice_id)?;
+        if invoice.attributes.stage != InvoiceStage::Invoice {
+            return Err(anyhow::anyhow!("#{} is a quote, only invoices can be credited", invoice_id));
+        }
+
+        let mut items = Vec::new();
+        for selection in &self.lines {
+            items.push(credit_line(&invoice, selection.line, selection.quantity)?);
+        }
+        if let Some(amount) = self.amount {
+            if amount <= Decimal::ZERO {
+                return Err(anyhow::anyhow!("Credit amount must be positive"));
+            }
+            items.push(InvoiceLine {
+                item: None,
+                name: self.reason.clone().unwrap_or_else(|| "Credit".to_string()),
+                description: None,
+                rate: invoice.currency.round(amount),
+                quantity: Decimal::NEGATIVE_ONE,
+                unit: None,
+                taxes: Vec::new(),
+                discount: None,
+            });
+        }
+        if interactive {
+            require_tty("--line or --amount")?;
+            let options = invoice.items
+                .iter()
+                .enumerate()
+                .map(|(index, line)| format!("{}: {} x {} @ {}", index + 1, line.quantity.normalize(), line.name, invoice.currency.format(line.rate)))
+                .collect::<Vec<_>>();
+            let selections = MultiSelect::new("Select lines to credit:", options).raw_prompt()?;
+            for selection in selections {
+                let line = &invoice.items[selection.index];
+                let quantity = CustomType::<Decimal>::new(&format!("Quantity of {} to credit:", line.name))
+                    .with_default(line.quantity)
+                    .with_error_message("Please enter a valid number")
+                    .prompt()?;
+                items.push(credit_line(&invoice, selection.index + 1, Some(quantity))?);
+            }
+        }
+        if items.is_empty() {
+            return Err(anyhow::anyhow!("A credit note needs at least one line"));
+        }
+        let subtotal: Decimal = items.iter().map(|line| line.subtotal(&invoice.currency)).sum();
+        let taxes: Decimal = tax_details(&items, Decimal::ONE, &invoice.currency).iter().map(|tax| tax.amount).sum();
+        let credited = invoice.calculate_credited() - subtotal - taxes;
+        if credited > invoice.calculate_total() {
+            return Err(anyhow::anyhow!(
+                "Credits of {} would exceed the invoice total of {}",
+                invoice.currency.format(credited),
+                invoice.currency.format(invoice.calculate_total())
+            ));
+        }
+
+        let reason = match &self.reason {
+            Some(reason) => Some(reason.clone()),
+            None if interactive => prompt_optional("Enter a reason for the credit, or 'None':", "None")?
+                .filter(|reason| !reason.trim().is_empty()),
+            None => None,
+        };
+        Ok(CreateCreditNote {
+            invoice: invoice_id,
+            date: self.date.unwrap_or_else(|| Local::now().date_naive()),
+            reason,
+            items: items.iter().map(|line| line.to_item()).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::invoice::tests::{invoice, line};
+    use crate::models::invoice::Discount;
+
+    #[test]
+    fn whole_line_credit_under_fixed_discount_matches_the_invoice() {
+        let invoice = invoice(vec![line("10.00", "3", &[])], Some(Discount::Fixed(Decimal::from(10))));
+        assert_eq!(invoice.calculate_total(), Decimal::new(2000, 2));
+        let credit = credit_line(&invoice, 1, None).unwrap();
+        assert_eq!(credit.quantity, Decimal::from(-3));
+        assert_eq!(credit.subtotal(&invoice.currency), Decimal::new(-2000, 2));
+    }
+
+    #[test]
+    fn partial_credits_add_up_to_the_line() {
+        let invoice = invoice(vec![line("10.00", "3", &[])], Some(Discount::Fixed(Decimal::from(10))));
+        let credited: Decimal = [Decimal::ONE, Decimal::TWO]
+            .into_iter()
+            .map(|quantity| credit_line(&invoice, 1, Some(quantity)).unwrap().subtotal(&invoice.currency))
+            .sum();
+        assert_eq!(credited, Decimal::new(-2000, 2));
+    }
+
+    #[test]
+    fn rejects_more_than_the_line_quantity() {
+        let invoice = invoice(vec![line("10.00", "3", &[])], None);
+        assert!(credit_line(&invoice, 1, Some(Decimal::from(4))).is_err());
+        assert!(credit_line(&invoice, 2, None).is_err());
+    }
+}
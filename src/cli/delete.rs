@@ -12,6 +12,7 @@ pub enum DeleteCommands {
     Terms,
     Method,
     Item,
+    Tax,
     Template,
     Invoice,
 }
@@ -48,6 +49,16 @@ pub fn handle_delete(arg: &DeleteCommands, db: &InvoiceDB) -> Result<(), anyhow:
             println!("{}", entity);
             db.delete_entry(entity.delete()?.prepare(), &id)?;
         }
+        DeleteCommands::Tax => {
+            let id = EntitySelector::new(db, "taxes", "Select Tax", false).select_entity()?;
+            let entity = db.get_tax(&id)?;
+            let users = db.tax_users(&id)?;
+            if !users.is_empty() {
+                return Err(anyhow::anyhow!("{} is still applied by {}", entity.name, users.join(", ")));
+            }
+            println!("{}", entity);
+            db.delete_entry(entity.delete()?.prepare(), &id)?;
+        }
         DeleteCommands::Template => {
             let id = EntitySelector::new(db, "templates", "Select Template", false).select_entity()?;
             let entity = db.get_template(&id)?;
@@ -89,6 +100,18 @@ pub struct DeleteItem {
     pub id: i64,
 }
 #[derive(Debug)]
+pub struct DeleteTax {
+    pub id: i64,
+}
+#[derive(Debug)]
+pub struct DeletePayment {
+    pub id: i64,
+}
+#[derive(Debug)]
+pub struct DeleteRecurring {
+    pub id: i64,
+}
+#[derive(Debug)]
 pub struct DeleteTemplate {
     pub id: i64,
 }
@@ -102,6 +125,9 @@ impl PrepDelete for DeleteClient {}
 impl PrepDelete for DeleteTerms {}
 impl PrepDelete for DeleteMethod {}
 impl PrepDelete for DeleteItem {}
+impl PrepDelete for DeleteTax {}
+impl PrepDelete for DeletePayment {}
+impl PrepDelete for DeleteRecurring {}
 impl PrepDelete for DeleteTemplate {}
 impl PrepDelete for DeleteInvoice {}
 
@@ -134,6 +160,21 @@ impl TableName for DeleteItem {
         "items".to_string()
     }
 }
+impl TableName for DeleteTax {
+    fn table_name(&self) -> String {
+        "taxes".to_string()
+    }
+}
+impl TableName for DeletePayment {
+    fn table_name(&self) -> String {
+        "payments".to_string()
+    }
+}
+impl TableName for DeleteRecurring {
+    fn table_name(&self) -> String {
+        "recurring".to_string()
+    }
+}
 impl TableName for DeleteTemplate {
     fn table_name(&self) -> String {
         "templates".to_string()
@@ -1,3 +1,4 @@
+use chrono::NaiveDate;
 use clap::Subcommand;
 use std::path::PathBuf;
 use rust_decimal::Decimal;
@@ -5,9 +6,11 @@ use rust_decimal::Decimal;
 use crate::db::InvoiceDB;
 use crate::db::prepare::PrepUpdate;
 use crate::models::EntityUpdater;
-use crate::models::invoice::{InvoiceStage, PaidStatus};
+use crate::models::invoice::{Discount, InvoiceStage, PaidStatus, QuoteStatus};
+use crate::models::methods::{MethodKind, Methods, ReferenceType};
 use crate::cli::contact::Contact;
 use crate::commands::selectors::EntitySelector;
+use crate::render::TemplateEngine;
 
 #[derive(Debug, Subcommand, PartialEq)]
 pub enum EditCommands {
@@ -16,11 +19,12 @@ pub enum EditCommands {
     Terms,
     Method,
     Item,
+    Tax,
     Template,
     Invoice,
 }
 
-pub fn handle_edit(edit: &EditCommands, db: &InvoiceDB) -> Result<(), anyhow::Error> {
+pub fn handle_edit(edit: &EditCommands, db: &InvoiceDB, renderer: &TemplateEngine) -> Result<(), anyhow::Error> {
     match edit {
         EditCommands::Company => {
             let id = EntitySelector::new(db, "company", "Select Company", false).select_entity()?;
@@ -40,22 +44,36 @@ pub fn handle_edit(edit: &EditCommands, db: &InvoiceDB) -> Result<(), anyhow::Er
         EditCommands::Method => {
             let id = EntitySelector::new(db, "methods", "Select Payment Method", false).select_entity()?;
             let entity = db.get_method(&id)?;
-            db.update_entry(entity.update()?.prepare(), &id)?;
+            let mut edit = entity.update()?;
+            edit.check(&entity)?;
+            db.update_entry(edit.prepare(), &id)?;
         }
         EditCommands::Item => {
             let id = EntitySelector::new(db, "items", "Select Item", false).select_entity()?;
             let entity = db.get_item(&id)?;
             db.update_entry(entity.update()?.prepare(), &id)?;
         }
+        EditCommands::Tax => {
+            let id = EntitySelector::new(db, "taxes", "Select Tax", false).select_entity()?;
+            let entity = db.get_tax(&id)?;
+            db.update_entry(entity.update()?.prepare(), &id)?;
+        }
         EditCommands::Template => {
             let id = EntitySelector::new(db, "templates", "Select template", false).select_entity()?;
             let entity = db.get_template(&id)?;
-            db.update_entry(entity.update()?.prepare(), &id)?;
+            let edit = entity.update()?;
+            if let Some(layout) = &edit.layout {
+                renderer.require_layout(layout)?;
+            }
+            db.update_entry(edit.prepare(), &id)?;
         }
         EditCommands::Invoice => {
             let id = EntitySelector::new(db, "invoices", "Select invoice", false).select_entity()?;
             let entity = db.get_invoice(&id)?;
             db.update_entry(entity.update()?.prepare(), &id)?;
+            if let Some(number) = db.assign_number(&id)? {
+                println!("Invoice #{} numbered {}", id, number);
+            }
         }
     }
     Ok(())
@@ -67,6 +85,8 @@ pub struct EditCompany {
     pub id: i64,
     pub name: Option<String>,
     pub logo: Option<PathBuf>,
+    pub number_pattern: Option<String>,
+    pub tax_id: Option<String>,
     pub contact: Contact,
 }
 
@@ -74,6 +94,8 @@ pub struct EditCompany {
 pub struct EditClient {
     pub id: i64,
     pub name: Option<String>,
+    pub tax_id: Option<String>,
+    pub currency: Option<String>,
     pub contact: Contact,
 }
 
@@ -84,12 +106,63 @@ pub struct EditTerms {
     pub due: Option<i64>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct EditMethod {
     pub id: i64,
     pub name: Option<String>,
     pub link: Option<String>,
     pub qr: Option<PathBuf>,
+    pub kind: Option<MethodKind>,
+    pub iban: Option<String>,
+    pub bic: Option<String>,
+    pub beneficiary: Option<String>,
+    pub reference_type: Option<ReferenceType>,
+    pub street: Option<String>,
+    pub postcode: Option<String>,
+    pub town: Option<String>,
+    pub country: Option<String>,
+    pub bank_name: Option<String>,
+    pub account_number: Option<String>,
+    pub routing_number: Option<String>,
+    pub paypal: Option<String>,
+    pub wallet: Option<String>,
+    pub network: Option<String>,
+    pub instructions: Option<String>,
+}
+
+impl EditMethod {
+    /// Checks that `method` with this edit saved still has the fields its
+    /// kind requires.
+    pub fn check(&mut self, method: &Methods) -> Result<(), anyhow::Error> {
+        let kind = self.kind.unwrap_or(method.kind);
+        let name = self.name.clone().unwrap_or_else(|| method.name.clone());
+        kind.require(&name, |field| match self.text_field(field) {
+            Some(slot) => slot.is_some() || method.value(field).is_some(),
+            None => true,
+        })
+    }
+    /// The text field named `field`, one of `TEXT_FIELDS`.
+    pub fn text_field(&mut self, field: &str) -> Option<&mut Option<String>> {
+        let slot = match field {
+            "link" => &mut self.link,
+            "iban" => &mut self.iban,
+            "bic" => &mut self.bic,
+            "beneficiary" => &mut self.beneficiary,
+            "street" => &mut self.street,
+            "postcode" => &mut self.postcode,
+            "town" => &mut self.town,
+            "country" => &mut self.country,
+            "bank_name" => &mut self.bank_name,
+            "account_number" => &mut self.account_number,
+            "routing_number" => &mut self.routing_number,
+            "paypal" => &mut self.paypal,
+            "wallet" => &mut self.wallet,
+            "network" => &mut self.network,
+            "instructions" => &mut self.instructions,
+            _ => return None,
+        };
+        Some(slot)
+    }
 }
 
 #[derive(Debug)]
@@ -97,6 +170,15 @@ pub struct EditItem {
     pub id: i64,
     pub name: Option<String>,
     pub rate: Option<Decimal>,
+    /// `Some(None)` clears the unit.
+    pub unit: Option<Option<String>>,
+}
+
+#[derive(Debug)]
+pub struct EditTax {
+    pub id: i64,
+    pub name: Option<String>,
+    pub rate: Option<Decimal>,
 }
 
 #[derive(Debug)]
@@ -107,6 +189,8 @@ pub struct EditTemplate {
     pub client: Option<i64>,
     pub terms: Option<i64>,
     pub methods: Option<Vec<i64>>,
+    pub taxes: Option<Vec<i64>>,
+    pub layout: Option<String>,
 }
 
 #[derive(Debug)]
@@ -117,4 +201,8 @@ pub struct EditInvoice {
     pub stage: Option<InvoiceStage>,
     pub status: Option<PaidStatus>,
     pub notes: Option<String>,
+    /// `Some(None)` clears the invoice discount.
+    pub discount: Option<Option<Discount>>,
+    pub quote_status: Option<QuoteStatus>,
+    pub expires: Option<NaiveDate>,
 }
@@ -0,0 +1,46 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Subcommand;
+
+use crate::commands::selectors::EntitySelector;
+use crate::db::InvoiceDB;
+use crate::render::einvoice::{ExportFormat, Ubl};
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum ExportCommands {
+    /// Write an invoice as a standalone e-invoice XML file
+    Invoice {
+        id: Option<i64>,
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+        /// Defaults to the invoice's file name with an .xml extension
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+pub fn handle_export(cmd: &ExportCommands, db: &InvoiceDB) -> Result<(), anyhow::Error> {
+    match cmd {
+        ExportCommands::Invoice { id, format, output } => {
+            let id = match id {
+                Some(id) => *id,
+                None => EntitySelector::new(db, "invoices", "Select Invoice", false).select_entity()?,
+            };
+            let invoice = db.get_invoice(&id)?;
+            let xml = match format {
+                ExportFormat::Ubl => {
+                    Ubl.check(&invoice)?;
+                    Ubl.xml(&invoice)
+                }
+            };
+            let output = match output {
+                Some(output) => output.clone(),
+                None => PathBuf::from(invoice.file_name()).with_extension("xml"),
+            };
+            fs::write(&output, xml)?;
+            println!("Exported {} as {} to {}", invoice.reference(), format, output.display());
+        }
+    }
+    Ok(())
+}
@@ -1,104 +1,142 @@
-use std::path::PathBuf;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use chrono::NaiveDate;
 use clap::{Args, Subcommand};
-use inquire::{DateSelect, Confirm, Select};
+use inquire::{DateSelect, Confirm, Select, Text};
+use inquire::validator::Validation;
 use anyhow::Result;
+use rust_decimal::Decimal;
 use tokio::runtime::Runtime;
 
 use crate::cli::create::{CreateInvoice, CreateTemplate};
 use crate::db::InvoiceDB;
-use crate::models::editor_optional;
-use crate::models::invoice::{InvoiceItem, InvoiceAttrs, InvoiceStage, PaidStatus};
-use crate::render::TemplateEngine;
+use crate::models::{editor_optional, prompt_optional};
+use crate::models::currency::{parse_currency, Currency};
+use crate::models::taxes::Taxes;
+use crate::models::invoice::{check_quantity, prompt_discount, Discount, Invoice, InvoiceItem, InvoiceAttrs, InvoiceStage, PaidStatus};
+use crate::cli::settings::{pdf_engine, pdf_fonts};
+use crate::render::einvoice::{EInvoiceFormat, FacturX, FacturxProfile};
+use crate::render::{PdfEngine, TemplateEngine};
 use crate::db::prepare::PrepCreate;
+use crate::commands::json_input::{InvoiceSpec, InvoiceSpecLine};
 use crate::commands::selectors::EntitySelector;
 
 #[derive(Debug, Subcommand, PartialEq)]
 pub enum GenerateCommands {
     Template(GenerateTemplate),
-    Invoice(GenerateInvoice),
+    Invoice(Box<GenerateInvoice>),
 }
 
 pub fn handle_generate(gen: &GenerateCommands, db: &InvoiceDB, renderer: &TemplateEngine) -> Result<(), anyhow::Error> {
     match gen {
         GenerateCommands::Template(obj) => {
-            let template = GenerateTemplate::generate(obj, &db)?;
+            if let Some(layout) = &obj.layout {
+                renderer.require_layout(layout)?;
+            }
+            let template = GenerateTemplate::generate(obj, db)?;
             db.create_entry(template.prepare())?;
         }
         GenerateCommands::Invoice(obj) => {
-            let output = match (&obj.id, &obj.output) {
-                (Some(id), Some(output)) => {
-                    let invoice_obj = db.get_invoice(id)?;
-                    let render = renderer.render(&invoice_obj)?;
-                    renderer.to_file(&render, output)?;
-                    let pdf = renderer.to_pdf(&output)?;
-                    // open::that(pdf)?;
-                    (render, pdf, invoice_obj)
-                }
-                (None, Some(output)) => {
-                    let invoice = GenerateInvoice::generate(obj, &db)?;
-                    let new_invoice = db.create_entry(invoice.prepare())?;
-                    let invoice_obj = db.get_invoice(&new_invoice)?;
-                    let render = renderer.render(&invoice_obj)?;
-                    renderer.to_file(&render, output)?;
-                    let pdf = renderer.to_pdf(&output)?;
-                    // open::that(pdf)?;
-                    (render, pdf, invoice_obj)
-                }
-                (Some(id), None) => {
-                    let invoice_obj = db.get_invoice(id)?;
-                    let output = std::path::PathBuf::from(
-                        format!("Invoice{}_{}.html",
-                                invoice_obj.id.to_string(),
-                                invoice_obj.date.to_string()
-                                )
-                        );
-                    let render = renderer.render(&invoice_obj)?;
-                    renderer.to_file(&render, &output)?;
-                    let pdf = renderer.to_pdf(&output)?;
-                    // open::that(pdf)?;
-                    (render, pdf, invoice_obj)
+            if let Some(layout) = &obj.layout {
+                renderer.require_layout(layout)?;
+            }
+            let engine = pdf_engine(db, obj.pdf_engine)?;
+            let facturx = match obj.einvoice {
+                Some(EInvoiceFormat::Facturx) => {
+                    if obj.pdf_engine == Some(PdfEngine::Chrome) {
+                        return Err(anyhow::anyhow!("--einvoice facturx is drawn by the native engine, drop --pdf-engine chrome"));
+                    }
+                    Some(FacturX {
+                        profile: obj.profile.unwrap_or_default(),
+                        fonts: pdf_fonts(db)?,
+                    })
                 }
-                (None, None) => {
-                    let invoice = GenerateInvoice::generate(obj, &db)?;
-                    let id = db.create_entry(invoice.prepare())?;
-                    let invoice_obj = db.get_invoice(&id)?;
-                    let output = std::path::PathBuf::from(
-                        format!("Invoice{}_{}.html",
-                                invoice_obj.id.to_string(),
-                                invoice_obj.date.to_string()
-                                )
-                        );
-                    let render = renderer.render(&invoice_obj)?;
-                    renderer.to_file(&render, &output)?;
-                    let pdf = renderer.to_pdf(&output)?;
-                    // open::that(pdf)?;
-                    (render, pdf, invoice_obj)
+                None => None,
+            };
+            let id = match obj.id {
+                Some(id) => id,
+                None => {
+                    let invoice = GenerateInvoice::generate(obj, db)?;
+                    db.create_invoice(invoice.prepare(), |invoice| match &facturx {
+                        Some(facturx) => facturx.check(invoice),
+                        None => Ok(()),
+                    })?
                 }
             };
+            let output = render_invoice(db, renderer, &id, obj.output.as_ref(), obj.layout.as_deref(), engine, facturx)?;
             if obj.email {
-                if !output.2.template.client.contact.email.is_some() {
-                    println!("Client email field is empty, cannot send email");
-                } else {
-                    println!("I would send an email here");
-                    let config = db.get_config()?;
-                    //let result = config.send_mail()?;
-                    let result = Runtime::new()?.block_on(config.send_mail(output));
-                    if let Err(e) = result {
-                        eprintln!("Email was not sent: {:?}", e);
-                    }
-                }
+                email_invoice(db, output)?;
             } else {
-                open::that(&output.1)?;
+                open_viewer(&output.1, obj.no_open);
             }
         }
     }
     Ok(())
 }
 
+/// Renders an invoice to HTML and PDF, by default as `<number>_<date>.html`
+/// in the current directory. `layout` overrides the invoice template's layout.
+/// With `facturx`, the invoice is checked against its profile first.
+pub fn render_invoice(
+    db: &InvoiceDB,
+    renderer: &TemplateEngine,
+    id: &i64,
+    output: Option<&PathBuf>,
+    layout: Option<&str>,
+    engine: PdfEngine,
+    facturx: Option<FacturX>,
+) -> Result<(String, PathBuf, Invoice)> {
+    let invoice_obj = db.get_invoice(id)?;
+    if let Some(facturx) = &facturx {
+        facturx.check(&invoice_obj)?;
+    }
+    let output = match output {
+        Some(output) => output.clone(),
+        None => PathBuf::from(invoice_obj.file_name()),
+    };
+    let layout = layout.unwrap_or(invoice_obj.template.layout());
+    let render = renderer.render(&invoice_obj, layout)?;
+    renderer.to_file(&render, &output)?;
+    let page = db.page_setup(&invoice_obj.template.company, layout)?;
+    let pdf = renderer.invoice_pdf(&invoice_obj, &output, engine, &page, facturx)?;
+    Ok((render, pdf, invoice_obj))
+}
+
+/// Opens a rendered PDF in the system viewer, unless `no_open` is set or the
+/// command is not run from a terminal. The PDF is already written, so a
+/// viewer that fails to start is only reported.
+pub fn open_viewer(pdf: &Path, no_open: bool) {
+    if no_open || !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return;
+    }
+    if let Err(e) = open::that(pdf) {
+        eprintln!("Could not open {} in a viewer: {}", pdf.display(), e);
+    }
+}
+
+pub fn email_invoice(db: &InvoiceDB, output: (String, PathBuf, Invoice)) -> Result<()> {
+    if output.2.template.client.contact.email.is_none() {
+        println!("Client email field is empty, cannot send email");
+    } else {
+        println!("I would send an email here");
+        let config = db.get_config()?;
+        //let result = config.send_mail()?;
+        let result = Runtime::new()?.block_on(config.send_mail(output));
+        if let Err(e) = result {
+            eprintln!("Email was not sent: {:?}", e);
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug, Args, PartialEq)]
 pub struct GenerateTemplate {
     pub name: String,
+    /// Layout used to render invoices from this template, defaults to default.html
+    #[arg(long, short)]
+    pub layout: Option<String>,
 }
 
 impl GenerateTemplate {
@@ -107,12 +145,15 @@ impl GenerateTemplate {
         let client_selection = EntitySelector::new(db, "client", "Select Client:", true).select_entity()?;
         let terms_selection = EntitySelector::new(db, "terms", "Select Payment Terms:", true).select_entity()?;
         let methods_selection = EntitySelector::new(db, "methods", "Select Payment Methods:", true).multi_select_entity()?;
+        let taxes_selection = EntitySelector::new(db, "taxes", "Select Taxes:", true).multi_select_entity()?;
         let new_template = CreateTemplate {
             name: self.name.clone(),
             company: company_selection,
             client: client_selection,
             terms: terms_selection,
             methods: methods_selection,
+            taxes: taxes_selection,
+            layout: self.layout.clone(),
         };
         Ok(new_template)
     }
@@ -125,29 +166,186 @@ pub struct GenerateInvoice {
     pub output: Option<PathBuf>,
     #[arg(long, short)]
     pub email: bool,
+    /// Do not open the PDF afterwards, implied when not run from a terminal
+    #[arg(long, conflicts_with = "email")]
+    pub no_open: bool,
+    /// Render with this layout instead of the template's
+    #[arg(long)]
+    pub layout: Option<String>,
+    /// Produce the PDF with this engine instead of the pdf-engine setting
+    #[arg(long, value_enum)]
+    pub pdf_engine: Option<PdfEngine>,
+    /// Embed machine-readable invoice data, making the PDF a PDF/A-3 drawn by the native engine
+    #[arg(long, value_enum)]
+    pub einvoice: Option<EInvoiceFormat>,
+    /// Factur-X profile of the embedded data, en16931 by default
+    #[arg(long, value_enum, requires = "einvoice")]
+    pub profile: Option<FacturxProfile>,
+    /// Template ID for a new invoice
+    #[arg(long, short)]
+    pub template: Option<i64>,
+    /// Invoice date (YYYY-MM-DD)
+    #[arg(long, short)]
+    pub date: Option<NaiveDate>,
+    /// Add an item as ID:QTY, may be repeated
+    #[arg(long = "item", short, value_name = "ID:QTY")]
+    pub items: Vec<ItemQuantity>,
+    /// Quote or Invoice
+    #[arg(long)]
+    pub stage: Option<String>,
+    /// Waiting, Past Due, Paid, Failed or Refunded
+    #[arg(long)]
+    pub status: Option<String>,
+    /// Date for a Paid, Failed or Refunded status (YYYY-MM-DD)
+    #[arg(long)]
+    pub status_date: Option<NaiveDate>,
+    /// Check number for a Paid status
+    #[arg(long)]
+    pub check: Option<String>,
+    /// Show payment methods on the invoice
+    #[arg(long)]
+    pub show_methods: Option<bool>,
+    /// Show notes on the invoice
+    #[arg(long)]
+    pub show_notes: Option<bool>,
+    /// Read the invoice notes from a file
+    #[arg(long)]
+    pub notes_file: Option<PathBuf>,
+    /// Discount for the whole invoice, e.g. 10% or 50
+    #[arg(long)]
+    pub discount: Option<Discount>,
+    /// Date a quote stops being valid (YYYY-MM-DD)
+    #[arg(long)]
+    pub expires: Option<NaiveDate>,
+    /// ISO 4217 code of the invoice, defaults to the client's currency or the currency setting
+    #[arg(long, value_parser = parse_currency)]
+    pub currency: Option<String>,
+    /// Read the invoice from a json spec, flags override its values
+    #[arg(long)]
+    pub from_json: Option<PathBuf>,
+}
+
+/// An `ID:QTY` pair supplied with `--item`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemQuantity {
+    pub item: i64,
+    pub quantity: Decimal,
+}
+
+impl FromStr for ItemQuantity {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (item, quantity) = s.split_once(':').unwrap_or((s, "1"));
+        match (item.trim().parse::<i64>(), quantity.trim().parse::<Decimal>()) {
+            (Ok(item), Ok(quantity)) => Ok(ItemQuantity { item, quantity: check_quantity(quantity)? }),
+            _ => Err(format!("Invalid item, expected ID:QTY: {}", s)),
+        }
+    }
+}
+
+/// Errors out when a value is missing and there is no terminal to prompt on.
+pub fn require_tty(missing: &str) -> Result<()> {
+    if io::stdin().is_terminal() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("No {} given and stdin is not a TTY", missing))
+    }
 }
 
 impl GenerateInvoice {
     pub fn generate(&self, db: &InvoiceDB) -> Result<CreateInvoice> {
-        let date = DateSelect::new("Invoice date").prompt()?;
-        let template = EntitySelector::new(db, "templates", "Select Template:", false).select_entity()?;
-        let show_methods = Confirm::new("Show payment method?")
-            .with_default(false)
-            .prompt()?;
-        let show_notes = Confirm::new("Show notes?")
-            .with_default(true)
-            .prompt()?;
-        let stages = vec!["Quote", "Invoice"];
-        let selected_stage = Select::new("Select invoice stage:", stages).prompt()?;
-        
-        let stage = InvoiceStage::from_str(&selected_stage)
-            .map_err(|err| anyhow::anyhow!(err))?;
-
-        let statuses = vec!["Waiting", "Past Due", "Paid", "Failed", "Refunded"];
-        let selected_status = Select::new("Select payment status:", statuses).prompt()?;
-
-        let status = PaidStatus::from_str(&selected_status)
-            .map_err(|err| anyhow::anyhow!(err))?;
+        let spec = match &self.from_json {
+            Some(path) => InvoiceSpec::from_file(path)?,
+            None => InvoiceSpec::default(),
+        };
+        let mut lines = self.items
+            .iter()
+            .map(|line| InvoiceSpecLine {
+                item: Some(line.item),
+                name: None,
+                description: None,
+                rate: None,
+                quantity: line.quantity,
+                unit: None,
+                taxes: None,
+                discount: None,
+            })
+            .collect::<Vec<_>>();
+        if lines.is_empty() {
+            lines = spec.items.unwrap_or_default();
+        }
+        // Once the lines are scripted, the optional extras fall back to
+        // their defaults instead of walking through the wizard.
+        let scripted = !lines.is_empty();
+
+        let date = match self.date.or(spec.date) {
+            Some(date) => date,
+            None => {
+                require_tty("--date")?;
+                DateSelect::new("Invoice date").prompt()?
+            }
+        };
+        let template = match self.template.or(spec.template) {
+            Some(template) => template,
+            None => {
+                require_tty("--template")?;
+                EntitySelector::new(db, "templates", "Select Template:", false).select_entity()?
+            }
+        };
+        let show_methods = match self.show_methods.or(spec.show_methods) {
+            Some(show_methods) => show_methods,
+            None if scripted => false,
+            None => {
+                require_tty("--show-methods")?;
+                Confirm::new("Show payment method?")
+                    .with_default(false)
+                    .prompt()?
+            }
+        };
+        let notes = match (&self.notes_file, spec.notes) {
+            (Some(path), _) => Some(fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Error reading {}: {}", path.display(), e))?),
+            (None, Some(notes)) => Some(notes),
+            (None, None) if scripted => None,
+            (None, None) => {
+                require_tty("--notes-file")?;
+                editor_optional("Enter notes about the invoice, or enter None to leave it blank:", "")?
+            }
+        };
+        let show_notes = match self.show_notes.or(spec.show_notes) {
+            Some(show_notes) => show_notes,
+            None if scripted => notes.is_some(),
+            None => {
+                require_tty("--show-notes")?;
+                Confirm::new("Show notes?")
+                    .with_default(true)
+                    .prompt()?
+            }
+        };
+        let stage = match self.stage.clone().or(spec.stage) {
+            Some(stage) => InvoiceStage::from_str(&stage),
+            None => {
+                require_tty("--stage")?;
+                let stages = vec!["Quote", "Invoice"];
+                let selected_stage = Select::new("Select invoice stage:", stages).prompt()?;
+                InvoiceStage::from_str(selected_stage)
+            }
+        }.map_err(|err| anyhow::anyhow!(err))?;
+
+        let status = match self.status.clone().or(spec.status) {
+            Some(status) => Self::parse_status(
+                &status,
+                self.status_date.or(spec.status_date),
+                self.check.clone().or(spec.check),
+            )?,
+            None => {
+                require_tty("--status")?;
+                let statuses = vec!["Waiting", "Past Due", "Paid", "Failed", "Refunded"];
+                let selected_status = Select::new("Select payment status:", statuses).prompt()?;
+                PaidStatus::from_str(selected_status)
+                    .map_err(|err| anyhow::anyhow!(err))?
+            }
+        };
 
         let attributes = InvoiceAttrs {
             show_methods,
@@ -156,30 +354,161 @@ impl GenerateInvoice {
             status,
         };
 
-        let notes = editor_optional("Enter notes about the invoice, or enter None to leave it blank:", "")?;
-        let item_ids = EntitySelector::new(db, "items", "Add items to the invoice:", true).multi_select_entity()?;
-        let mut items = Vec::new();
-        for item_id in item_ids {
-            let item_short = &db.get_item(&item_id)?;
-            let quantity: i64 = inquire::CustomType::<i64>::new(&format!(
-                "Enter quantity for item {} - {}:",
-                item_short.id, item_short.name
-            ))
-            .with_error_message("Please enter a valid integer")
-            .prompt()?;
-            items.push(InvoiceItem {
-                item: item_id,
-                quantity: quantity,
-            });
-        }
+        let template_obj = db.get_template(&template)?;
+        let currency = match self.currency.as_ref().or(spec.currency.as_ref()) {
+            Some(code) => db.currency(code)?,
+            None => db.client_currency(&template_obj.client)?,
+        };
+        let items = if scripted {
+            lines
+                .into_iter()
+                .map(|line| Self::spec_line(db, line, &currency))
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            require_tty("--item")?;
+            Self::prompt_items(db, &template_obj.taxes, &currency)?
+        };
+        let items = items.into_iter().map(|item| item.with_taxes(&template_obj.taxes)).collect();
+        let discount = match self.discount.clone().or(spec.discount) {
+            Some(discount) => Some(discount),
+            None if scripted => None,
+            None => prompt_discount("Discount for the whole invoice:", "")?,
+        };
+        let expires = match self.expires.or(spec.expires) {
+            Some(expires) => Some(expires),
+            None if scripted || attributes.stage != InvoiceStage::Quote => None,
+            None => DateSelect::new("Quote valid until (Esc for no expiry)")
+                .with_default(date + chrono::Duration::days(30))
+                .prompt_skippable()?,
+        };
         let new_invoice = CreateInvoice {
             template,
             attributes,
             notes,
             date,
             items,
+            discount,
+            source_quote: None,
+            expires,
+            currency,
         };
 
         Ok(new_invoice)
     }
+    fn parse_status(status: &str, date: Option<NaiveDate>, check: Option<String>) -> Result<PaidStatus> {
+        let date = date.map(|date| date.format("%Y%m%d").to_string());
+        let name = PaidStatus::canonical_name(status).map_err(|err| anyhow::anyhow!(err))?;
+        let status = match (name, date) {
+            ("Paid", Some(date)) => PaidStatus::Paid { date, check },
+            ("Past Due", Some(date)) => PaidStatus::PastDue { date },
+            ("Failed", Some(date)) => PaidStatus::Failed { date },
+            ("Refunded", Some(date)) => PaidStatus::Refunded { date },
+            ("Paid" | "Failed" | "Refunded", None) => {
+                require_tty("--status-date")?;
+                PaidStatus::from_str(name).map_err(|err| anyhow::anyhow!(err))?
+            }
+            _ => PaidStatus::from_str(name).map_err(|err| anyhow::anyhow!(err))?,
+        };
+        Ok(status)
+    }
+    fn spec_line(db: &InvoiceDB, line: InvoiceSpecLine, currency: &Currency) -> Result<InvoiceItem> {
+        let (name, rate, unit) = match line.item {
+            Some(item_id) => {
+                let item = db.get_item(&item_id)?;
+                (
+                    line.name.unwrap_or(item.name),
+                    line.rate.unwrap_or(item.rate),
+                    line.unit.or(item.unit),
+                )
+            }
+            None => match (line.name, line.rate) {
+                (Some(name), Some(rate)) => (name, rate, line.unit),
+                _ => return Err(anyhow::anyhow!("Ad-hoc lines need both a name and a rate")),
+            },
+        };
+        Ok(InvoiceItem {
+            item: line.item,
+            name,
+            description: line.description,
+            rate: currency.round(rate),
+            quantity: line.quantity,
+            unit,
+            taxes: line.taxes.map(|ids| Self::tax_copies(db, &ids)).transpose()?,
+            discount: line.discount,
+        })
+    }
+    /// Copies of the taxes with the given ids, to pin on a line.
+    pub fn tax_copies(db: &InvoiceDB, ids: &[i64]) -> Result<Vec<Taxes>> {
+        Ok(ids.iter().map(|id| db.get_tax(id)).collect::<Result<Vec<_>, _>>()?)
+    }
+    pub fn prompt_items(db: &InvoiceDB, template_taxes: &[Taxes], currency: &Currency) -> Result<Vec<InvoiceItem>> {
+        let item_ids = EntitySelector::new(db, "items", "Add items to the invoice:", true).multi_select_entity()?;
+        let mut items = Vec::new();
+        for item_id in item_ids {
+            let item_short = &db.get_item(&item_id)?;
+            let description = prompt_optional(
+                &format!("Description for {} (type 'None' to skip):", item_short.name),
+                "None",
+            )?.filter(|description| !description.trim().is_empty());
+            let line = InvoiceItem {
+                item: Some(item_id),
+                name: item_short.name.clone(),
+                description,
+                rate: currency.round(item_short.rate),
+                quantity: Decimal::ZERO,
+                unit: item_short.unit.clone(),
+                taxes: None,
+                discount: None,
+            };
+            items.push(Self::prompt_line(db, line, template_taxes)?);
+        }
+        while Confirm::new("Add an ad-hoc line?").with_default(false).prompt()? {
+            let name = Text::new("Enter line description:").prompt()?;
+            let rate = inquire::CustomType::<Decimal>::new("Enter rate:")
+                .with_error_message("Please enter a valid number")
+                .prompt()?;
+            let line = InvoiceItem {
+                item: None,
+                name,
+                description: None,
+                rate: currency.round(rate),
+                quantity: Decimal::ZERO,
+                unit: None,
+                taxes: None,
+                discount: None,
+            };
+            items.push(Self::prompt_line(db, line, template_taxes)?);
+        }
+        Ok(items)
+    }
+    fn prompt_line(db: &InvoiceDB, mut line: InvoiceItem, template_taxes: &[Taxes]) -> Result<InvoiceItem> {
+        let unit = line.unit.as_deref().map(|unit| format!(" ({})", unit)).unwrap_or_default();
+        line.quantity = inquire::CustomType::<Decimal>::new(&format!(
+            "Enter quantity for {}{}:",
+            line.name, unit
+        ))
+        .with_error_message("Please enter a valid number")
+        .with_validator(|quantity: &Decimal| match check_quantity(*quantity) {
+            Ok(_) => Ok(Validation::Valid),
+            Err(err) => Ok(Validation::Invalid(err.into())),
+        })
+        .prompt()?;
+        if !db.get_table("taxes")?.is_empty() {
+            let default_taxes = template_taxes
+                .iter()
+                .map(|tax| tax.name.clone())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let use_default = Confirm::new(&format!("Apply template taxes to {}?", line.name))
+                .with_help_message(&format!("Template taxes: {}", if default_taxes.is_empty() { "None" } else { &default_taxes }))
+                .with_default(true)
+                .prompt()?;
+            if !use_default {
+                let ids = EntitySelector::new(db, "taxes", "Select taxes for this item:", false).multi_select_entity()?;
+                line.taxes = Some(Self::tax_copies(db, &ids)?);
+            }
+        }
+        line.discount = prompt_discount(&format!("Discount for {}:", line.name), "")?;
+        Ok(line)
+    }
 }
@@ -0,0 +1,62 @@
+use clap::Subcommand;
+
+use crate::db::InvoiceDB;
+use crate::models::template::Template;
+use crate::render::TemplateEngine;
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum LayoutCommands {
+    /// List the layouts in the templates directory and the templates using them
+    List,
+    /// Test-render layouts against a sample invoice
+    Validate {
+        /// Only validate this layout
+        name: Option<String>,
+    },
+}
+
+pub fn handle_layouts(cmd: &LayoutCommands, db: &InvoiceDB, renderer: &TemplateEngine) -> Result<(), anyhow::Error> {
+    match cmd {
+        LayoutCommands::List => {
+            let templates = db.get_table("templates")?
+                .iter()
+                .map(|entry| db.get_template(&entry.id))
+                .collect::<Result<Vec<Template>, _>>()?;
+            for layout in renderer.layouts() {
+                let users = templates
+                    .iter()
+                    .filter(|template| template.layout() == layout)
+                    .map(|template| template.name.clone())
+                    .collect::<Vec<_>>();
+                if users.is_empty() {
+                    println!("{}", layout);
+                } else {
+                    println!("{}\tused by: {}", layout, users.join(", "));
+                }
+            }
+        }
+        LayoutCommands::Validate { name } => {
+            let layouts = match name {
+                Some(name) => {
+                    renderer.require_layout(name)?;
+                    vec![name.clone()]
+                }
+                None => renderer.layouts(),
+            };
+            let mut failed = 0;
+            for layout in &layouts {
+                match renderer.validate(layout) {
+                    Ok(()) => println!("ok\t{}", layout),
+                    Err(e) => {
+                        println!("failed\t{}: {}", layout, e);
+                        failed += 1;
+                    }
+                }
+            }
+            if failed > 0 {
+                return Err(anyhow::anyhow!("{} of {} layouts failed to render", failed, layouts.len()));
+            }
+        }
+    }
+    Ok(())
+}
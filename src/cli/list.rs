@@ -1,64 +1,113 @@
-use clap::Subcommand;
+use chrono::NaiveDate;
+use clap::{Args, Subcommand};
+use serde::Serialize;
 
 use crate::db::InvoiceDB;
-use crate::commands::selectors::EntitySelector;
+use crate::commands::output::{print_rows, OutputFormat};
+use crate::models::Tabular;
 
 #[derive(Debug, Subcommand, PartialEq)]
 #[group(required = true, multiple = false)]
 pub enum ListFlags {
-    Company,
-    Client,
-    Terms,
-    Methods,
-    Items,
-    Templates,
+    Company(ListArgs),
+    Client(ListArgs),
+    Terms(ListArgs),
+    Methods(ListArgs),
+    Items(ListArgs),
+    Taxes(ListArgs),
+    Templates(ListArgs),
+    Payments(ListArgs),
     Invoices {
+        /// Print the full invoice instead of a table row
         #[arg(short, long)]
         verbose: bool,
+        #[command(flatten)]
+        args: ListArgs,
+        #[command(flatten)]
+        filter: InvoiceFilter,
     },
 }
 
+#[derive(Debug, Args, PartialEq)]
+pub struct ListArgs {
+    /// Output format
+    #[arg(long, short, value_enum, default_value_t)]
+    pub format: OutputFormat,
+    /// Only list the entry with this ID
+    #[arg(long)]
+    pub id: Option<i64>,
+}
+
+/// Narrows `list invoices` down; every filter given must match.
+#[derive(Debug, Args, PartialEq, Default)]
+pub struct InvoiceFilter {
+    /// Client ID or name (case-insensitive, partial match)
+    #[arg(long)]
+    pub client: Option<String>,
+    /// Company ID or name (case-insensitive, partial match)
+    #[arg(long)]
+    pub company: Option<String>,
+    /// Waiting, Past Due, Paid, Failed or Refunded
+    #[arg(long)]
+    pub status: Option<String>,
+    /// Quote or Invoice
+    #[arg(long)]
+    pub stage: Option<String>,
+    /// Invoices dated on or after (YYYY-MM-DD)
+    #[arg(long)]
+    pub from: Option<NaiveDate>,
+    /// Invoices dated on or before (YYYY-MM-DD)
+    #[arg(long)]
+    pub to: Option<NaiveDate>,
+    /// Unpaid invoices past their due date
+    #[arg(long)]
+    pub overdue: bool,
+}
+
 pub fn handle_list(flags: &ListFlags, db: &InvoiceDB) -> Result<(), anyhow::Error> {
     match flags {
-        ListFlags::Company => {
-            let id = EntitySelector::new(db, "company", "Select Company", false).select_entity()?;
-            let entity = db.get_company(&id)?;
-            println!("{}", entity);
-        },
-        ListFlags::Client => {
-            let id = EntitySelector::new(db, "client", "Select Client", false).select_entity()?;
-            let entity = db.get_client(&id)?;
-            println!("{}", entity);
-        },
-        ListFlags::Methods => {
-            let id = EntitySelector::new(db, "methods", "Select Payment Method", false).select_entity()?;
-            let entity = db.get_method(&id)?;
-            println!("{}", entity);
-        },
-        ListFlags::Terms => {
-            let id = EntitySelector::new(db, "terms", "Select Terms", false).select_entity()?;
-            let entity = db.get_terms(&id)?;
-            println!("{}", entity);
-        },
-        ListFlags::Items => {
-            let id = EntitySelector::new(db, "items", "Select Item", false).select_entity()?;
-            let entity = db.get_item(&id)?;
-            println!("{}", entity);
-        },
-        ListFlags::Templates => {
-            let id = EntitySelector::new(db, "templates", "Select Template", false).select_entity()?;
-            let entity = db.get_template(&id)?;
-            println!("{}", entity);
-        },
-        ListFlags::Invoices { verbose } => {
-            let id = EntitySelector::new(db, "invoices", "Select Invoices", false).select_entity()?;
-            let entity = db.get_invoice(&id)?;
-            if *verbose {
-                println!("{}", entity);
+        ListFlags::Company(args) => list(db, "company", args, |id| db.get_company(id)),
+        ListFlags::Client(args) => list(db, "client", args, |id| db.get_client(id)),
+        ListFlags::Methods(args) => list(db, "methods", args, |id| db.get_method(id)),
+        ListFlags::Terms(args) => list(db, "terms", args, |id| db.get_terms(id)),
+        ListFlags::Items(args) => list(db, "items", args, |id| db.get_item(id)),
+        ListFlags::Taxes(args) => list(db, "taxes", args, |id| db.get_tax(id)),
+        ListFlags::Templates(args) => list(db, "templates", args, |id| db.get_template(id)),
+        ListFlags::Payments(args) => list(db, "payments", args, |id| db.get_payment(id)),
+        ListFlags::Invoices { verbose, args, filter } => {
+            let invoices = db.find_invoices(filter, args.id)?
+                .iter()
+                .map(|id| db.get_invoice(id))
+                .collect::<Result<Vec<_>, _>>()?;
+            if *verbose && args.format == OutputFormat::Table {
+                for invoice in invoices {
+                    println!("{}", invoice);
+                }
+                Ok(())
             } else {
-                println!("{}", entity.summary());
+                print_rows(&invoices, args.format)
             }
         }
     }
-    Ok(())
+}
+
+fn fetch<T, F>(db: &InvoiceDB, table: &str, args: &ListArgs, get: F) -> Result<Vec<T>, anyhow::Error>
+where
+    F: Fn(&i64) -> Result<T, rusqlite::Error>,
+{
+    let ids = match args.id {
+        Some(id) => vec![id],
+        None => db.get_table(table)?.iter().map(|entry| entry.id).collect(),
+    };
+    ids.iter()
+        .map(|id| get(id).map_err(|e| anyhow::anyhow!("Could not read {} #{}: {}", table, id, e)))
+        .collect()
+}
+
+fn list<T, F>(db: &InvoiceDB, table: &str, args: &ListArgs, get: F) -> Result<(), anyhow::Error>
+where
+    T: Tabular + Serialize,
+    F: Fn(&i64) -> Result<T, rusqlite::Error>,
+{
+    print_rows(&fetch(db, table, args, get)?, args.format)
 }
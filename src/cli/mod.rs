@@ -3,11 +3,21 @@ use clap_complete::{generate, Generator, Shell};
 use anyhow::Result;
 use clap::{Parser, Subcommand, Command, CommandFactory};
 
+use crate::cli::convert::*;
 use crate::cli::create::*;
+use crate::cli::credit::*;
 use crate::cli::edit::*;
+use crate::cli::export::*;
 use crate::cli::list::*;
+use crate::cli::page_setup::*;
 use crate::cli::delete::*;
 use crate::cli::generate::*;
+use crate::cli::layouts::*;
+use crate::cli::payment::*;
+use crate::cli::recurring::*;
+use crate::cli::report::*;
+use crate::cli::settings::*;
+use crate::cli::status::*;
 use crate::cli::config::configure_email;
 use crate::render::TemplateEngine;
 use crate::db::InvoiceDB;
@@ -17,8 +27,18 @@ pub mod edit;
 pub mod delete;
 pub mod contact;
 pub mod create;
+mod convert;
+mod credit;
+mod export;
 mod generate;
-mod list;
+mod layouts;
+mod page_setup;
+pub mod list;
+mod payment;
+mod recurring;
+mod report;
+mod settings;
+mod status;
 mod config;
 
 #[derive(Parser, Debug, PartialEq)]
@@ -40,6 +60,10 @@ pub enum Commands {
     /// Edit email configuration
     EditConfig,
 
+    #[command(subcommand)]
+    /// Show and change settings such as the PDF engine
+    Settings(SettingsCommands),
+
     #[command(subcommand)]
     /// Create an entity
     Create(CreateCommands),
@@ -59,6 +83,42 @@ pub enum Commands {
     #[command(subcommand)]
     /// Generate a template or an invoice
     Generate(GenerateCommands),
+
+    #[command(subcommand)]
+    /// List and validate the layouts invoices are rendered with
+    Layouts(LayoutCommands),
+
+    #[command(subcommand)]
+    /// Paper size, margins, header and footer of PDFs
+    PageSetup(PageSetupCommands),
+
+    #[command(subcommand)]
+    /// Convert a quote into an invoice
+    Convert(ConvertCommands),
+
+    #[command(subcommand)]
+    /// Issue, list and render credit notes
+    Credit(CreditCommands),
+
+    #[command(subcommand)]
+    /// Export invoices as e-invoice XML
+    Export(ExportCommands),
+
+    #[command(subcommand)]
+    /// Record and review payments against invoices
+    Payment(PaymentCommands),
+
+    #[command(subcommand)]
+    /// Check and update invoice payment status
+    Status(StatusCommands),
+
+    #[command(subcommand)]
+    /// Summarize invoices for bookkeeping
+    Report(ReportCommands),
+
+    #[command(subcommand)]
+    /// Schedule invoices that repeat on an interval
+    Recurring(RecurringCommands),
 }
 
 impl Cli {
@@ -70,24 +130,62 @@ impl Cli {
             print_completions(generator, &mut cmd);
         }
         if let Some(commands) = cli.command { 
+            if !matches!(commands, Commands::Status(_)) {
+                for invoice in &db.past_due {
+                    eprintln!("{}", past_due_line(invoice));
+                }
+                for quote in &db.expired_quotes {
+                    eprintln!("{}", expired_line(quote));
+                }
+            }
             match commands {
                 Commands::EditConfig => {
-                    configure_email(&db)?;
+                    configure_email(db)?;
+                }
+                Commands::Settings(cmd) => {
+                    handle_settings(&cmd, db)?;
                 }
                 Commands::Create(create) => {
-                    handle_create(&create, &db)?;
+                    handle_create(&create, db)?;
                 }
                 Commands::List(flags) => {
-                    handle_list(&flags, &db)?;
+                    handle_list(&flags, db)?;
                 }
                 Commands::Edit(edit) => {
-                    handle_edit(&edit, &db)?;
+                    handle_edit(&edit, db, renderer)?;
                 }
                 Commands::Delete(arg) => {
-                    handle_delete(&arg, &db)?;
+                    handle_delete(&arg, db)?;
                 }
                 Commands::Generate(gen) => {
-                    handle_generate(&gen, &db, &renderer)?;
+                    handle_generate(&gen, db, renderer)?;
+                }
+                Commands::Layouts(cmd) => {
+                    handle_layouts(&cmd, db, renderer)?;
+                }
+                Commands::PageSetup(cmd) => {
+                    handle_page_setup(&cmd, db, renderer)?;
+                }
+                Commands::Convert(cmd) => {
+                    handle_convert(&cmd, db)?;
+                }
+                Commands::Credit(cmd) => {
+                    handle_credit(&cmd, db, renderer)?;
+                }
+                Commands::Export(cmd) => {
+                    handle_export(&cmd, db)?;
+                }
+                Commands::Payment(cmd) => {
+                    handle_payment(&cmd, db)?;
+                }
+                Commands::Status(cmd) => {
+                    handle_status(&cmd, db)?;
+                }
+                Commands::Report(cmd) => {
+                    handle_report(&cmd, db)?;
+                }
+                Commands::Recurring(cmd) => {
+                    handle_recurring(&cmd, db, renderer)?;
                 }
             }
         }
@@ -0,0 +1,134 @@
+use clap::{Args, Subcommand};
+
+use crate::db::InvoiceDB;
+use crate::models::page::{parse_scale, Margins, Orientation, PageOption, PageSetup, PageTemplate, PaperSize};
+use crate::render::TemplateEngine;
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum PageSetupCommands {
+    /// Show the page options used for a company, a layout, or both together
+    Show {
+        #[arg(long, short)]
+        company: Option<i64>,
+        #[arg(long, short)]
+        layout: Option<String>,
+    },
+    /// Set page options for a company or a layout, keeping the others
+    Set(SetPageSetup),
+    /// Clear page options of a company or a layout, all of them by default
+    Clear {
+        #[command(flatten)]
+        target: PageTarget,
+        /// Only clear these options
+        #[arg(value_enum)]
+        options: Vec<PageOption>,
+    },
+}
+
+/// Whose page options to change.
+#[derive(Debug, Args, PartialEq)]
+#[group(required = true, multiple = false)]
+pub struct PageTarget {
+    /// Company ID
+    #[arg(long, short)]
+    pub company: Option<i64>,
+    /// Layout file name, e.g. default.html
+    #[arg(long, short)]
+    pub layout: Option<String>,
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct SetPageSetup {
+    #[command(flatten)]
+    pub target: PageTarget,
+    #[arg(long, value_enum)]
+    pub paper: Option<PaperSize>,
+    #[arg(long, value_enum)]
+    pub orientation: Option<Orientation>,
+    /// One to four lengths in mm, cm, in or pt, in CSS order, e.g. "20mm" or "1in 0.75in"
+    #[arg(long)]
+    pub margins: Option<Margins>,
+    /// Zoom applied to the content, from 0.1 to 2
+    #[arg(long, value_parser = parse_scale)]
+    pub scale: Option<f32>,
+    /// Text at the top of every page. May use {page}, {pages}, {number}, {date}, {company} and {client}
+    #[arg(long)]
+    pub header: Option<PageTemplate>,
+    /// Text at the bottom of every page, e.g. "Page {page} of {pages}"
+    #[arg(long)]
+    pub footer: Option<PageTemplate>,
+}
+
+impl PageTarget {
+    fn load(&self, db: &InvoiceDB, renderer: &TemplateEngine) -> Result<PageSetup, anyhow::Error> {
+        match (&self.company, &self.layout) {
+            (Some(company), _) => Ok(db.get_company(company)?.page_setup()),
+            (None, Some(layout)) => {
+                renderer.require_layout(layout)?;
+                Ok(db.get_layout_page_setup(layout)?)
+            }
+            (None, None) => Err(anyhow::anyhow!("Pass --company or --layout")),
+        }
+    }
+    fn save(&self, db: &InvoiceDB, setup: &PageSetup) -> Result<(), anyhow::Error> {
+        match (&self.company, &self.layout) {
+            (Some(company), _) => db.set_company_page_setup(company, setup),
+            (None, Some(layout)) => db.set_layout_page_setup(layout, setup),
+            (None, None) => Err(anyhow::anyhow!("Pass --company or --layout")),
+        }
+    }
+}
+
+pub fn handle_page_setup(cmd: &PageSetupCommands, db: &InvoiceDB, renderer: &TemplateEngine) -> Result<(), anyhow::Error> {
+    match cmd {
+        PageSetupCommands::Show { company, layout } => {
+            let mut setup = PageSetup::default();
+            if let Some(company) = company {
+                setup = db.get_company(company)?.page_setup();
+            }
+            if let Some(layout) = layout {
+                renderer.require_layout(layout)?;
+                setup = setup.overlay(&db.get_layout_page_setup(layout)?);
+            }
+            print_page_setup(&setup);
+        }
+        PageSetupCommands::Set(obj) => {
+            let setup = obj.target.load(db, renderer)?.overlay(&PageSetup {
+                paper: obj.paper,
+                orientation: obj.orientation,
+                margins: obj.margins,
+                scale: obj.scale,
+                header: obj.header.clone(),
+                footer: obj.footer.clone(),
+            });
+            obj.target.save(db, &setup)?;
+            print_page_setup(&setup);
+        }
+        PageSetupCommands::Clear { target, options } => {
+            let mut setup = target.load(db, renderer)?;
+            if options.is_empty() {
+                setup = PageSetup::default();
+            }
+            for option in options {
+                setup.clear(*option);
+            }
+            target.save(db, &setup)?;
+            print_page_setup(&setup);
+        }
+    }
+    Ok(())
+}
+
+/// Prints `setup` with the defaults filled in for unset options.
+fn print_page_setup(setup: &PageSetup) {
+    let defaults = PageSetup {
+        paper: Some(PaperSize::default()),
+        orientation: Some(Orientation::default()),
+        scale: Some(1.0),
+        ..PageSetup::default()
+    };
+    print!("{}", defaults.overlay(setup));
+    if setup.margins.is_none() {
+        println!("Margins:\tdefault");
+    }
+}
@@ -0,0 +1,162 @@
+use chrono::{Local, NaiveDate};
+use clap::{Args, Subcommand};
+use inquire::{CustomType, DateSelect, Select};
+use rust_decimal::Decimal;
+
+use crate::cli::create::CreatePayment;
+use crate::cli::edit::EditInvoice;
+use crate::commands::selectors::EntitySelector;
+use crate::db::InvoiceDB;
+use crate::db::prepare::{PrepCreate, PrepDelete, PrepUpdate};
+use crate::models::{prompt_optional, EntityDeleter};
+use crate::models::invoice::InvoiceStage;
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum PaymentCommands {
+    /// Record a payment against an invoice
+    Add(AddPayment),
+    /// List the payments recorded against an invoice
+    List { invoice: Option<i64> },
+    /// Remove a payment recorded against an invoice
+    Delete { invoice: Option<i64> },
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct AddPayment {
+    /// Invoice ID, prompts when omitted
+    pub invoice: Option<i64>,
+    /// Amount received, defaults to the outstanding balance
+    #[arg(long, short)]
+    pub amount: Option<Decimal>,
+    /// Payment date (YYYY-MM-DD)
+    #[arg(long, short)]
+    pub date: Option<NaiveDate>,
+    /// Payment method ID
+    #[arg(long, short)]
+    pub method: Option<i64>,
+    /// Reference or check number
+    #[arg(long, short)]
+    pub reference: Option<String>,
+}
+
+pub fn handle_payment(cmd: &PaymentCommands, db: &InvoiceDB) -> Result<(), anyhow::Error> {
+    match cmd {
+        PaymentCommands::Add(obj) => {
+            let payment = obj.prompt(db)?;
+            let invoice_id = payment.invoice;
+            let id = db.create_entry(payment.prepare())?;
+            db.print_entry("payments", &id)?;
+            settle(db, &invoice_id)?;
+        }
+        PaymentCommands::List { invoice } => {
+            let invoice_id = select_invoice(db, invoice)?;
+            let invoice = db.get_invoice(&invoice_id)?;
+            for payment in &invoice.payments {
+                println!("{}", payment);
+            }
+            println!(
+                "Paid: {} of {}",
+                invoice.currency.format(invoice.calculate_paid()),
+                invoice.currency.format(invoice.calculate_total())
+            );
+            println!("Balance: {}", invoice.currency.format(invoice.balance()));
+        }
+        PaymentCommands::Delete { invoice } => {
+            let invoice_id = select_invoice(db, invoice)?;
+            let invoice = db.get_invoice(&invoice_id)?;
+            if invoice.payments.is_empty() {
+                return Err(anyhow::anyhow!("Invoice #{} has no payments", invoice_id));
+            }
+            let options = invoice.payments.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+            let selection = Select::new("Select payment to delete:", options).raw_prompt()?;
+            let payment = &invoice.payments[selection.index];
+            db.delete_entry(payment.delete()?.prepare(), &payment.id)?;
+            settle(db, &invoice_id)?;
+        }
+    }
+    Ok(())
+}
+
+fn select_invoice(db: &InvoiceDB, invoice: &Option<i64>) -> Result<i64, anyhow::Error> {
+    match invoice {
+        Some(id) => Ok(*id),
+        None => EntitySelector::new(db, "invoices", "Select Invoice", false).select_entity(),
+    }
+}
+
+/// Moves the invoice to Paid once the balance is cleared, or back to
+/// Waiting when a payment is removed from a paid invoice. Refunded
+/// invoices are left alone.
+fn settle(db: &InvoiceDB, invoice_id: &i64) -> Result<(), anyhow::Error> {
+    let invoice = db.get_invoice(invoice_id)?;
+    if let Some(status) = invoice.settled_status() {
+        println!("Invoice #{} status changed to {}", invoice.id, status);
+        let edit_invoice = EditInvoice {
+            id: invoice.id,
+            show_methods: None,
+            show_notes: None,
+            stage: None,
+            status: Some(status),
+            notes: None,
+            discount: None,
+            quote_status: None,
+            expires: None,
+        };
+        edit_invoice.prepare().execute(db)?;
+    }
+    println!("Balance: {}", invoice.currency.format(invoice.balance()));
+    Ok(())
+}
+
+impl AddPayment {
+    fn prompt(&self, db: &InvoiceDB) -> Result<CreatePayment, anyhow::Error> {
+        let invoice_id = select_invoice(db, &self.invoice)?;
+        let invoice = db.get_invoice(&invoice_id)?;
+        if invoice.attributes.stage != InvoiceStage::Invoice {
+            return Err(anyhow::anyhow!("Invoice #{} is a quote, payments are recorded against invoices", invoice_id));
+        }
+        let amount = match self.amount {
+            Some(amount) => amount,
+            None => CustomType::<Decimal>::new("Amount received:")
+                .with_default(invoice.balance())
+                .with_error_message("Please enter a valid number")
+                .prompt()?,
+        };
+        let amount = invoice.currency.round(amount);
+        if amount <= Decimal::ZERO {
+            return Err(anyhow::anyhow!("Payment amount must be more than zero, record refunds as credit notes"));
+        }
+        let date = match self.date {
+            Some(date) => date,
+            None => DateSelect::new("Payment date")
+                .with_default(Local::now().date_naive())
+                .prompt()?,
+        };
+        let method = match self.method {
+            Some(method) => Some(method),
+            None if invoice.template.methods.is_empty() => None,
+            None => {
+                let mut options = invoice.template.methods
+                    .iter()
+                    .map(|method| format!("{} - {}", method.id, method.name))
+                    .collect::<Vec<_>>();
+                options.push("None".to_string());
+                let selection = Select::new("Payment method:", options).prompt()?;
+                selection.split(" - ").next().and_then(|id| id.parse::<i64>().ok())
+            }
+        };
+        let reference = match &self.reference {
+            Some(reference) => Some(reference.clone()),
+            None => prompt_optional("Enter a reference or check number, or 'None':", "None")?
+                .filter(|reference| !reference.trim().is_empty()),
+        };
+        Ok(CreatePayment {
+            invoice: invoice_id,
+            amount,
+            date,
+            method,
+            reference,
+            currency: invoice.currency,
+        })
+    }
+}
@@ -0,0 +1,235 @@
+use std::path::PathBuf;
+
+use chrono::{Datelike, Local, NaiveDate};
+use clap::{Args, Subcommand};
+
+use crate::cli::create::{CreateInvoice, CreateRecurring};
+use crate::cli::generate::{email_invoice, render_invoice, require_tty, GenerateInvoice, ItemQuantity};
+use crate::commands::output::{print_rows, OutputFormat};
+use crate::commands::selectors::EntitySelector;
+use crate::db::InvoiceDB;
+use crate::db::prepare::{PrepCreate, PrepDelete};
+use crate::models::invoice::{InvoiceAttrs, InvoiceItem, InvoiceStage, PaidStatus};
+use crate::models::recurring::{Interval, Recurring};
+use crate::models::EntityDeleter;
+use crate::cli::settings::pdf_engine;
+use crate::render::{PdfEngine, TemplateEngine};
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum RecurringCommands {
+    /// Schedule an invoice to be generated from a template on an interval
+    Add(AddRecurring),
+    /// List the recurring schedules
+    List {
+        /// Output format
+        #[arg(long, short, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// Delete a recurring schedule
+    Delete { id: Option<i64> },
+    /// Generate every invoice that has fallen due
+    Run(RunRecurring),
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct AddRecurring {
+    pub name: String,
+    /// Template ID, prompts when omitted
+    #[arg(long, short)]
+    pub template: Option<i64>,
+    /// Add an item as ID:QTY, may be repeated
+    #[arg(long = "item", short, value_name = "ID:QTY")]
+    pub items: Vec<ItemQuantity>,
+    #[arg(long, value_enum, default_value = "monthly")]
+    pub interval: Interval,
+    /// Day of the month for monthly, quarterly and yearly schedules,
+    /// defaults to the day of the start date
+    #[arg(long, short, value_parser = clap::value_parser!(u32).range(1..=31))]
+    pub day: Option<u32>,
+    /// Date of the first invoice (YYYY-MM-DD), defaults to today
+    #[arg(long, short)]
+    pub start: Option<NaiveDate>,
+    /// No invoices are generated after this date (YYYY-MM-DD)
+    #[arg(long, short)]
+    pub end: Option<NaiveDate>,
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct RunRecurring {
+    /// Generate invoices due on or before this date (YYYY-MM-DD), defaults to today
+    #[arg(long)]
+    pub as_of: Option<NaiveDate>,
+    /// Render each generated invoice to HTML and PDF
+    #[arg(long, short)]
+    pub render: bool,
+    /// Email each generated invoice to the client, implies --render
+    #[arg(long, short)]
+    pub email: bool,
+    /// Directory for rendered invoices, defaults to the current directory
+    #[arg(long, short)]
+    pub output_dir: Option<PathBuf>,
+    /// Produce PDFs with this engine instead of the pdf-engine setting
+    #[arg(long, value_enum)]
+    pub pdf_engine: Option<PdfEngine>,
+}
+
+pub fn handle_recurring(cmd: &RecurringCommands, db: &InvoiceDB, renderer: &TemplateEngine) -> Result<(), anyhow::Error> {
+    match cmd {
+        RecurringCommands::Add(obj) => {
+            let schedule = obj.prompt(db)?;
+            let id = db.create_entry(schedule.prepare())?;
+            db.print_entry("recurring", &id)?;
+        }
+        RecurringCommands::List { format } => {
+            let schedules = db.get_table("recurring")?
+                .iter()
+                .map(|entry| db.get_recurring(&entry.id))
+                .collect::<Result<Vec<_>, _>>()?;
+            print_rows(&schedules, *format)?;
+        }
+        RecurringCommands::Delete { id } => {
+            let id = match id {
+                Some(id) => *id,
+                None => EntitySelector::new(db, "recurring", "Select Schedule", false).select_entity()?,
+            };
+            let schedule = db.get_recurring(&id)?;
+            db.delete_entry(schedule.delete()?.prepare(), &id)?;
+            println!("Deleted recurring schedule #{} ({})", id, schedule.name);
+        }
+        RecurringCommands::Run(obj) => {
+            let as_of = obj.as_of.unwrap_or_else(|| Local::now().date_naive());
+            let mut created = Vec::new();
+            for entry in db.get_table("recurring")? {
+                // Each pass re-reads the schedule, so an interrupted or
+                // concurrent run never bills the same period twice.
+                loop {
+                    let schedule = db.get_recurring(&entry.id)?;
+                    if !schedule.is_due(as_of) {
+                        break;
+                    }
+                    let invoice = schedule_invoice(db, &schedule)?;
+                    match db.run_schedule(&schedule, invoice.prepare())? {
+                        Some(invoice_id) => {
+                            println!(
+                                "Generated invoice #{} dated {} from {}",
+                                invoice_id,
+                                schedule.next_run_date(),
+                                schedule.name
+                            );
+                            created.push(invoice_id);
+                        }
+                        None => break,
+                    }
+                }
+            }
+            if created.is_empty() {
+                println!("No recurring invoices are due");
+            }
+            if obj.render || obj.email {
+                let engine = pdf_engine(db, obj.pdf_engine)?;
+                for invoice_id in created {
+                    let output = match &obj.output_dir {
+                        Some(dir) => Some(dir.join(db.get_invoice(&invoice_id)?.file_name())),
+                        None => None,
+                    };
+                    let output = render_invoice(db, renderer, &invoice_id, output.as_ref(), None, engine, None)?;
+                    if obj.email {
+                        email_invoice(db, output)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The invoice owed for the schedule's next run. Lines that reference an
+/// item pick up its current name, rate and unit, and taxes their current rate.
+fn schedule_invoice(db: &InvoiceDB, schedule: &Recurring) -> Result<CreateInvoice, anyhow::Error> {
+    let template = db.get_template(&schedule.template_id)?;
+    let currency = db.client_currency(&template.client)?;
+    let items = schedule.items
+        .iter()
+        .map(|line| {
+            let taxes = line.taxes.as_ref().map(|taxes| {
+                taxes.iter().map(|tax| db.get_tax(&tax.id).unwrap_or_else(|_| tax.clone())).collect()
+            });
+            let line = InvoiceItem { taxes, ..line.clone() };
+            let line = match line.item.map(|id| db.get_item(&id)) {
+                Some(Ok(item)) => InvoiceItem {
+                    name: item.name,
+                    rate: currency.round(item.rate),
+                    unit: item.unit,
+                    ..line
+                },
+                _ => line,
+            };
+            line.with_taxes(&template.taxes)
+        })
+        .collect();
+    Ok(CreateInvoice {
+        template: schedule.template_id,
+        date: schedule.next_run_date(),
+        attributes: InvoiceAttrs {
+            show_methods: !template.methods.is_empty(),
+            show_notes: false,
+            stage: InvoiceStage::Invoice,
+            status: PaidStatus::Waiting,
+        },
+        notes: None,
+        items,
+        discount: None,
+        source_quote: None,
+        expires: None,
+        currency,
+    })
+}
+
+impl AddRecurring {
+    fn prompt(&self, db: &InvoiceDB) -> Result<CreateRecurring, anyhow::Error> {
+        let template = match self.template {
+            Some(template) => template,
+            None => {
+                require_tty("--template")?;
+                EntitySelector::new(db, "templates", "Select Template:", false).select_entity()?
+            }
+        };
+        let template_obj = db.get_template(&template)?;
+        let currency = db.client_currency(&template_obj.client)?;
+        let items = if self.items.is_empty() {
+            require_tty("--item")?;
+            GenerateInvoice::prompt_items(db, &template_obj.taxes, &currency)?
+        } else {
+            self.items
+                .iter()
+                .map(|line| {
+                    let item = db.get_item(&line.item)?;
+                    Ok(InvoiceItem {
+                        item: Some(item.id),
+                        name: item.name,
+                        description: None,
+                        rate: currency.round(item.rate),
+                        quantity: line.quantity,
+                        unit: item.unit,
+                        taxes: None,
+                        discount: None,
+                    })
+                })
+                .collect::<Result<Vec<_>, anyhow::Error>>()?
+        };
+        let next_run = self.start.unwrap_or_else(|| Local::now().date_naive());
+        let day = match self.interval {
+            Interval::Weekly => None,
+            _ => Some(self.day.unwrap_or(next_run.day())),
+        };
+        Ok(CreateRecurring {
+            name: self.name.clone(),
+            template,
+            items,
+            interval: self.interval,
+            day,
+            next_run,
+            end_date: self.end,
+        })
+    }
+}
@@ -0,0 +1,68 @@
+use chrono::{Local, NaiveDate};
+use clap::{Args, Subcommand};
+
+use crate::cli::list::InvoiceFilter;
+use crate::commands::output::{print_rows, OutputFormat};
+use crate::db::InvoiceDB;
+use crate::models::report::{aging, revenue, RevenueGroup};
+use crate::models::invoice::Invoice;
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum ReportCommands {
+    /// Outstanding receivables per client, bucketed by days past due
+    Aging(AgingArgs),
+    /// Invoiced and paid amounts by period, client or item
+    Revenue(RevenueArgs),
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct AgingArgs {
+    /// Output format
+    #[arg(long, short, value_enum, default_value_t)]
+    pub format: OutputFormat,
+    /// Age balances as of this date (YYYY-MM-DD), defaults to today
+    #[arg(long)]
+    pub as_of: Option<NaiveDate>,
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct RevenueArgs {
+    /// Group amounts by
+    #[arg(long, value_enum, default_value = "month")]
+    pub by: RevenueGroup,
+    /// Only count amounts dated on or after (YYYY-MM-DD)
+    #[arg(long)]
+    pub from: Option<NaiveDate>,
+    /// Only count amounts dated on or before (YYYY-MM-DD)
+    #[arg(long)]
+    pub to: Option<NaiveDate>,
+    /// Output format
+    #[arg(long, short, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+fn invoice_stage(db: &InvoiceDB) -> Result<Vec<Invoice>, anyhow::Error> {
+    let filter = InvoiceFilter {
+        stage: Some("Invoice".to_string()),
+        ..Default::default()
+    };
+    db.find_invoices(&filter, None)?
+        .iter()
+        .map(|id| db.get_invoice(id).map_err(|e| e.into()))
+        .collect()
+}
+
+pub fn handle_report(cmd: &ReportCommands, db: &InvoiceDB) -> Result<(), anyhow::Error> {
+    match cmd {
+        ReportCommands::Aging(args) => {
+            let invoices = invoice_stage(db)?;
+            let as_of = args.as_of.unwrap_or_else(|| Local::now().date_naive());
+            print_rows(&aging(&invoices, as_of), args.format)?;
+        }
+        ReportCommands::Revenue(args) => {
+            let invoices = invoice_stage(db)?;
+            print_rows(&revenue(&invoices, args.by, args.from, args.to), args.format)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use clap::Subcommand;
+
+use crate::db::InvoiceDB;
+use crate::models::settings::{system_fonts, Setting};
+use crate::render::PdfEngine;
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum SettingsCommands {
+    /// Show every setting and its value
+    List,
+    /// Change a setting
+    Set {
+        #[arg(value_enum)]
+        setting: Setting,
+        value: String,
+    },
+    /// Restore a setting to its default
+    Unset {
+        #[arg(value_enum)]
+        setting: Setting,
+    },
+}
+
+pub fn handle_settings(cmd: &SettingsCommands, db: &InvoiceDB) -> Result<(), anyhow::Error> {
+    match cmd {
+        SettingsCommands::List => {
+            for setting in Setting::all() {
+                match db.get_setting(*setting)? {
+                    Some(value) => println!("{}\t{}", setting, value),
+                    None => println!("{}\t{} (default)", setting, setting.default_value()),
+                }
+            }
+        }
+        SettingsCommands::Set { setting, value } => {
+            let value = setting.parse(value).map_err(anyhow::Error::msg)?;
+            db.set_setting(*setting, Some(&value))?;
+            println!("{} set to {}", setting, value);
+        }
+        SettingsCommands::Unset { setting } => {
+            db.set_setting(*setting, None)?;
+            println!("{} reset to {}", setting, setting.default_value());
+        }
+    }
+    Ok(())
+}
+
+/// The engine to produce PDFs with: `flag` when given, otherwise the
+/// `pdf-engine` setting.
+pub fn pdf_engine(db: &InvoiceDB, flag: Option<PdfEngine>) -> Result<PdfEngine, anyhow::Error> {
+    if let Some(engine) = flag {
+        return Ok(engine);
+    }
+    match db.get_setting(Setting::PdfEngine)? {
+        Some(value) => value.parse::<PdfEngine>().map_err(anyhow::Error::msg),
+        None => Ok(PdfEngine::default()),
+    }
+}
+
+/// Regular and bold TrueType fonts to embed in PDF/A files: the pdf-font
+/// settings when set, otherwise the first installed system pair.
+pub fn pdf_fonts(db: &InvoiceDB) -> Result<(Vec<u8>, Vec<u8>), anyhow::Error> {
+    let (regular, bold) = match (db.get_setting(Setting::PdfFont)?, db.get_setting(Setting::PdfFontBold)?) {
+        (Some(regular), bold) => (regular.clone(), bold.unwrap_or(regular)),
+        (None, Some(bold)) => return Err(anyhow::anyhow!("pdf-font-bold is set but pdf-font is not: {}", bold)),
+        (None, None) => system_fonts()
+            .map(|(regular, bold)| (regular.to_string(), bold.to_string()))
+            .ok_or_else(|| anyhow::anyhow!("No TrueType font found to embed, set pdf-font and pdf-font-bold"))?,
+    };
+    let read = |path: &str| std::fs::read(path).map_err(|e| anyhow::anyhow!("Error reading font {}: {}", path, e));
+    Ok((read(&regular)?, read(&bold)?))
+}
@@ -0,0 +1,64 @@
+use chrono::{Local, NaiveDate};
+use clap::Subcommand;
+
+use crate::db::InvoiceDB;
+use crate::models::invoice::{Invoice, PaidStatus};
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum StatusCommands {
+    /// Move Waiting invoices past their due date to Past Due and Open
+    /// quotes past their expiry date to Expired
+    Refresh,
+}
+
+pub fn handle_status(cmd: &StatusCommands, db: &InvoiceDB) -> Result<(), anyhow::Error> {
+    match cmd {
+        StatusCommands::Refresh => {
+            // Opening the database already ran the check; pick up anything
+            // that has fallen due since then as well.
+            let today = Local::now().date_naive();
+            let mut changed = db.past_due.iter().collect::<Vec<_>>();
+            let refreshed = db.refresh_past_due(today)?;
+            changed.extend(refreshed.iter());
+            let mut expired = db.expired_quotes.iter().collect::<Vec<_>>();
+            let refreshed = db.expire_quotes(today)?;
+            expired.extend(refreshed.iter());
+            if changed.is_empty() && expired.is_empty() {
+                println!("No invoices are newly past due and no quotes newly expired");
+            }
+            for invoice in changed {
+                println!("{}", past_due_line(invoice));
+            }
+            for quote in expired {
+                println!("{}", expired_line(quote));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn expired_line(quote: &Invoice) -> String {
+    format!(
+        "Quote #{} for {} expired on {}",
+        quote.id,
+        quote.template.client.name,
+        quote.expiry_date().map(|date| date.to_string()).unwrap_or_default()
+    )
+}
+
+pub fn past_due_line(invoice: &Invoice) -> String {
+    let since = match &invoice.attributes.status {
+        PaidStatus::PastDue { date } => NaiveDate::parse_from_str(date, "%Y%m%d")
+            .map(|date| date.to_string())
+            .unwrap_or_else(|_| date.clone()),
+        _ => String::new(),
+    };
+    format!(
+        "Invoice #{} for {} was due {}, marked Past Due on {} (balance {})",
+        invoice.id,
+        invoice.template.client.name,
+        invoice.due_date(),
+        since,
+        invoice.currency.format(invoice.balance())
+    )
+}
@@ -1,11 +1,15 @@
 use std::fs;
+use std::path::Path;
 
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
 use serde::Deserialize;
 use serde_json;
 
 use crate::cli::create::{
-    CreateClient, CreateCompany, CreateItem, CreateMethod, CreateTerms, FromJSON,
+    CreateClient, CreateCompany, CreateItem, CreateMethod, CreateTax, CreateTerms, FromJSON,
 };
+use crate::models::invoice::{check_quantity, Discount};
 
 #[derive(Deserialize, Debug)]
 pub struct Entities {
@@ -14,6 +18,7 @@ pub struct Entities {
     pub terms: Option<Vec<CreateTerms>>,
     pub method: Option<Vec<CreateMethod>>,
     pub item: Option<Vec<CreateItem>>,
+    pub tax: Option<Vec<CreateTax>>,
 }
 
 impl FromJSON {
@@ -23,3 +28,53 @@ impl FromJSON {
         Ok(entities)
     }
 }
+
+/// A scripted invoice for `generate invoice --from-json`. Anything left out
+/// is taken from the command line flags or prompted for.
+#[derive(Deserialize, Debug, Default)]
+pub struct InvoiceSpec {
+    pub template: Option<i64>,
+    pub date: Option<NaiveDate>,
+    pub stage: Option<String>,
+    pub status: Option<String>,
+    pub status_date: Option<NaiveDate>,
+    pub check: Option<String>,
+    pub show_methods: Option<bool>,
+    pub show_notes: Option<bool>,
+    pub notes: Option<String>,
+    pub discount: Option<Discount>,
+    pub expires: Option<NaiveDate>,
+    pub currency: Option<String>,
+    pub items: Option<Vec<InvoiceSpecLine>>,
+}
+
+/// A line on a scripted invoice. Lines with an `item` id default their name,
+/// rate and unit from the items table; lines without one are ad-hoc and must
+/// supply a name and rate.
+#[derive(Deserialize, Debug)]
+pub struct InvoiceSpecLine {
+    pub item: Option<i64>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub rate: Option<Decimal>,
+    pub quantity: Decimal,
+    pub unit: Option<String>,
+    pub taxes: Option<Vec<i64>>,
+    pub discount: Option<Discount>,
+}
+
+impl InvoiceSpec {
+    pub fn from_file(path: &Path) -> Result<Self, anyhow::Error> {
+        let data = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Error reading {}: {}", path.display(), e))?;
+        let spec: InvoiceSpec = serde_json::from_str(&data)?;
+        let line_discounts = spec.items.iter().flatten().filter_map(|line| line.discount.as_ref());
+        for discount in spec.discount.iter().chain(line_discounts) {
+            discount.validate().map_err(anyhow::Error::msg)?;
+        }
+        for line in spec.items.iter().flatten() {
+            check_quantity(line.quantity).map_err(anyhow::Error::msg)?;
+        }
+        Ok(spec)
+    }
+}
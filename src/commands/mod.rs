@@ -1,4 +1,5 @@
 pub mod json_input;
+pub mod output;
 pub mod paths;
 pub mod selectors;
 pub mod send_mail;
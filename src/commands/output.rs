@@ -0,0 +1,80 @@
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::models::Tabular;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Csv,
+}
+
+/// Prints `rows` to stdout in the requested format. A reader that goes away
+/// early, as `head` does, ends the program normally instead of failing it.
+pub fn print_rows<T: Tabular + Serialize>(rows: &[T], format: OutputFormat) -> Result<(), anyhow::Error> {
+    let output = match format {
+        OutputFormat::Table => to_table(rows),
+        OutputFormat::Csv => to_csv(rows),
+        OutputFormat::Json => format!("{}\n", serde_json::to_string_pretty(rows)?),
+    };
+    let mut stdout = io::stdout().lock();
+    match stdout.write_all(output.as_bytes()).and_then(|_| stdout.flush()) {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(0),
+        result => Ok(result?),
+    }
+}
+
+pub fn to_table<T: Tabular>(rows: &[T]) -> String {
+    let headers = T::headers();
+    let cells = rows.iter().map(|row| row.row()).collect::<Vec<_>>();
+    let mut widths = headers.iter().map(|h| h.chars().count()).collect::<Vec<_>>();
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let format_line = |line: Vec<String>| {
+        line.iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    let mut output = String::new();
+    output.push_str(&format_line(headers.iter().map(|h| h.to_string()).collect()));
+    output.push('\n');
+    output.push_str(&format_line(widths.iter().map(|width| "-".repeat(*width)).collect()));
+    output.push('\n');
+    for row in cells {
+        output.push_str(&format_line(row));
+        output.push('\n');
+    }
+    output
+}
+
+pub fn to_csv<T: Tabular>(rows: &[T]) -> String {
+    let mut output = String::new();
+    let headers = T::headers().iter().map(|h| csv_field(h)).collect::<Vec<_>>();
+    output.push_str(&headers.join(","));
+    output.push('\n');
+    for row in rows {
+        let fields = row.row().iter().map(|cell| csv_field(cell)).collect::<Vec<_>>();
+        output.push_str(&fields.join(","));
+        output.push('\n');
+    }
+    output
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
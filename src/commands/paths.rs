@@ -1,6 +1,6 @@
 use anyhow::{Context, Error, Result};
 use directories::ProjectDirs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
 pub struct Paths {
@@ -25,20 +25,42 @@ impl Paths {
         std::fs::create_dir_all(&templates)
             .with_context(|| format!("Unable to create templates dir: {:?}", &templates))?;
 
-        // Create defaults file if none exists
-        templates.push("default.html");
-        let existing_default = templates.is_file();
-        if !existing_default {
-            let default_html = include_str!("../templates/default.html");
-            std::fs::write(&templates, default_html).expect("Unable to create default.html");
-        }
-
-        templates.pop();
+        install_layout(&templates, "default.html", include_str!("../templates/default.html"))?;
+        install_layout(&templates, "credit_note.html", include_str!("../templates/credit_note.html"))?;
 
         let paths = Paths {
             db: db_path,
-            templates: templates,
+            templates,
         };
         Ok(paths)
     }
 }
+
+/// Writes a bundled layout into the templates dir when it is missing. An
+/// existing copy is left alone, since it may have been edited. When its first
+/// line, which carries the layout version, differs from the bundled one, a
+/// warning is printed once per bundled version. The version warned about is
+/// kept next to the templates dir so a customized layout is not flagged on
+/// every run.
+fn install_layout(templates: &Path, name: &str, bundled: &str) -> Result<()> {
+    let path = templates.join(name);
+    if !path.is_file() {
+        return std::fs::write(&path, bundled).with_context(|| format!("Unable to create {}", name));
+    }
+    let installed = std::fs::read_to_string(&path).with_context(|| format!("Unable to read {:?}", path))?;
+    let version = bundled.lines().next().unwrap_or_default();
+    if installed.lines().next() == Some(version) {
+        return Ok(());
+    }
+    let warned = templates.with_file_name(format!(".{}.version", name));
+    if std::fs::read_to_string(&warned).is_ok_and(|seen| seen == version) {
+        return Ok(());
+    }
+    eprintln!(
+        "{:?} is older than this version of invoice-cli and may not show newer invoice fields. \
+         Delete it to have the current version written, then redo any edits of your own. \
+         This is only reported once.",
+        path
+    );
+    std::fs::write(&warned, version).with_context(|| format!("Unable to write {:?}", warned))
+}
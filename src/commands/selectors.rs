@@ -3,7 +3,7 @@ use anyhow::Result;
 use inquire::{MultiSelect, Text, Confirm, Select};
 
 use crate::db::InvoiceDB;
-use crate::cli::create::{CreateCommands, CreateItem, CreateTerms, handle_create};
+use crate::cli::create::{CreateCommands, CreateItem, CreateMethodArgs, CreateTax, CreateTerms, handle_create};
 
 pub struct EntitySelector<'a> {
     pub db: &'a InvoiceDB,
@@ -25,7 +25,7 @@ impl<'a> EntitySelector<'a> {
         }
     }
     pub fn multi_select_entity(&self) -> Result<Vec<i64>, anyhow::Error> {
-        let mut selection = MultiSelect::new(&self.prompt, self.get_options()?).prompt()?;
+        let mut selection = MultiSelect::new(self.prompt, self.get_options()?).prompt()?;
         let mut add_more = self.allow_add && selection.contains(&self.add_new_str.to_string());
         selection.retain(|s| s != &self.add_new_str);
 
@@ -40,16 +40,17 @@ impl<'a> EntitySelector<'a> {
     }
 
     pub fn select_entity(&self) -> Result<i64, anyhow::Error> {
-        let selection = Select::new(&self.prompt, self.get_options()?)
-            .prompt()
-            .map(|ans| ans.split(" - ").next().unwrap().parse::<i64>().unwrap())?;
-        Ok(selection)
+        let selection = Select::new(self.prompt, self.get_options()?).prompt()?;
+        if self.allow_add && selection == self.add_new_str {
+            return self.add_new_entity();
+        }
+        Ok(selection.split(" - ").next().unwrap().parse::<i64>().unwrap())
     }
 
     fn get_options(&self) -> Result<Vec<String>, anyhow::Error> {
         let add_new_str = format!("Add new {}", self.table);
         let list_result = self.db.get_table(self.table)?;
-        if list_result.is_empty() {
+        if list_result.is_empty() && !self.allow_add {
             Err(anyhow::anyhow!("The {} table is empty.", &self.table))
         } else {
             let mut options: Vec<String> = list_result
@@ -66,8 +67,8 @@ impl<'a> EntitySelector<'a> {
     fn add_new_entity(&self) -> Result<i64, anyhow::Error> {
         let name = Text::new(&format!("Enter new {} name:", &self.table).to_string()).prompt()?;
         match self.table {
-            "company" => handle_create(&CreateCommands::Company{ name: name.clone() }, &self.db),
-            "clients" => handle_create(&CreateCommands::Client{ name: name.clone() }, &self.db),
+            "company" => handle_create(&CreateCommands::Company{ name: name.clone() }, self.db),
+            "client" => handle_create(&CreateCommands::Client{ name: name.clone() }, self.db),
             "terms" => {
                 let due = Text::new("Enter due date:")
                     .prompt()?
@@ -77,7 +78,7 @@ impl<'a> EntitySelector<'a> {
                     name: name.clone(),
                     due,
                 });
-                handle_create(&new_terms, &self.db)
+                handle_create(&new_terms, self.db)
             }
 
             "items" => {
@@ -85,13 +86,34 @@ impl<'a> EntitySelector<'a> {
                     .prompt()?
                     .parse::<Decimal>()
                     .map_err(|_| anyhow::anyhow!("Invalid number"))?;
+                let unit = Text::new("Enter item unit (optional):")
+                    .prompt_skippable()?
+                    .filter(|unit| !unit.trim().is_empty());
                 let new_item = CreateCommands::Item(CreateItem {
                     name: name.clone(),
                     rate,
+                    unit,
+                });
+                handle_create(&new_item, self.db)
+            }
+            "methods" => {
+                let new_method = CreateCommands::Method(Box::new(CreateMethodArgs {
+                    name: name.clone(),
+                    ..Default::default()
+                }));
+                handle_create(&new_method, self.db)
+            }
+            "taxes" => {
+                let rate = Text::new("Enter tax rate (percent):")
+                    .prompt()?
+                    .parse::<Decimal>()
+                    .map_err(|_| anyhow::anyhow!("Invalid number"))?;
+                let new_tax = CreateCommands::Tax(CreateTax {
+                    name: name.clone(),
+                    rate,
                 });
-                handle_create(&new_item, &self.db)
+                handle_create(&new_tax, self.db)
             }
-            "methods" => handle_create(&CreateCommands::Method{ name: name.clone() }, &self.db),
             _ => Err(anyhow::anyhow!("Unknown entity type")),
         }
     }
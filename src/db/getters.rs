@@ -1,23 +1,33 @@
+use std::str::FromStr;
+
 use invoice_cli::i64_to_decimal;
 use anyhow::Result;
-use std::collections::HashMap;
+use rusqlite::OptionalExtension;
 
+use crate::cli::list::InvoiceFilter;
 use crate::db::InvoiceDB;
 use crate::models::client::Client;
 use crate::models::company::Company;
 use crate::models::contact::Contact;
+use crate::models::credit_note::CreditNote;
+use crate::models::currency::{Currency, Locale};
 use crate::models::config::Config;
-use crate::models::invoice::{Invoice, InvoiceItem, InvoiceAttrs, InvoiceStage, PaidStatus};
+use crate::models::invoice::{Invoice, InvoiceItem, InvoiceLine, InvoiceAttrs, InvoiceStage, PaidStatus, QuoteStatus};
 use crate::models::template::Template;
-use crate::models::items::Items;
-use crate::models::methods::Methods;
+use crate::models::items::{Items, RATE_PLACES};
+use crate::models::methods::{MethodKind, Methods, ReferenceType};
+use crate::models::payments::Payments;
+use crate::models::recurring::{Interval, Recurring};
+use crate::models::taxes::Taxes;
 use crate::models::terms::Terms;
+use crate::models::page::PageSetup;
+use crate::models::settings::Setting;
 use crate::models::ShortList;
 
 impl InvoiceDB {
     pub fn get_config(&self) -> Result<Config, rusqlite::Error> {
         let query = "SELECT * FROM email_config WHERE id = ?";
-        let config = self.connection.query_row(query, &[&0], |row| {
+        let config = self.connection.query_row(query, [&0], |row| {
             Ok(Config {
                 id: row.get(0)?,
                 smtp_server: row.get(1)?,
@@ -30,9 +40,33 @@ impl InvoiceDB {
         })?;
         Ok(config)
     }
+    /// The stored value of `setting`, or `None` while it is unset.
+    pub fn get_setting(&self, setting: Setting) -> Result<Option<String>, rusqlite::Error> {
+        self.connection
+            .query_row("SELECT value FROM settings WHERE key = ?", [setting.to_string()], |row| row.get(0))
+            .optional()
+    }
+    /// `code` as a currency written in the `locale` setting.
+    pub fn currency(&self, code: &str) -> Result<Currency, rusqlite::Error> {
+        let locale = match self.get_setting(Setting::Locale)? {
+            Some(locale) => Locale::from_str(&locale).map_err(|_| rusqlite::Error::InvalidQuery)?,
+            None => Locale::default(),
+        };
+        Currency::new(code, locale).map_err(|_| rusqlite::Error::InvalidQuery)
+    }
+    /// The currency new invoices to `client` are in: its own, otherwise the
+    /// `currency` setting.
+    pub fn client_currency(&self, client: &Client) -> Result<Currency, rusqlite::Error> {
+        match &client.currency {
+            Some(code) => self.currency(code),
+            None => self.currency(
+                &self.get_setting(Setting::Currency)?.unwrap_or_else(|| Setting::Currency.default_value()),
+            ),
+        }
+    }
     pub fn get_company(&self, id: &i64) -> Result<Company, rusqlite::Error> {
         let query = "SELECT * FROM company WHERE id = ?";
-        let company = self.connection.query_row(query, &[id], |row| {
+        let company = self.connection.query_row(query, [id], |row| {
             Ok(Company {
                 id: row.get(0)?,
                 name: row.get(1)?,
@@ -45,14 +79,32 @@ impl InvoiceDB {
                     city: row.get(7)?,
                     state: row.get(8)?,
                     zip: row.get(9)?,
+                    country: row.get(12)?,
                 },
+                number_pattern: row.get(10)?,
+                page_setup: row.get(11)?,
+                tax_id: row.get(13)?,
             })
         })?;
         Ok(company)
     }
+    /// Page options stored for `layout`, empty when it has none.
+    pub fn get_layout_page_setup(&self, layout: &str) -> Result<PageSetup, rusqlite::Error> {
+        let setup: Option<String> = self.connection
+            .query_row("SELECT page_setup FROM layout_pages WHERE layout = ?", [layout], |row| row.get(0))
+            .optional()?;
+        Ok(setup
+            .and_then(|setup| serde_json::from_str(&setup).ok())
+            .unwrap_or_default())
+    }
+    /// The page options a document from `company` rendered with `layout`
+    /// is printed with: the layout's options over the company's.
+    pub fn page_setup(&self, company: &Company, layout: &str) -> Result<PageSetup, rusqlite::Error> {
+        Ok(company.page_setup().overlay(&self.get_layout_page_setup(layout)?))
+    }
     pub fn get_client(&self, id: &i64) -> Result<Client, rusqlite::Error> {
         let query = "SELECT * FROM client WHERE id = ?";
-        let client = self.connection.query_row(query, &[id], |row| {
+        let client = self.connection.query_row(query, [id], |row| {
             Ok(Client {
                 id: row.get(0)?,
                 name: row.get(1)?,
@@ -64,14 +116,17 @@ impl InvoiceDB {
                     city: row.get(6)?,
                     state: row.get(7)?,
                     zip: row.get(8)?,
+                    country: row.get(9)?,
                 },
+                tax_id: row.get(10)?,
+                currency: row.get(11)?,
             })
         })?;
         Ok(client)
     }
     pub fn get_terms(&self, id: &i64) -> Result<Terms, rusqlite::Error> {
         let query = "SELECT * FROM terms WHERE id = ?";
-        let terms = self.connection.query_row(query, &[id], |row| {
+        let terms = self.connection.query_row(query, [id], |row| {
             Ok(Terms {
                 id: row.get(0)?,
                 name: row.get(1)?,
@@ -82,31 +137,122 @@ impl InvoiceDB {
     }
     pub fn get_method(&self, id: &i64) -> Result<Methods, rusqlite::Error> {
         let query = "SELECT * FROM methods WHERE id = ?";
-        let method = self.connection.query_row(query, &[id], |row| {
+        let method = self.connection.query_row(query, [id], |row| {
+            let kind: String = row.get(4)?;
+            let reference_type: Option<String> = row.get(8)?;
             Ok(Methods {
                 id: row.get(0)?,
                 name: row.get(1)?,
                 link: row.get(2)?,
                 qr: row.get(3)?,
+                kind: MethodKind::from_str(&kind).map_err(|_| rusqlite::Error::InvalidQuery)?,
+                iban: row.get(5)?,
+                bic: row.get(6)?,
+                beneficiary: row.get(7)?,
+                reference_type: reference_type
+                    .map(|reference_type| ReferenceType::from_str(&reference_type))
+                    .transpose()
+                    .map_err(|_| rusqlite::Error::InvalidQuery)?,
+                street: row.get(9)?,
+                postcode: row.get(10)?,
+                town: row.get(11)?,
+                country: row.get(12)?,
+                bank_name: row.get(13)?,
+                account_number: row.get(14)?,
+                routing_number: row.get(15)?,
+                paypal: row.get(16)?,
+                wallet: row.get(17)?,
+                network: row.get(18)?,
+                instructions: row.get(19)?,
             })
         })?;
         Ok(method)
     }
     pub fn get_item(&self, id: &i64) -> Result<Items, rusqlite::Error> {
         let query = "SELECT * FROM items WHERE id = ?";
-        let item = self.connection.query_row(query, &[id], |row| {
+        let item = self.connection.query_row(query, [id], |row| {
             let rate: i64 = row.get(2)?;
             Ok(Items {
                 id: row.get(0)?,
                 name: row.get(1)?,
-                rate: i64_to_decimal!(rate),
+                rate: i64_to_decimal!(rate, RATE_PLACES),
+                unit: row.get(3)?,
             })
         })?;
         Ok(item)
     }
+    pub fn get_tax(&self, id: &i64) -> Result<Taxes, rusqlite::Error> {
+        let query = "SELECT * FROM taxes WHERE id = ?";
+        let tax = self.connection.query_row(query, [id], |row| {
+            let rate: i64 = row.get(2)?;
+            Ok(Taxes {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                rate: i64_to_decimal!(rate, RATE_PLACES),
+            })
+        })?;
+        Ok(tax)
+    }
+    pub fn get_payment(&self, id: &i64) -> Result<Payments, rusqlite::Error> {
+        let query = "SELECT payments.*, methods.name, invoices.currency FROM payments
+                     JOIN invoices ON invoices.id = payments.invoice_id
+                     LEFT JOIN methods ON methods.id = payments.method_id
+                     WHERE payments.id = ?";
+        self.connection.query_row(query, [id], |row| self.payment_from_row(row))
+    }
+    pub fn get_payments(&self, invoice_id: &i64) -> Result<Vec<Payments>, rusqlite::Error> {
+        let query = "SELECT payments.*, methods.name, invoices.currency FROM payments
+                     JOIN invoices ON invoices.id = payments.invoice_id
+                     LEFT JOIN methods ON methods.id = payments.method_id
+                     WHERE payments.invoice_id = ?
+                     ORDER BY payments.date, payments.id";
+        let mut stmt = self.connection.prepare(query)?;
+        let payments = stmt
+            .query_map([invoice_id], |row| self.payment_from_row(row))?
+            .collect::<Result<Vec<Payments>, rusqlite::Error>>()?;
+        Ok(payments)
+    }
+    fn payment_from_row(&self, row: &rusqlite::Row) -> Result<Payments, rusqlite::Error> {
+        let amount: i64 = row.get(2)?;
+        let currency = self.currency(&row.get::<_, String>(7)?)?;
+        Ok(Payments {
+            id: row.get(0)?,
+            invoice_id: row.get(1)?,
+            amount: i64_to_decimal!(amount, currency.minor_units()),
+            currency,
+            date: row.get(3)?,
+            method_id: row.get(4)?,
+            reference: row.get(5)?,
+            method: row.get(6)?,
+        })
+    }
+    pub fn get_recurring(&self, id: &i64) -> Result<Recurring, rusqlite::Error> {
+        let query = "SELECT recurring.*, templates.name FROM recurring
+                     JOIN templates ON templates.id = recurring.template_id
+                     WHERE recurring.id = ?";
+        self.connection.query_row(query, [id], |row| {
+            let items_str: String = row.get(3)?;
+            let items = serde_json::from_str(&items_str).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e))
+            })?;
+            let interval_str: String = row.get(4)?;
+            let interval = Interval::from_str(&interval_str).map_err(|_| rusqlite::Error::InvalidQuery)?;
+            Ok(Recurring {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                template_id: row.get(2)?,
+                items,
+                interval,
+                day: row.get(5)?,
+                next_run: row.get(6)?,
+                end_date: row.get(7)?,
+                template: row.get(8)?,
+            })
+        })
+    }
     pub fn get_template(&self, id: &i64) -> Result<Template, rusqlite::Error> {
         let query = "SELECT * FROM templates WHERE id = ?";
-        let template = self.connection.query_row(query, &[id], |row| {
+        let template = self.connection.query_row(query, [id], |row| {
             let company_id: i64 = row.get(2)?;
             let client_id: i64 = row.get(3)?;
             let terms_id: i64 = row.get(4)?;
@@ -122,20 +268,30 @@ impl InvoiceDB {
                 methods.push(obj);
             }
 
+            let taxes_json: String = row.get(6)?;
+            let tax_list: Vec<i64> =
+                serde_json::from_str(&taxes_json).expect("Failed to deserialize taxes");
+            let taxes = tax_list
+                .iter()
+                .map(|tax| self.get_tax(tax))
+                .collect::<Result<Vec<Taxes>, rusqlite::Error>>()?;
+
             Ok(Template {
                 id: row.get(0)?,
                 name: row.get(1)?,
                 company: self.get_company(&company_id)?,
                 client: self.get_client(&client_id)?,
                 terms: self.get_terms(&terms_id)?,
-                methods: methods,
+                methods,
+                taxes,
+                layout: row.get(7)?,
             })
         })?;
         Ok(template)
     }
     pub fn get_invoice(&self, id: &i64) -> Result<Invoice, rusqlite::Error> {
         let query = "SELECT * FROM invoices WHERE id = ?";
-        let invoice = self.connection.query_row(query, &[id], |row| {
+        let invoice = self.connection.query_row(query, [id], |row| {
             let template_id: i64 = row.get(1)?;
             let date: String = row.get(2)?;
             let show_methods: bool = match row.get(3)? {
@@ -162,7 +318,9 @@ impl InvoiceDB {
 
             let status = match status_str.as_str() {
                 "Waiting" => PaidStatus::Waiting,
-                "Past Due" => PaidStatus::PastDue,
+                "Past Due" => PaidStatus::PastDue {
+                    date: status_date.unwrap_or_else(|| "Unknown".to_string()),
+                },
                 "Paid" => PaidStatus::Paid {
                     date: status_date.unwrap_or_else(|| "Unknown".to_string()),
                     check: status_check
@@ -178,6 +336,11 @@ impl InvoiceDB {
 
             let notes: Option<String> = row.get(9)?;
             let items_str: String = row.get(10)?;
+            let discount_str: Option<String> = row.get(11)?;
+            let discount = discount_str
+                .map(|discount| serde_json::from_str(&discount))
+                .transpose()
+                .map_err(|_| rusqlite::Error::ExecuteReturnedResults)?;
 
             let attributes = InvoiceAttrs{
                 show_methods,
@@ -186,31 +349,182 @@ impl InvoiceDB {
                 status
             };
 
-            let items_vec: Vec<InvoiceItem> = serde_json::from_str(&items_str)
-                .map_err(|_| rusqlite::Error::ExecuteReturnedResults)?;
-            let items: HashMap<Items, i64> = items_vec
-                .into_iter()
-                .map(|item| {
-                    let item_data = self.get_item(&item.item)?;
-                    Ok((item_data, item.quantity))
-                })
-                .collect::<Result<HashMap<Items, i64>, rusqlite::Error>>()?;
+            let template = self.get_template(&template_id)?;
+            let items = self.invoice_lines(&items_str)?;
+            let quote_status = row.get::<_, Option<String>>(13)?
+                .map(|status| QuoteStatus::from_str(&status))
+                .transpose()
+                .map_err(|_| rusqlite::Error::InvalidQuery)?;
+            let id: i64 = row.get(0)?;
             Ok(Invoice {
-                id: row.get(0)?,
-                template: self.get_template(&template_id)?,
+                id,
+                template,
                 date,
                 attributes,
                 notes,
                 items,
+                discount,
+                payments: self.get_payments(&id)?,
+                credits: self.get_credit_notes(&id)?,
+                source_quote: row.get(12)?,
+                quote_status,
+                expires: row.get(14)?,
+                number: row.get(15)?,
+                currency: self.currency(&row.get::<_, String>(17)?)?,
             })
         })?;
         Ok(invoice)
     }
 
+    /// Reads the lines stored in an `items_json` column, with the copies of
+    /// their taxes taken when the document was created.
+    fn invoice_lines(&self, items_str: &str) -> Result<Vec<InvoiceLine>, rusqlite::Error> {
+        let items_vec: Vec<InvoiceItem> = serde_json::from_str(items_str)
+            .map_err(|_| rusqlite::Error::ExecuteReturnedResults)?;
+        Ok(items_vec
+            .into_iter()
+            .map(|item| InvoiceLine {
+                item: item.item,
+                name: item.name,
+                description: item.description,
+                rate: item.rate,
+                quantity: item.quantity,
+                unit: item.unit,
+                taxes: item.taxes.unwrap_or_default(),
+                discount: item.discount,
+            })
+            .collect())
+    }
+    pub fn get_credit_note(&self, id: &i64) -> Result<CreditNote, rusqlite::Error> {
+        let query = "SELECT credit_notes.*, invoices.template_id, invoices.date, invoices.number, invoices.currency FROM credit_notes
+                     JOIN invoices ON invoices.id = credit_notes.invoice_id
+                     WHERE credit_notes.id = ?";
+        self.connection.query_row(query, [id], |row| self.credit_note_from_row(row))
+    }
+    /// Credit notes issued against `invoice_id`, oldest first.
+    pub fn get_credit_notes(&self, invoice_id: &i64) -> Result<Vec<CreditNote>, rusqlite::Error> {
+        let query = "SELECT credit_notes.*, invoices.template_id, invoices.date, invoices.number, invoices.currency FROM credit_notes
+                     JOIN invoices ON invoices.id = credit_notes.invoice_id
+                     WHERE credit_notes.invoice_id = ?
+                     ORDER BY credit_notes.number";
+        let mut stmt = self.connection.prepare(query)?;
+        let credits = stmt
+            .query_map([invoice_id], |row| self.credit_note_from_row(row))?
+            .collect::<Result<Vec<CreditNote>, rusqlite::Error>>()?;
+        Ok(credits)
+    }
+    fn credit_note_from_row(&self, row: &rusqlite::Row) -> Result<CreditNote, rusqlite::Error> {
+        let items_str: String = row.get(5)?;
+        let template = self.get_template(&row.get(6)?)?;
+        Ok(CreditNote {
+            id: row.get(0)?,
+            number: row.get(1)?,
+            invoice_id: row.get(2)?,
+            date: row.get(3)?,
+            reason: row.get(4)?,
+            items: self.invoice_lines(&items_str)?,
+            template,
+            invoice_date: row.get(7)?,
+            invoice_number: row.get(8)?,
+            currency: self.currency(&row.get::<_, String>(9)?)?,
+        })
+    }
+    /// The invoice converted from `quote_id`, if any.
+    pub fn converted_from(&self, quote_id: &i64) -> Result<Option<i64>, rusqlite::Error> {
+        self.connection
+            .query_row(
+                "SELECT id FROM invoices WHERE source_quote_id = ? ORDER BY id LIMIT 1",
+                [quote_id],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+    /// Templates, invoices, credit notes and recurring schedules that still
+    /// apply the tax `id`, named for an error message.
+    pub fn tax_users(&self, id: &i64) -> Result<Vec<String>, rusqlite::Error> {
+        let line_taxes = "SELECT 1 FROM json_each(items_json) AS line, json_each(line.value, '$.taxes') AS tax
+                          WHERE tax.value = ?1";
+        let query = format!(
+            "SELECT 'template ' || name FROM templates
+             WHERE EXISTS (SELECT 1 FROM json_each(taxes_json) WHERE value = ?1)
+             UNION ALL SELECT 'invoice #' || id FROM invoices WHERE EXISTS ({0})
+             UNION ALL SELECT 'credit note #' || id FROM credit_notes WHERE EXISTS ({0})
+             UNION ALL SELECT 'recurring schedule ' || name FROM recurring WHERE EXISTS ({0})",
+            line_taxes
+        );
+        let mut stmt = self.connection.prepare(&query)?;
+        let users = stmt
+            .query_map([id], |row| row.get(0))?
+            .collect::<Result<Vec<String>, rusqlite::Error>>()?;
+        Ok(users)
+    }
+    /// IDs of the invoices matching `filter`, oldest first.
+    pub fn find_invoices(&self, filter: &InvoiceFilter, id: Option<i64>) -> Result<Vec<i64>> {
+        let mut query = String::from(
+            "SELECT invoices.id FROM invoices
+             JOIN templates ON templates.id = invoices.template_id
+             JOIN client ON client.id = templates.client_id
+             JOIN company ON company.id = templates.company_id
+             JOIN terms ON terms.id = templates.terms_id
+             WHERE 1 = 1",
+        );
+        let mut params: Vec<rusqlite::types::Value> = Vec::new();
+        if let Some(id) = id {
+            query.push_str(" AND invoices.id = ?");
+            params.push(id.into());
+        }
+        for (table, value) in [("client", &filter.client), ("company", &filter.company)] {
+            if let Some(value) = value {
+                match value.parse::<i64>() {
+                    Ok(id) => {
+                        query.push_str(&format!(" AND {}.id = ?", table));
+                        params.push(id.into());
+                    }
+                    Err(_) => {
+                        query.push_str(&format!(" AND {}.name LIKE ? ESCAPE '\\'", table));
+                        let value = value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+                        params.push(format!("%{}%", value).into());
+                    }
+                }
+            }
+        }
+        if let Some(status) = &filter.status {
+            let status = PaidStatus::canonical_name(status).map_err(|err| anyhow::anyhow!(err))?;
+            query.push_str(" AND invoices.status = ?");
+            params.push(status.to_string().into());
+        }
+        if let Some(stage) = &filter.stage {
+            let stage = InvoiceStage::from_str(stage).map_err(|err| anyhow::anyhow!(err))?;
+            query.push_str(" AND invoices.stage = ?");
+            params.push(stage.to_string().into());
+        }
+        if let Some(from) = &filter.from {
+            query.push_str(" AND invoices.date >= ?");
+            params.push(from.format("%Y%m%d").to_string().into());
+        }
+        if let Some(to) = &filter.to {
+            query.push_str(" AND invoices.date <= ?");
+            params.push(to.format("%Y%m%d").to_string().into());
+        }
+        if filter.overdue {
+            query.push_str(
+                " AND invoices.stage = 'Invoice'
+                  AND invoices.status IN ('Waiting', 'Past Due')
+                  AND date(substr(invoices.date, 1, 4) || '-' || substr(invoices.date, 5, 2) || '-' || substr(invoices.date, 7, 2),
+                           '+' || terms.due || ' days') < date('now', 'localtime')",
+            );
+        }
+        query.push_str(" ORDER BY invoices.date, invoices.id");
+        let mut stmt = self.connection.prepare(&query)?;
+        let ids = stmt
+            .query_map(rusqlite::params_from_iter(params), |row| row.get(0))?
+            .collect::<Result<Vec<i64>, rusqlite::Error>>()?;
+        Ok(ids)
+    }
     pub fn get_table(&self, table_name: &str) -> Result<Vec<ShortList>, rusqlite::Error> {
         let query = match table_name {
-            "invoices" => format!("SELECT id, date FROM {}", table_name),
-            _ => format!("SELECT id, name FROM {}", table_name),
+            "invoices" | "payments" | "credit_notes" => format!("SELECT id, date FROM {} ORDER BY id", table_name),
+            _ => format!("SELECT id, name FROM {} ORDER BY id", table_name),
         };
 
         let mut stmt = self.connection.prepare(&query)?;
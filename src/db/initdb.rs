@@ -21,7 +21,11 @@ impl<'conn> InvoiceTx<'conn> {
                 addr2 TEXT,
                 city TEXT,
                 state TEXT,
-                zip TEXT
+                zip TEXT,
+                number_pattern TEXT,
+                page_setup TEXT,
+                country TEXT,
+                tax_id TEXT
             )",
                 [],
             )
@@ -37,7 +41,10 @@ impl<'conn> InvoiceTx<'conn> {
                 addr2 TEXT,
                 city TEXT,
                 state TEXT,
-                zip TEXT
+                zip TEXT,
+                country TEXT,
+                tax_id TEXT,
+                currency TEXT
             )",
                 [],
             )
@@ -47,7 +54,8 @@ impl<'conn> InvoiceTx<'conn> {
                 "CREATE TABLE IF NOT EXISTS items (
                  id INTEGER PRIMARY KEY AUTOINCREMENT,
                  name TEXT NOT NULL UNIQUE,
-                 rate INTEGER
+                 rate INTEGER,
+                 unit TEXT
              )",
                 [],
             )
@@ -68,11 +76,37 @@ impl<'conn> InvoiceTx<'conn> {
                  id INTEGER PRIMARY KEY AUTOINCREMENT,
                  name NOT NULL UNIQUE,
                  link TEXT,
-                 qr BLOB
+                 qr BLOB,
+                 kind TEXT NOT NULL DEFAULT 'other',
+                 iban TEXT,
+                 bic TEXT,
+                 beneficiary TEXT,
+                 reference_type TEXT,
+                 street TEXT,
+                 postcode TEXT,
+                 town TEXT,
+                 country TEXT,
+                 bank_name TEXT,
+                 account_number TEXT,
+                 routing_number TEXT,
+                 paypal TEXT,
+                 wallet TEXT,
+                 network TEXT,
+                 instructions TEXT
              )",
                 [],
             )
             .context("failed to create methods table")?;
+        self.tx
+            .execute(
+                "CREATE TABLE IF NOT EXISTS taxes (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 name TEXT NOT NULL UNIQUE,
+                 rate INTEGER NOT NULL
+             )",
+                [],
+            )
+            .context("failed to create taxes table")?;
         self.tx
             .execute(
                 "CREATE TABLE IF NOT EXISTS templates (
@@ -82,6 +116,8 @@ impl<'conn> InvoiceTx<'conn> {
                 client_id INTEGER NOT NULL,
                 terms_id INTEGER NOT NULL,
                 methods_json TEXT NOT NULL,
+                taxes_json TEXT NOT NULL DEFAULT '[]',
+                layout TEXT,
                 FOREIGN KEY (company_id)
                     REFERENCES company (id)
                     ON DELETE NO ACTION
@@ -112,14 +148,77 @@ impl<'conn> InvoiceTx<'conn> {
                  status_check TEXT,
                  notes TEXT,
                  items_json TEXT NOT NULL,
+                 discount_json TEXT,
+                 source_quote_id INTEGER,
+                 quote_status TEXT,
+                 expires TEXT,
+                 number TEXT,
+                 number_seq INTEGER,
+                 currency TEXT NOT NULL,
+                 company_id INTEGER,
                  FOREIGN KEY (template_id)
                      REFERENCES templates (id)
                      ON DELETE NO ACTION
+                     ON UPDATE NO ACTION,
+                 FOREIGN KEY (source_quote_id)
+                     REFERENCES invoices (id)
+                     ON DELETE SET NULL
                      ON UPDATE NO ACTION
              )",
                 [],
             )
             .context("failed to create invoices table")?;
+        self.tx
+            .execute(
+                "CREATE TABLE IF NOT EXISTS payments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                invoice_id INTEGER NOT NULL,
+                amount INTEGER NOT NULL,
+                date TEXT NOT NULL,
+                method_id INTEGER,
+                reference TEXT,
+                FOREIGN KEY (invoice_id)
+                    REFERENCES invoices (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION,
+                FOREIGN KEY (method_id)
+                    REFERENCES methods (id)
+                    ON DELETE SET NULL
+                    ON UPDATE NO ACTION
+            )",
+                [],
+            )
+            .context("failed to create payments table")?;
+        self.tx.execute(
+            "CREATE TABLE IF NOT EXISTS recurring (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                template_id INTEGER NOT NULL,
+                items_json TEXT NOT NULL,
+                interval TEXT NOT NULL,
+                day INTEGER,
+                next_run TEXT NOT NULL,
+                end_date TEXT,
+                FOREIGN KEY (template_id)
+                    REFERENCES templates (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION
+            );", [])
+            .context("failed to create recurring table")?;
+        self.tx.execute(
+            "CREATE TABLE IF NOT EXISTS credit_notes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                number INTEGER UNIQUE,
+                invoice_id INTEGER NOT NULL,
+                date TEXT NOT NULL,
+                reason TEXT,
+                items_json TEXT NOT NULL,
+                FOREIGN KEY (invoice_id)
+                    REFERENCES invoices (id)
+                    ON DELETE NO ACTION
+                    ON UPDATE NO ACTION
+            );", [])
+            .context("failed to create credit_notes table")?;
         self.tx.execute(
             "CREATE TABLE IF NOT EXISTS email_config (
                 id INTEGER PRIMARY KEY CHECK (id = 0),
@@ -131,6 +230,21 @@ impl<'conn> InvoiceTx<'conn> {
                 fromname TEXT NOT NULL
             );", [])
             .context("failed to create email_config table")?;
+        self.tx.execute(
+            "CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );", [])
+            .context("failed to create settings table")?;
+        self.tx.execute(
+            "CREATE TABLE IF NOT EXISTS layout_pages (
+                layout TEXT PRIMARY KEY,
+                page_setup TEXT NOT NULL
+            );", [])
+            .context("failed to create layout_pages table")?;
+        self.tx.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS invoices_company_number ON invoices (company_id, number);", [])
+            .context("failed to create invoice number index")?;
 
         Ok(())
     }
@@ -1,6 +1,11 @@
 use anyhow::{Context, Result};
+use invoice_cli::i64_to_decimal;
+use rusqlite::OptionalExtension;
 
 use crate::db::InvoiceTx;
+use crate::models::currency::{Currency, Locale};
+use crate::models::items::RATE_PLACES;
+use crate::models::settings::Setting;
 
 impl<'conn> InvoiceTx<'conn> {
     pub fn migrate01(&self) -> Result<()> {
@@ -56,6 +61,401 @@ impl<'conn> InvoiceTx<'conn> {
 
         Ok(())
     }
+    pub fn migrate03(&self) -> Result<()> {
+        self.tx.execute(
+            "CREATE TABLE IF NOT EXISTS taxes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                rate INTEGER NOT NULL
+            );", [])
+            .context("failed to create taxes table")?;
+        self.tx.execute(
+            "ALTER TABLE templates ADD COLUMN taxes_json TEXT NOT NULL DEFAULT '[]';", [])
+            .context("failed to add taxes to templates")?;
+
+        Ok(())
+    }
+    pub fn migrate04(&self) -> Result<()> {
+        self.tx.execute(
+            "ALTER TABLE invoices ADD COLUMN discount_json TEXT;", [])
+            .context("failed to add discount to invoices")?;
+
+        Ok(())
+    }
+    pub fn migrate05(&self) -> Result<()> {
+        let mut stmt = self.tx.prepare("SELECT id, items_json FROM invoices")?;
+        let invoices = stmt
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<(i64, String)>, rusqlite::Error>>()
+            .context("failed to read invoice items")?;
+
+        for (id, items_json) in invoices {
+            let mut lines: Vec<serde_json::Value> = serde_json::from_str(&items_json)
+                .with_context(|| format!("failed to parse items for invoice {}", id))?;
+            for line in lines.iter_mut() {
+                let Some(obj) = line.as_object_mut() else { continue };
+                if obj.contains_key("name") {
+                    continue;
+                }
+                let item_id = obj.get("item").and_then(|item| item.as_i64()).unwrap_or_default();
+                let item: Option<(String, i64)> = self.tx
+                    .query_row(
+                        "SELECT name, rate FROM items WHERE id = ?",
+                        [item_id],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .optional()?;
+                let (name, rate) = item
+                    .unwrap_or_else(|| (format!("Deleted item #{}", item_id), 0));
+                obj.insert("name".to_string(), name.into());
+                obj.insert("rate".to_string(), i64_to_decimal!(rate, 2).to_string().into());
+            }
+            self.tx.execute(
+                "UPDATE invoices SET items_json = ? WHERE id = ?",
+                rusqlite::params![serde_json::to_string(&lines)?, id])
+                .with_context(|| format!("failed to snapshot items for invoice {}", id))?;
+        }
+
+        Ok(())
+    }
+    pub fn migrate06(&self) -> Result<()> {
+        self.tx.execute(
+            "ALTER TABLE items ADD COLUMN unit TEXT;", [])
+            .context("failed to add unit to items")?;
+
+        Ok(())
+    }
+    pub fn migrate07(&self) -> Result<()> {
+        self.tx.execute(
+            "CREATE TABLE IF NOT EXISTS payments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                invoice_id INTEGER NOT NULL,
+                amount INTEGER NOT NULL,
+                date TEXT NOT NULL,
+                method_id INTEGER,
+                reference TEXT,
+                FOREIGN KEY (invoice_id)
+                    REFERENCES invoices (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION,
+                FOREIGN KEY (method_id)
+                    REFERENCES methods (id)
+                    ON DELETE SET NULL
+                    ON UPDATE NO ACTION
+            );", [])
+            .context("failed to create payments table")?;
+
+        Ok(())
+    }
+    pub fn migrate08(&self) -> Result<()> {
+        self.tx.execute(
+            "CREATE TABLE IF NOT EXISTS recurring (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                template_id INTEGER NOT NULL,
+                items_json TEXT NOT NULL,
+                interval TEXT NOT NULL,
+                day INTEGER,
+                next_run TEXT NOT NULL,
+                end_date TEXT,
+                FOREIGN KEY (template_id)
+                    REFERENCES templates (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION
+            );", [])
+            .context("failed to create recurring table")?;
+
+        Ok(())
+    }
+    pub fn migrate09(&self) -> Result<()> {
+        self.tx.execute(
+            "ALTER TABLE invoices ADD COLUMN source_quote_id INTEGER
+                REFERENCES invoices (id) ON DELETE SET NULL ON UPDATE NO ACTION;", [])
+            .context("failed to add source_quote_id to invoices")?;
+        self.tx.execute(
+            "ALTER TABLE invoices ADD COLUMN quote_status TEXT;", [])
+            .context("failed to add quote_status to invoices")?;
+        self.tx.execute(
+            "ALTER TABLE invoices ADD COLUMN expires TEXT;", [])
+            .context("failed to add expires to invoices")?;
+        self.tx.execute(
+            "UPDATE invoices SET quote_status = 'Open' WHERE stage = 'Quote';", [])
+            .context("failed to set quote status on existing quotes")?;
+
+        Ok(())
+    }
+    pub fn migrate10(&self) -> Result<()> {
+        self.tx.execute(
+            "CREATE TABLE IF NOT EXISTS credit_notes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                number INTEGER UNIQUE,
+                invoice_id INTEGER NOT NULL,
+                date TEXT NOT NULL,
+                reason TEXT,
+                items_json TEXT NOT NULL,
+                FOREIGN KEY (invoice_id)
+                    REFERENCES invoices (id)
+                    ON DELETE NO ACTION
+                    ON UPDATE NO ACTION
+            );", [])
+            .context("failed to create credit_notes table")?;
+
+        Ok(())
+    }
+    pub fn migrate11(&self) -> Result<()> {
+        self.tx.execute(
+            "ALTER TABLE company ADD COLUMN number_pattern TEXT;", [])
+            .context("failed to add number_pattern to company")?;
+        self.tx.execute(
+            "ALTER TABLE invoices ADD COLUMN number TEXT;", [])
+            .context("failed to add number to invoices")?;
+        self.tx.execute(
+            "ALTER TABLE invoices ADD COLUMN number_seq INTEGER;", [])
+            .context("failed to add number_seq to invoices")?;
+
+        Ok(())
+    }
+    pub fn migrate12(&self) -> Result<()> {
+        self.tx.execute(
+            "ALTER TABLE templates ADD COLUMN layout TEXT;", [])
+            .context("failed to add layout to templates")?;
+
+        Ok(())
+    }
+    pub fn migrate13(&self) -> Result<()> {
+        self.tx.execute(
+            "CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );", [])
+            .context("failed to create settings table")?;
+
+        Ok(())
+    }
+    pub fn migrate14(&self) -> Result<()> {
+        self.tx.execute(
+            "ALTER TABLE company ADD COLUMN page_setup TEXT;", [])
+            .context("failed to add page_setup to company")?;
+        self.tx.execute(
+            "CREATE TABLE IF NOT EXISTS layout_pages (
+                layout TEXT PRIMARY KEY,
+                page_setup TEXT NOT NULL
+            );", [])
+            .context("failed to create layout_pages table")?;
+
+        Ok(())
+    }
+    pub fn migrate15(&self) -> Result<()> {
+        for table in ["company", "client"] {
+            self.tx.execute(
+                &format!("ALTER TABLE {} ADD COLUMN country TEXT;", table), [])
+                .with_context(|| format!("failed to add country to {}", table))?;
+            self.tx.execute(
+                &format!("ALTER TABLE {} ADD COLUMN tax_id TEXT;", table), [])
+                .with_context(|| format!("failed to add tax_id to {}", table))?;
+        }
+
+        Ok(())
+    }
+    pub fn migrate16(&self) -> Result<()> {
+        self.tx.execute(
+            "ALTER TABLE methods ADD COLUMN kind TEXT NOT NULL DEFAULT 'other';", [])
+            .context("failed to add kind to methods")?;
+        for column in ["iban", "bic", "beneficiary"] {
+            self.tx.execute(
+                &format!("ALTER TABLE methods ADD COLUMN {} TEXT;", column), [])
+                .with_context(|| format!("failed to add {} to methods", column))?;
+        }
+
+        Ok(())
+    }
+    pub fn migrate17(&self) -> Result<()> {
+        for column in ["reference_type", "street", "postcode", "town", "country"] {
+            self.tx.execute(
+                &format!("ALTER TABLE methods ADD COLUMN {} TEXT;", column), [])
+                .with_context(|| format!("failed to add {} to methods", column))?;
+        }
+
+        Ok(())
+    }
+    pub fn migrate18(&self) -> Result<()> {
+        for column in ["bank_name", "account_number", "routing_number", "paypal", "wallet", "network", "instructions"] {
+            self.tx.execute(
+                &format!("ALTER TABLE methods ADD COLUMN {} TEXT;", column), [])
+                .with_context(|| format!("failed to add {} to methods", column))?;
+        }
+
+        Ok(())
+    }
+    pub fn migrate19(&self) -> Result<()> {
+        self.tx.execute(
+            "ALTER TABLE client ADD COLUMN currency TEXT;", [])
+            .context("failed to add currency to client")?;
+        self.tx.execute(
+            "ALTER TABLE invoices ADD COLUMN currency TEXT NOT NULL DEFAULT 'USD';", [])
+            .context("failed to add currency to invoices")?;
+        // Until now every invoice was in the currency setting, with amounts
+        // kept in hundredths whatever its minor unit.
+        let code: Option<String> = self.tx
+            .query_row("SELECT value FROM settings WHERE key = ?", [Setting::Currency.to_string()], |row| row.get(0))
+            .optional()?;
+        let code = code.unwrap_or_else(|| Setting::Currency.default_value());
+        let currency = match Currency::new(&code, Locale::default()) {
+            Ok(currency) => currency,
+            Err(e) => {
+                eprintln!("{}, existing invoices are taken to be in USD", e);
+                Currency::new("USD", Locale::default()).map_err(anyhow::Error::msg)?
+            }
+        };
+        self.tx.execute(
+            "UPDATE invoices SET currency = ?;", [currency.code()])
+            .context("failed to set the currency of invoices")?;
+        let shift = currency.minor_units() as i32 - 2;
+        let amount = match shift {
+            0 => "amount".to_string(),
+            1.. => format!("amount * {}", 10_i64.pow(shift as u32)),
+            _ => format!("CAST(ROUND(amount / {}.0) AS INTEGER)", 10_i64.pow(shift.unsigned_abs())),
+        };
+        self.tx.execute(
+            &format!("UPDATE payments SET amount = {};", amount), [])
+            .context("failed to rescale payment amounts")?;
+        self.tx.execute(
+            &format!("UPDATE items SET rate = rate * {};", 10_i64.pow(RATE_PLACES - 2)), [])
+            .context("failed to rescale item rates")?;
+
+        Ok(())
+    }
+    pub fn migrate20(&self) -> Result<()> {
+        self.tx.execute(
+            &format!("UPDATE taxes SET rate = rate * {};", 10_i64.pow(RATE_PLACES - 2)), [])
+            .context("failed to rescale tax rates")?;
+        // Lines kept tax ids, or nothing for the template taxes, and were
+        // resolved against the live tables. Pin a copy of each tax instead.
+        let snapshot = |tax_id: i64| -> Result<serde_json::Value> {
+            let tax: Option<(String, i64)> = self.tx
+                .query_row(
+                    "SELECT name, rate FROM taxes WHERE id = ?",
+                    [tax_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            let (name, rate) = tax
+                .unwrap_or_else(|| (format!("Deleted tax #{}", tax_id), 0));
+            Ok(serde_json::json!({
+                "id": tax_id,
+                "name": name,
+                "rate": i64_to_decimal!(rate, RATE_PLACES).to_string(),
+            }))
+        };
+        let pin = |items_json: &str, default_taxes: Option<&str>| -> Result<String> {
+            let mut lines: Vec<serde_json::Value> = serde_json::from_str(items_json)?;
+            for line in lines.iter_mut() {
+                let Some(obj) = line.as_object_mut() else { continue };
+                let tax_ids: Vec<i64> = match (obj.get("taxes"), default_taxes) {
+                    (Some(taxes), _) => serde_json::from_value(taxes.clone())?,
+                    (None, Some(default_taxes)) => serde_json::from_str(default_taxes)?,
+                    (None, None) => continue,
+                };
+                let taxes = tax_ids.into_iter().map(&snapshot).collect::<Result<Vec<_>>>()?;
+                obj.insert("taxes".to_string(), taxes.into());
+            }
+            Ok(serde_json::to_string(&lines)?)
+        };
+
+        let documents = [
+            ("invoices", "SELECT invoices.id, invoices.items_json, taxes_json FROM invoices
+                          JOIN templates ON templates.id = invoices.template_id"),
+            ("credit_notes", "SELECT credit_notes.id, credit_notes.items_json, taxes_json FROM credit_notes
+                              JOIN invoices ON invoices.id = credit_notes.invoice_id
+                              JOIN templates ON templates.id = invoices.template_id"),
+        ];
+        for (table, query) in documents {
+            let mut stmt = self.tx.prepare(query)?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?
+                .collect::<Result<Vec<(i64, String, String)>, rusqlite::Error>>()
+                .with_context(|| format!("failed to read {} items", table))?;
+            for (id, items_json, taxes_json) in rows {
+                let items_json = pin(&items_json, Some(&taxes_json))
+                    .with_context(|| format!("failed to parse items for {} {}", table, id))?;
+                self.tx.execute(
+                    &format!("UPDATE {} SET items_json = ? WHERE id = ?", table),
+                    rusqlite::params![items_json, id])
+                    .with_context(|| format!("failed to snapshot taxes for {} {}", table, id))?;
+            }
+        }
+        // Schedules still follow the template when a line has no taxes.
+        let mut stmt = self.tx.prepare("SELECT id, items_json FROM recurring")?;
+        let schedules = stmt
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<(i64, String)>, rusqlite::Error>>()
+            .context("failed to read recurring items")?;
+        for (id, items_json) in schedules {
+            let items_json = pin(&items_json, None)
+                .with_context(|| format!("failed to parse items for schedule {}", id))?;
+            self.tx.execute(
+                "UPDATE recurring SET items_json = ? WHERE id = ?",
+                rusqlite::params![items_json, id])
+                .with_context(|| format!("failed to snapshot taxes for schedule {}", id))?;
+        }
+
+        Ok(())
+    }
+    pub fn migrate21(&self) -> Result<()> {
+        // Numbers are unique per company. The company is kept on the invoice
+        // so the index holds even if its template later changes company.
+        self.tx.execute(
+            "ALTER TABLE invoices ADD COLUMN company_id INTEGER;", [])
+            .context("failed to add company_id to invoices")?;
+        self.tx.execute(
+            "UPDATE invoices SET company_id = (SELECT company_id FROM templates WHERE templates.id = invoices.template_id);", [])
+            .context("failed to set the company of invoices")?;
+        self.tx.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS invoices_company_number ON invoices (company_id, number);", [])
+            .context("failed to create invoice number index")?;
+
+        Ok(())
+    }
+    /// Fails when an invoice number is used twice within a company, which
+    /// the index added by `migrate21` would reject. Run before any step so
+    /// the database is left as it was and can be fixed by hand.
+    pub fn check_invoice_numbers(&self) -> Result<()> {
+        let has_numbers: bool = self.tx.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('invoices') WHERE name = 'number'",
+            [],
+            |row| row.get(0))?;
+        if !has_numbers {
+            return Ok(());
+        }
+        let mut stmt = self.tx.prepare(
+            "SELECT invoices.number, GROUP_CONCAT(invoices.id, ', ') FROM invoices
+             JOIN templates ON templates.id = invoices.template_id
+             WHERE invoices.number IS NOT NULL
+             GROUP BY templates.company_id, invoices.number HAVING COUNT(*) > 1")?;
+        let duplicates = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<(String, String)>, rusqlite::Error>>()
+            .context("failed to check invoice numbers")?;
+        let Some((number, ids)) = duplicates.first() else {
+            return Ok(());
+        };
+        let list = duplicates
+            .iter()
+            .map(|(number, ids)| format!("{} (invoices {})", number, ids))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let id = ids.rsplit(", ").next().unwrap_or(ids);
+        Err(anyhow::anyhow!(
+            "invoice numbers are used more than once within a company: {}. \
+             Nothing was upgraded. Give all but one of each its own number, for example with\n  \
+             sqlite3 {} \"UPDATE invoices SET number = '{}-2' WHERE id = {}\"\n\
+             and run the command again",
+            list,
+            self.tx.path().unwrap_or("<database>"),
+            number,
+            id,
+        ))
+    }
     pub fn iter_migration(&self, version: i32) -> Result<()> {
         self.tx.execute("CREATE TABLE IF NOT EXISTS migrations (
             version INTEGER PRIMARY KEY);", [])
@@ -1,8 +1,11 @@
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
+use chrono::Local;
 use rusqlite::{Connection, Transaction, OptionalExtension};
 
+use crate::models::invoice::Invoice;
+
 pub mod cached;
 pub mod getters;
 pub mod prepare;
@@ -14,6 +17,10 @@ mod migrate;
 #[derive(Debug)]
 pub struct InvoiceDB {
     connection: Connection,
+    /// Invoices moved to Past Due by the check run in `open`.
+    pub past_due: Vec<Invoice>,
+    /// Quotes moved to Expired by the check run in `open`.
+    pub expired_quotes: Vec<Invoice>,
 }
 
 pub struct InvoiceTx<'conn> {
@@ -42,7 +49,7 @@ impl InvoiceDB {
             .pragma_update(None, "foreign_keys", true)
             .context("failed to enable foreign keys pragma")?;
 
-        let mut db = InvoiceDB { connection };
+        let mut db = InvoiceDB { connection, past_due: Vec::new(), expired_quotes: Vec::new() };
 
         if !existing_db {
             let initdb = db.transaction()?;
@@ -55,13 +62,13 @@ impl InvoiceDB {
             let current_version: Result<Option<i32>, rusqlite::Error> = db
                 .connection
                 .query_row(
-                    "SELECT version FROM migrations LIMIT 1",
+                    "SELECT MAX(version) FROM migrations",
                     [],
                     |row| row.get(0)
-                ).optional();
+                ).optional().map(Option::flatten);
             match current_version {
-                Ok(Some(vers)) if vers < version => { db.run_migrations(version)?; }
-                Ok(None) => { db.run_migrations(version)?; }
+                Ok(Some(vers)) if vers < version => { db.run_migrations(vers, version)?; }
+                Ok(None) => { db.run_migrations(0, version)?; }
                 Ok(Some(_)) => {}
                 Err(err) => {
                     return Err(anyhow::Error::new(err).context("failed to query migrations table"));
@@ -69,16 +76,166 @@ impl InvoiceDB {
             }
         }
 
+        db.past_due = db
+            .refresh_past_due(Local::now().date_naive())
+            .context("failed to refresh past due invoices")?;
+        db.expired_quotes = db
+            .expire_quotes(Local::now().date_naive())
+            .context("failed to expire quotes")?;
+
         Ok(db)
     }
-    pub fn run_migrations(&mut self, version: i32) -> Result<()> {
-        let tx = self.transaction()?;
-        tx.migrate01()?;
-        tx.commit()?;
+    /// Runs each step newer than `current` in its own transaction, recording
+    /// its version in the same transaction so a committed step never runs twice.
+    pub fn run_migrations(&mut self, current: i32, version: i32) -> Result<()> {
+        if current < 21 {
+            self.transaction()?.check_invoice_numbers()?;
+        }
 
-        let tx = self.transaction()?;
-        tx.migrate02()?;
-        tx.commit()?;
+        if current < 1 {
+            let tx = self.transaction()?;
+            tx.migrate01()?;
+            tx.iter_migration(1)?;
+            tx.commit()?;
+        }
+
+        if current < 2 {
+            let tx = self.transaction()?;
+            tx.migrate02()?;
+            tx.iter_migration(2)?;
+            tx.commit()?;
+        }
+
+        if current < 3 {
+            let tx = self.transaction()?;
+            tx.migrate03()?;
+            tx.iter_migration(3)?;
+            tx.commit()?;
+        }
+
+        if current < 4 {
+            let tx = self.transaction()?;
+            tx.migrate04()?;
+            tx.iter_migration(4)?;
+            tx.commit()?;
+        }
+
+        if current < 5 {
+            let tx = self.transaction()?;
+            tx.migrate05()?;
+            tx.iter_migration(5)?;
+            tx.commit()?;
+        }
+
+        if current < 6 {
+            let tx = self.transaction()?;
+            tx.migrate06()?;
+            tx.iter_migration(6)?;
+            tx.commit()?;
+        }
+
+        if current < 7 {
+            let tx = self.transaction()?;
+            tx.migrate07()?;
+            tx.iter_migration(7)?;
+            tx.commit()?;
+        }
+
+        if current < 8 {
+            let tx = self.transaction()?;
+            tx.migrate08()?;
+            tx.iter_migration(8)?;
+            tx.commit()?;
+        }
+
+        if current < 9 {
+            let tx = self.transaction()?;
+            tx.migrate09()?;
+            tx.iter_migration(9)?;
+            tx.commit()?;
+        }
+
+        if current < 10 {
+            let tx = self.transaction()?;
+            tx.migrate10()?;
+            tx.iter_migration(10)?;
+            tx.commit()?;
+        }
+
+        if current < 11 {
+            let tx = self.transaction()?;
+            tx.migrate11()?;
+            tx.iter_migration(11)?;
+            tx.commit()?;
+        }
+
+        if current < 12 {
+            let tx = self.transaction()?;
+            tx.migrate12()?;
+            tx.iter_migration(12)?;
+            tx.commit()?;
+        }
+
+        if current < 13 {
+            let tx = self.transaction()?;
+            tx.migrate13()?;
+            tx.iter_migration(13)?;
+            tx.commit()?;
+        }
+
+        if current < 14 {
+            let tx = self.transaction()?;
+            tx.migrate14()?;
+            tx.iter_migration(14)?;
+            tx.commit()?;
+        }
+
+        if current < 15 {
+            let tx = self.transaction()?;
+            tx.migrate15()?;
+            tx.iter_migration(15)?;
+            tx.commit()?;
+        }
+
+        if current < 16 {
+            let tx = self.transaction()?;
+            tx.migrate16()?;
+            tx.iter_migration(16)?;
+            tx.commit()?;
+        }
+
+        if current < 17 {
+            let tx = self.transaction()?;
+            tx.migrate17()?;
+            tx.iter_migration(17)?;
+            tx.commit()?;
+        }
+
+        if current < 18 {
+            let tx = self.transaction()?;
+            tx.migrate18()?;
+            tx.iter_migration(18)?;
+            tx.commit()?;
+        }
+
+        if current < 19 {
+            let tx = self.transaction()?;
+            tx.migrate19()?;
+            tx.iter_migration(19)?;
+            tx.commit()?;
+        }
+        if current < 20 {
+            let tx = self.transaction()?;
+            tx.migrate20()?;
+            tx.iter_migration(20)?;
+            tx.commit()?;
+        }
+        if current < 21 {
+            let tx = self.transaction()?;
+            tx.migrate21()?;
+            tx.iter_migration(21)?;
+            tx.commit()?;
+        }
 
         let tx = self.transaction()?;
         tx.iter_migration(version)?;
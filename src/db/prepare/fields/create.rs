@@ -1,5 +1,6 @@
 use crate::db::prepare::PrepFields;
 use crate::cli::create::*;
+use crate::models::methods::MethodKind;
 
 impl PrepFields for CreateCompany {
     fn fields(&self) -> Vec<std::string::String> {
@@ -8,6 +9,12 @@ impl PrepFields for CreateCompany {
         if self.logo.is_some() {
             fnames.push("logo".to_string());
         }
+        if self.number_pattern.is_some() {
+            fnames.push("number_pattern".to_string());
+        }
+        if self.tax_id.is_some() {
+            fnames.push("tax_id".to_string());
+        }
         if let Some(contact) = &self.contact {
             fnames.extend(contact.fields());
         }
@@ -19,6 +26,12 @@ impl PrepFields for CreateClient {
     fn fields(&self) -> Vec<std::string::String> {
         let mut fnames = Vec::new();
         fnames.push("name".to_string());
+        if self.tax_id.is_some() {
+            fnames.push("tax_id".to_string());
+        }
+        if self.currency.is_some() {
+            fnames.push("currency".to_string());
+        }
         if let Some(contact) = &self.contact {
             fnames.extend(contact.fields());
         }
@@ -28,10 +41,10 @@ impl PrepFields for CreateClient {
 
 impl PrepFields for CreateTerms {
     fn fields(&self) -> Vec<std::string::String> {
-        let mut fnames = Vec::new();
-        fnames.push("name".to_string());
-        fnames.push("due".to_string());
-        fnames
+        vec![
+            "name".to_string(),
+            "due".to_string(),
+        ]
     }
 }
 
@@ -45,45 +58,166 @@ impl PrepFields for CreateMethod {
         if self.qr.is_some() {
             fnames.push("qr".to_string());
         }
+        if self.kind != MethodKind::Other {
+            fnames.push("kind".to_string());
+        }
+        if self.iban.is_some() {
+            fnames.push("iban".to_string());
+        }
+        if self.bic.is_some() {
+            fnames.push("bic".to_string());
+        }
+        if self.beneficiary.is_some() {
+            fnames.push("beneficiary".to_string());
+        }
+        if self.reference_type.is_some() {
+            fnames.push("reference_type".to_string());
+        }
+        if self.street.is_some() {
+            fnames.push("street".to_string());
+        }
+        if self.postcode.is_some() {
+            fnames.push("postcode".to_string());
+        }
+        if self.town.is_some() {
+            fnames.push("town".to_string());
+        }
+        if self.country.is_some() {
+            fnames.push("country".to_string());
+        }
+        if self.bank_name.is_some() {
+            fnames.push("bank_name".to_string());
+        }
+        if self.account_number.is_some() {
+            fnames.push("account_number".to_string());
+        }
+        if self.routing_number.is_some() {
+            fnames.push("routing_number".to_string());
+        }
+        if self.paypal.is_some() {
+            fnames.push("paypal".to_string());
+        }
+        if self.wallet.is_some() {
+            fnames.push("wallet".to_string());
+        }
+        if self.network.is_some() {
+            fnames.push("network".to_string());
+        }
+        if self.instructions.is_some() {
+            fnames.push("instructions".to_string());
+        }
         fnames
     }
 }
 
 impl PrepFields for CreateItem {
     fn fields(&self) -> Vec<std::string::String> {
-        let mut fnames = Vec::new();
-        fnames.push("name".to_string());
-        fnames.push("rate".to_string());
+        let mut fnames = vec![
+            "name".to_string(),
+            "rate".to_string(),
+        ];
+        if self.unit.is_some() {
+            fnames.push("unit".to_string());
+        }
+        fnames
+    }
+}
+
+impl PrepFields for CreateTax {
+    fn fields(&self) -> Vec<std::string::String> {
+        vec![
+            "name".to_string(),
+            "rate".to_string(),
+        ]
+    }
+}
+
+impl PrepFields for CreatePayment {
+    fn fields(&self) -> Vec<std::string::String> {
+        let mut fnames = vec![
+            "invoice_id".to_string(),
+            "amount".to_string(),
+            "date".to_string(),
+        ];
+        if self.method.is_some() {
+            fnames.push("method_id".to_string());
+        }
+        if self.reference.is_some() {
+            fnames.push("reference".to_string());
+        }
+        fnames
+    }
+}
+
+impl PrepFields for CreateCreditNote {
+    fn fields(&self) -> Vec<std::string::String> {
+        let mut fnames = vec![
+            "invoice_id".to_string(),
+            "date".to_string(),
+            "items_json".to_string(),
+        ];
+        if self.reason.is_some() {
+            fnames.push("reason".to_string());
+        }
+        fnames
+    }
+}
+
+impl PrepFields for CreateRecurring {
+    fn fields(&self) -> Vec<std::string::String> {
+        let mut fnames = vec![
+            "name".to_string(),
+            "template_id".to_string(),
+            "items_json".to_string(),
+            "interval".to_string(),
+            "next_run".to_string(),
+        ];
+        if self.day.is_some() {
+            fnames.push("day".to_string());
+        }
+        if self.end_date.is_some() {
+            fnames.push("end_date".to_string());
+        }
         fnames
     }
 }
 
 impl PrepFields for CreateTemplate {
     fn fields(&self) -> Vec<std::string::String> {
-        let mut fnames = Vec::new();
-        fnames.push("name".to_string());
-        fnames.push("company_id".to_string());
-        fnames.push("client_id".to_string());
-        fnames.push("terms_id".to_string());
-        fnames.push("methods_json".to_string());
+        let mut fnames = vec![
+            "name".to_string(),
+            "company_id".to_string(),
+            "client_id".to_string(),
+            "terms_id".to_string(),
+            "methods_json".to_string(),
+            "taxes_json".to_string(),
+        ];
+        if self.layout.is_some() {
+            fnames.push("layout".to_string());
+        }
         fnames
     }
 }
 
 impl PrepFields for CreateInvoice {
     fn fields(&self) -> Vec<std::string::String> {
-        let mut fnames = Vec::new();
-        fnames.push("template_id".to_string());
-        fnames.push("date".to_string());
-        fnames.push("show_methods".to_string());
-        fnames.push("show_notes".to_string());
-        fnames.push("stage".to_string());
-        fnames.push("status".to_string());
-        fnames.push("status_date".to_string());
-        fnames.push("status_check".to_string());
-        fnames.push("notes".to_string());
-        fnames.push("items_json".to_string());
-        fnames
+        vec![
+            "template_id".to_string(),
+            "date".to_string(),
+            "show_methods".to_string(),
+            "show_notes".to_string(),
+            "stage".to_string(),
+            "status".to_string(),
+            "status_date".to_string(),
+            "status_check".to_string(),
+            "notes".to_string(),
+            "items_json".to_string(),
+            "discount_json".to_string(),
+            "source_quote_id".to_string(),
+            "quote_status".to_string(),
+            "expires".to_string(),
+            "currency".to_string(),
+        ]
     }
 }
 
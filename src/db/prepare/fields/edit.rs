@@ -5,15 +5,15 @@ use crate::models::config::Config;
 
 impl PrepFields for Config {
     fn fields(&self) -> Vec<String> {
-        let mut fnames = Vec::new();
-        fnames.push("id".to_string());
-        fnames.push("smtp_server".to_string());
-        fnames.push("port".to_string());
-        fnames.push("tls".to_string());
-        fnames.push("username".to_string());
-        fnames.push("password".to_string());
-        fnames.push("fromname".to_string());
-        fnames
+        vec![
+            "id".to_string(),
+            "smtp_server".to_string(),
+            "port".to_string(),
+            "tls".to_string(),
+            "username".to_string(),
+            "password".to_string(),
+            "fromname".to_string(),
+        ]
     }
 }
 
@@ -27,6 +27,12 @@ impl PrepFields for EditCompany {
         if self.logo.is_some() {
             fnames.push("logo".to_string());
         }
+        if self.number_pattern.is_some() {
+            fnames.push("number_pattern".to_string());
+        }
+        if self.tax_id.is_some() {
+            fnames.push("tax_id".to_string());
+        }
         fnames.extend(self.contact.fields());
         fnames
     }
@@ -39,6 +45,12 @@ impl PrepFields for EditClient {
         if self.name.is_some() {
             fnames.push("name".to_string());
         }
+        if self.tax_id.is_some() {
+            fnames.push("tax_id".to_string());
+        }
+        if self.currency.is_some() {
+            fnames.push("currency".to_string());
+        }
         fnames.extend(self.contact.fields());
         fnames
     }
@@ -71,11 +83,76 @@ impl PrepFields for EditMethod {
         if self.qr.is_some() {
             fnames.push("qr".to_string());
         }
+        if self.kind.is_some() {
+            fnames.push("kind".to_string());
+        }
+        if self.iban.is_some() {
+            fnames.push("iban".to_string());
+        }
+        if self.bic.is_some() {
+            fnames.push("bic".to_string());
+        }
+        if self.beneficiary.is_some() {
+            fnames.push("beneficiary".to_string());
+        }
+        if self.reference_type.is_some() {
+            fnames.push("reference_type".to_string());
+        }
+        if self.street.is_some() {
+            fnames.push("street".to_string());
+        }
+        if self.postcode.is_some() {
+            fnames.push("postcode".to_string());
+        }
+        if self.town.is_some() {
+            fnames.push("town".to_string());
+        }
+        if self.country.is_some() {
+            fnames.push("country".to_string());
+        }
+        if self.bank_name.is_some() {
+            fnames.push("bank_name".to_string());
+        }
+        if self.account_number.is_some() {
+            fnames.push("account_number".to_string());
+        }
+        if self.routing_number.is_some() {
+            fnames.push("routing_number".to_string());
+        }
+        if self.paypal.is_some() {
+            fnames.push("paypal".to_string());
+        }
+        if self.wallet.is_some() {
+            fnames.push("wallet".to_string());
+        }
+        if self.network.is_some() {
+            fnames.push("network".to_string());
+        }
+        if self.instructions.is_some() {
+            fnames.push("instructions".to_string());
+        }
         fnames
     }
 }
 
 impl PrepFields for EditItem {
+    fn fields(&self) -> Vec<String> {
+        let mut fnames = Vec::new();
+        fnames.push(self.id.to_string());
+        if self.name.is_some() {
+            fnames.push("name".to_string());
+        }
+        if self.rate.is_some() {
+            fnames.push("rate".to_string());
+        }
+        if self.unit.is_some() {
+            fnames.push("unit".to_string());
+        }
+        fnames
+    }
+}
+
+impl PrepFields for EditTax {
     fn fields(&self) -> Vec<String> {
         let mut fnames = Vec::new();
         fnames.push(self.id.to_string());
@@ -108,6 +185,12 @@ impl PrepFields for EditTemplate {
         if self.methods.is_some() {
             fnames.push("methods_json".to_string());
         }
+        if self.taxes.is_some() {
+            fnames.push("taxes_json".to_string());
+        }
+        if self.layout.is_some() {
+            fnames.push("layout".to_string());
+        }
         fnames
     }
 }
@@ -132,7 +215,7 @@ impl PrepFields for EditInvoice {
                 fnames.push("status_date".to_string());
                 fnames.push("status_check".to_string());
             }
-            if let Some(PaidStatus::Failed { .. }) | Some(PaidStatus::Refunded { .. }) = &self.status{
+            if let Some(PaidStatus::PastDue { .. }) | Some(PaidStatus::Failed { .. }) | Some(PaidStatus::Refunded { .. }) = &self.status{
                 fnames.push("status_date".to_string());
             }
         }
@@ -140,6 +223,15 @@ impl PrepFields for EditInvoice {
         if self.notes.is_some() {
             fnames.push("notes".to_string());
         }
+        if self.discount.is_some() {
+            fnames.push("discount_json".to_string());
+        }
+        if self.quote_status.is_some() {
+            fnames.push("quote_status".to_string());
+        }
+        if self.expires.is_some() {
+            fnames.push("expires".to_string());
+        }
         fnames
     }
 }
@@ -37,7 +37,7 @@ pub trait PrepCreateUpdate: PrepFields + TableName + PrepValues {
         );
         CachedStmt {
             table: self.table_name(),
-            query: query,
+            query,
             params: self.values(),
         }
     }
@@ -63,7 +63,7 @@ pub trait PrepCreate: PrepFields + TableName + PrepValues {
         );
         CachedStmt {
             table: self.table_name(),
-            query: query,
+            query,
             params: self.values(),
         }
     }
@@ -96,7 +96,7 @@ pub trait PrepUpdate: PrepFields + TableName + PrepValues {
         );
         CachedStmt {
             table: self.table_name(),
-            query: query,
+            query,
             params: self.values(),
         }
     }
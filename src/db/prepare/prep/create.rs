@@ -8,5 +8,9 @@ impl PrepCreate for CreateClient {}
 impl PrepCreate for CreateTerms {}
 impl PrepCreate for CreateMethod {}
 impl PrepCreate for CreateItem {}
+impl PrepCreate for CreateTax {}
+impl PrepCreate for CreatePayment {}
+impl PrepCreate for CreateCreditNote {}
+impl PrepCreate for CreateRecurring {}
 impl PrepCreate for CreateTemplate {}
 impl PrepCreate for CreateInvoice {}
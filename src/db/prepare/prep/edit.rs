@@ -6,5 +6,6 @@ impl PrepUpdate for EditClient {}
 impl PrepUpdate for EditTerms {}
 impl PrepUpdate for EditMethod {}
 impl PrepUpdate for EditItem {}
+impl PrepUpdate for EditTax {}
 impl PrepUpdate for EditTemplate {}
 impl PrepUpdate for EditInvoice {}
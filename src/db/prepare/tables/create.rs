@@ -30,6 +30,30 @@ impl TableName for CreateItem {
     }
 }
 
+impl TableName for CreateTax {
+    fn table_name(&self) -> String {
+        "taxes".to_string()
+    }
+}
+
+impl TableName for CreatePayment {
+    fn table_name(&self) -> String {
+        "payments".to_string()
+    }
+}
+
+impl TableName for CreateCreditNote {
+    fn table_name(&self) -> String {
+        "credit_notes".to_string()
+    }
+}
+
+impl TableName for CreateRecurring {
+    fn table_name(&self) -> String {
+        "recurring".to_string()
+    }
+}
+
 impl TableName for CreateTemplate {
     fn table_name(&self) -> String {
         "templates".to_string()
@@ -38,6 +38,12 @@ impl TableName for EditItem {
     }
 }
 
+impl TableName for EditTax {
+    fn table_name(&self) -> String {
+        "taxes".to_string()
+    }
+}
+
 impl TableName for EditTemplate {
     fn table_name(&self) -> String {
         "templates".to_string()
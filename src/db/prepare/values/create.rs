@@ -3,7 +3,9 @@ use serde_json;
 
 use crate::db::prepare::PrepValues;
 use crate::cli::create::*;
-use crate::models::invoice::{InvoiceStage, PaidStatus};
+use crate::models::methods::MethodKind;
+use crate::models::invoice::{InvoiceStage, PaidStatus, QuoteStatus};
+use crate::models::items::RATE_PLACES;
 use crate::validators::{ValidSize, ValidImage};
 
 use invoice_cli::decimal_to_i64;
@@ -22,6 +24,12 @@ impl PrepValues for CreateCompany {
                 eprintln!("Invalid image file type.");
             }
         }
+        if self.number_pattern.is_some() {
+            values.push(self.number_pattern.clone().into());
+        }
+        if self.tax_id.is_some() {
+            values.push(self.tax_id.clone().into());
+        }
         if let Some(contact) = &self.contact {
             values.extend(contact.values());
         }
@@ -33,6 +41,12 @@ impl PrepValues for CreateClient {
     fn values(&self) -> Vec<Value> {
         let mut values: Vec<Value> = Vec::new();
         values.push(self.name.clone().into());
+        if self.tax_id.is_some() {
+            values.push(self.tax_id.clone().into());
+        }
+        if self.currency.is_some() {
+            values.push(self.currency.clone().into());
+        }
         if let Some(contact) = &self.contact {
             values.extend(contact.values());
         }
@@ -42,10 +56,10 @@ impl PrepValues for CreateClient {
 
 impl PrepValues for CreateTerms {
     fn values(&self) -> Vec<Value> {
-        let mut values: Vec<Value> = Vec::new();
-        values.push(self.name.clone().into());
-        values.push(self.due.into());
-        values
+        vec![
+            self.name.clone().into(),
+            self.due.into(),
+        ]
     }
 }
 
@@ -67,30 +81,150 @@ impl PrepValues for CreateMethod {
                 eprintln!("Invalid image file type.");
             }
         }
+        if self.kind != MethodKind::Other {
+            values.push(self.kind.to_string().into());
+        }
+        if self.iban.is_some() {
+            values.push(self.iban.clone().into());
+        }
+        if self.bic.is_some() {
+            values.push(self.bic.clone().into());
+        }
+        if self.beneficiary.is_some() {
+            values.push(self.beneficiary.clone().into());
+        }
+        if let Some(reference_type) = self.reference_type {
+            values.push(reference_type.to_string().into());
+        }
+        if self.street.is_some() {
+            values.push(self.street.clone().into());
+        }
+        if self.postcode.is_some() {
+            values.push(self.postcode.clone().into());
+        }
+        if self.town.is_some() {
+            values.push(self.town.clone().into());
+        }
+        if self.country.is_some() {
+            values.push(self.country.clone().into());
+        }
+        if self.bank_name.is_some() {
+            values.push(self.bank_name.clone().into());
+        }
+        if self.account_number.is_some() {
+            values.push(self.account_number.clone().into());
+        }
+        if self.routing_number.is_some() {
+            values.push(self.routing_number.clone().into());
+        }
+        if self.paypal.is_some() {
+            values.push(self.paypal.clone().into());
+        }
+        if self.wallet.is_some() {
+            values.push(self.wallet.clone().into());
+        }
+        if self.network.is_some() {
+            values.push(self.network.clone().into());
+        }
+        if self.instructions.is_some() {
+            values.push(self.instructions.clone().into());
+        }
         values
     }
 }
 
 impl PrepValues for CreateItem {
     fn values(&self) -> Vec<Value> {
-        let mut values: Vec<Value> = Vec::new();
-        values.push(self.name.clone().into());
-        values.push(decimal_to_i64!(self.rate).into());
+        let mut values: Vec<Value> = vec![
+            self.name.clone().into(),
+            decimal_to_i64!(self.rate, RATE_PLACES).into(),
+        ];
+        if self.unit.is_some() {
+            values.push(self.unit.clone().into());
+        }
+        values
+    }
+}
+
+impl PrepValues for CreateTax {
+    fn values(&self) -> Vec<Value> {
+        vec![
+            self.name.clone().into(),
+            decimal_to_i64!(self.rate, RATE_PLACES).into(),
+        ]
+    }
+}
+
+impl PrepValues for CreatePayment {
+    fn values(&self) -> Vec<Value> {
+        let mut values: Vec<Value> = vec![
+            self.invoice.into(),
+            decimal_to_i64!(self.amount, self.currency.minor_units()).into(),
+            self.date.format("%Y%m%d").to_string().into(),
+        ];
+        if self.method.is_some() {
+            values.push(self.method.into());
+        }
+        if self.reference.is_some() {
+            values.push(self.reference.clone().into());
+        }
+        values
+    }
+}
+
+impl PrepValues for CreateCreditNote {
+    fn values(&self) -> Vec<Value> {
+        let items_json = serde_json::to_string(&self.items).expect("Failed to serialize to JSON");
+        let mut values: Vec<Value> = vec![
+            self.invoice.into(),
+            self.date.format("%Y%m%d").to_string().into(),
+            items_json.into(),
+        ];
+        if self.reason.is_some() {
+            values.push(self.reason.clone().into());
+        }
+        values
+    }
+}
+
+impl PrepValues for CreateRecurring {
+    fn values(&self) -> Vec<Value> {
+        let items_json = serde_json::to_string(&self.items).expect("Failed to serialize to JSON");
+        let mut values: Vec<Value> = vec![
+            self.name.clone().into(),
+            self.template.into(),
+            items_json.into(),
+            self.interval.to_string().into(),
+            self.next_run.format("%Y%m%d").to_string().into(),
+        ];
+        if self.day.is_some() {
+            values.push(self.day.into());
+        }
+        if let Some(end_date) = &self.end_date {
+            values.push(end_date.format("%Y%m%d").to_string().into());
+        }
         values
     }
 }
 
 impl PrepValues for CreateTemplate {
     fn values(&self) -> Vec<Value> {
-        let mut values: Vec<Value> = Vec::new();
-        values.push(self.name.clone().into());
-        values.push(self.company.into());
-        values.push(self.client.into());
-        values.push(self.terms.into());
         let methods_json =
             serde_json::to_string(&self.methods).expect("Failed to serialize to JSON");
         println!("{:?}", methods_json);
-        values.push(methods_json.into());
+        let taxes_json =
+            serde_json::to_string(&self.taxes).expect("Failed to serialize to JSON");
+        let mut values: Vec<Value> = vec![
+            self.name.clone().into(),
+            self.company.into(),
+            self.client.into(),
+            self.terms.into(),
+            methods_json.into(),
+            taxes_json.into(),
+        ];
+        if self.layout.is_some() {
+            values.push(self.layout.clone().into());
+        }
         values
     }
 }
@@ -112,7 +246,7 @@ impl PrepValues for CreateInvoice {
 
         let status_str = match &self.attributes.status {
             PaidStatus::Waiting => "Waiting".to_string(),
-            PaidStatus::PastDue => "Past Due".to_string(),
+            PaidStatus::PastDue { .. } => "Past Due".to_string(),
             PaidStatus::Paid { .. } => "Paid".to_string(),
             PaidStatus::Failed { .. } => "Failed".to_string(),
             PaidStatus::Refunded { .. } => "Refunded".to_string(),
@@ -121,6 +255,7 @@ impl PrepValues for CreateInvoice {
 
         let status_date = match &self.attributes.status {
             PaidStatus::Paid { date, .. }
+            | PaidStatus::PastDue { date }
             | PaidStatus::Failed { date }
             | PaidStatus::Refunded { date } => Some(date.clone()),
             _ => None,
@@ -136,6 +271,18 @@ impl PrepValues for CreateInvoice {
         values.push(self.notes.clone().into());
         let items_json = serde_json::to_string(&self.items).expect("Failed to serialize to JSON");
         values.push(items_json.into());
+        let discount_json = self.discount
+            .as_ref()
+            .map(|discount| serde_json::to_string(discount).expect("Failed to serialize to JSON"));
+        values.push(discount_json.into());
+        values.push(self.source_quote.into());
+        let quote_status = match self.attributes.stage {
+            InvoiceStage::Quote => Some(QuoteStatus::Open.to_string()),
+            InvoiceStage::Invoice => None,
+        };
+        values.push(quote_status.into());
+        values.push(self.expires.map(|date| date.format("%Y%m%d").to_string()).into());
+        values.push(self.currency.code().to_string().into());
         values
     }
 }
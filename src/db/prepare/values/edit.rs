@@ -4,21 +4,22 @@ use crate::db::prepare::PrepValues;
 use crate::cli::edit::*;
 use crate::models::invoice::{InvoiceStage, PaidStatus};
 use crate::models::config::Config;
+use crate::models::items::RATE_PLACES;
 use crate::validators::{ValidSize, ValidImage};
 
 use invoice_cli::decimal_to_i64;
 
 impl PrepValues for Config {
     fn values(&self) -> Vec<Value> {
-        let mut values: Vec<Value> = Vec::new();
-        values.push(0.into());
-        values.push(self.smtp_server.clone().into());
-        values.push(self.port.into());
-        values.push(self.tls.into());
-        values.push(self.username.clone().into());
-        values.push(self.password.clone().into());
-        values.push(self.fromname.clone().into());
-        values
+        vec![
+            0.into(),
+            self.smtp_server.clone().into(),
+            self.port.into(),
+            self.tls.into(),
+            self.username.clone().into(),
+            self.password.clone().into(),
+            self.fromname.clone().into(),
+        ]
     }
 }
 
@@ -29,8 +30,8 @@ impl PrepValues for EditCompany {
             values.push(self.name.clone().into());
         }
         if let Some(logo) = &self.logo {
-            if self.is_valid_image(&logo) {
-                match self.read_image(&logo) {
+            if self.is_valid_image(logo) {
+                match self.read_image(logo) {
                     Ok(data) => values.push(Value::Blob(data)),
                     Err(e) => eprintln!("Error reading image file: {}", e),
                 }
@@ -38,6 +39,12 @@ impl PrepValues for EditCompany {
                 eprintln!("Invalid image file type.");
             }
         }
+        if self.number_pattern.is_some() {
+            values.push(self.number_pattern.clone().into());
+        }
+        if self.tax_id.is_some() {
+            values.push(self.tax_id.clone().into());
+        }
         values.extend(self.contact.values());
         values
     }
@@ -49,6 +56,12 @@ impl PrepValues for EditClient {
         if self.name.is_some() {
             values.push(self.name.clone().into());
         }
+        if self.tax_id.is_some() {
+            values.push(self.tax_id.clone().into());
+        }
+        if self.currency.is_some() {
+            values.push(self.currency.clone().into());
+        }
         values.extend(self.contact.values());
         values
     }
@@ -77,8 +90,8 @@ impl PrepValues for EditMethod {
             values.push(self.link.clone().into());
         }
         if let Some(qr) = &self.qr {
-            if self.is_valid_image(&qr) {
-                match self.read_image(&qr) {
+            if self.is_valid_image(qr) {
+                match self.read_image(qr) {
                     Ok(data) => values.push(Value::Blob(data)),
                     Err(e) => eprintln!("Error reading image file: {}", e),
                 }
@@ -86,6 +99,54 @@ impl PrepValues for EditMethod {
                 eprintln!("Invalid image file type.");
             }
         }
+        if let Some(kind) = self.kind {
+            values.push(kind.to_string().into());
+        }
+        if self.iban.is_some() {
+            values.push(self.iban.clone().into());
+        }
+        if self.bic.is_some() {
+            values.push(self.bic.clone().into());
+        }
+        if self.beneficiary.is_some() {
+            values.push(self.beneficiary.clone().into());
+        }
+        if let Some(reference_type) = self.reference_type {
+            values.push(reference_type.to_string().into());
+        }
+        if self.street.is_some() {
+            values.push(self.street.clone().into());
+        }
+        if self.postcode.is_some() {
+            values.push(self.postcode.clone().into());
+        }
+        if self.town.is_some() {
+            values.push(self.town.clone().into());
+        }
+        if self.country.is_some() {
+            values.push(self.country.clone().into());
+        }
+        if self.bank_name.is_some() {
+            values.push(self.bank_name.clone().into());
+        }
+        if self.account_number.is_some() {
+            values.push(self.account_number.clone().into());
+        }
+        if self.routing_number.is_some() {
+            values.push(self.routing_number.clone().into());
+        }
+        if self.paypal.is_some() {
+            values.push(self.paypal.clone().into());
+        }
+        if self.wallet.is_some() {
+            values.push(self.wallet.clone().into());
+        }
+        if self.network.is_some() {
+            values.push(self.network.clone().into());
+        }
+        if self.instructions.is_some() {
+            values.push(self.instructions.clone().into());
+        }
         values
     }
 }
@@ -97,7 +158,23 @@ impl PrepValues for EditItem {
             values.push(self.name.clone().into());
         }
         if let Some(rate) = self.rate {
-            values.push(decimal_to_i64!(rate).into());
+            values.push(decimal_to_i64!(rate, RATE_PLACES).into());
+        }
+        if let Some(unit) = &self.unit {
+            values.push(unit.clone().into());
+        }
+        values
+    }
+}
+
+impl PrepValues for EditTax {
+    fn values(&self) -> Vec<Value> {
+        let mut values: Vec<Value> = Vec::new();
+        if self.name.is_some() {
+            values.push(self.name.clone().into());
+        }
+        if let Some(rate) = self.rate {
+            values.push(decimal_to_i64!(rate, RATE_PLACES).into());
         }
         values
     }
@@ -112,6 +189,25 @@ impl PrepValues for EditTemplate {
         if self.company.is_some() {
             values.push(self.company.into());
         }
+        if self.client.is_some() {
+            values.push(self.client.into());
+        }
+        if self.terms.is_some() {
+            values.push(self.terms.into());
+        }
+        if let Some(methods) = &self.methods {
+            let methods_json =
+                serde_json::to_string(methods).expect("Failed to serialize to JSON");
+            values.push(methods_json.into());
+        }
+        if let Some(taxes) = &self.taxes {
+            let taxes_json =
+                serde_json::to_string(taxes).expect("Failed to serialize to JSON");
+            values.push(taxes_json.into());
+        }
+        if self.layout.is_some() {
+            values.push(self.layout.clone().into());
+        }
         values
     }
 }
@@ -134,7 +230,10 @@ impl PrepValues for EditInvoice {
         if let Some(status) = &self.status {
             match status {
                 PaidStatus::Waiting => { values.push("Waiting".to_string().into()) }
-                PaidStatus::PastDue => { values.push("Past Due".to_string().into()) }
+                PaidStatus::PastDue { date } => {
+                    values.push("Past Due".to_string().into());
+                    values.push(date.to_string().into());
+                }
                 PaidStatus::Paid { date, check } => {
                     values.push("Paid".to_string().into());
                     values.push(date.to_string().into());
@@ -157,7 +256,19 @@ impl PrepValues for EditInvoice {
         if let Some(notes) = &self.notes {
             values.push(notes.to_string().into());
         }
-            
+        if let Some(discount) = &self.discount {
+            let discount_json = discount
+                .as_ref()
+                .map(|discount| serde_json::to_string(discount).expect("Failed to serialize to JSON"));
+            values.push(discount_json.into());
+        }
+        if let Some(quote_status) = &self.quote_status {
+            values.push(quote_status.to_string().into());
+        }
+        if let Some(expires) = &self.expires {
+            values.push(expires.format("%Y%m%d").to_string().into());
+        }
+
         values
     }
 }
@@ -1,5 +1,18 @@
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use rust_decimal::Decimal;
+
+use crate::cli::edit::EditInvoice;
 use crate::db::cached::CachedStmt;
+use crate::db::prepare::PrepUpdate;
 use crate::db::InvoiceDB;
+use crate::models::invoice::{Invoice, InvoiceStage, PaidStatus, QuoteStatus};
+use crate::models::numbering::NumberPattern;
+use crate::models::page::PageSetup;
+use crate::models::recurring::Recurring;
+use crate::models::settings::Setting;
 
 use anyhow::Result;
 
@@ -13,12 +26,12 @@ impl InvoiceDB {
     pub fn update_entry(&self, cache: CachedStmt, id: &i64) -> Result<()> {
         let mut stmt = self.connection.prepare(&cache.query)?;
         stmt.execute(rusqlite::params_from_iter(&cache.params))?;
-        self.print_entry(&cache.table.as_str(), &id)?;
+        self.print_entry(cache.table.as_str(), id)?;
         Ok(())
     }
     pub fn delete_entry(&self, cache: CachedStmt, id: &i64) -> Result<()> {
         let mut stmt = self.connection.prepare(&cache.query)?;
-        stmt.execute(&[id])?;
+        stmt.execute([id])?;
         Ok(())
     }
     pub fn print_entry(&self, table: &str, new_id: &i64) -> Result<()> {
@@ -28,35 +41,269 @@ impl InvoiceDB {
                 println!("{}", new_entry);
             }
             "company" => {
-                let new_entry = self.get_company(&new_id)?;
+                let new_entry = self.get_company(new_id)?;
                 println!("{}", new_entry);
             }
             "client" => {
-                let new_entry = self.get_client(&new_id)?;
+                let new_entry = self.get_client(new_id)?;
                 println!("{}", new_entry);
             }
             "terms" => {
-                let new_entry = self.get_terms(&new_id)?;
+                let new_entry = self.get_terms(new_id)?;
                 println!("{}", new_entry);
             }
             "methods" => {
-                let new_entry = self.get_method(&new_id)?;
+                let new_entry = self.get_method(new_id)?;
                 println!("{}", new_entry);
             }
             "items" => {
-                let new_entry = self.get_item(&new_id)?;
+                let new_entry = self.get_item(new_id)?;
+                println!("{}", new_entry);
+            }
+            "payments" => {
+                let new_entry = self.get_payment(new_id)?;
+                println!("{}", new_entry);
+            }
+            "taxes" => {
+                let new_entry = self.get_tax(new_id)?;
                 println!("{}", new_entry);
             }
             "templates" => {
-                let new_entry = self.get_template(&new_id)?;
+                let new_entry = self.get_template(new_id)?;
                 println!("{}", new_entry);
             }
             "invoices" => {
-                let new_entry = self.get_invoice(&new_id)?;
+                let new_entry = self.get_invoice(new_id)?;
+                println!("{}", new_entry);
+            }
+            "recurring" => {
+                let new_entry = self.get_recurring(new_id)?;
+                println!("{}", new_entry);
+            }
+            "credit_notes" => {
+                let new_entry = self.get_credit_note(new_id)?;
                 println!("{}", new_entry);
             }
             _ => todo!("Something else!"),
         }
         Ok(())
     }
+    /// Creates the invoice owed by `schedule` and moves its next run on, in
+    /// one transaction. Returns `None` without creating anything when the
+    /// schedule was already run by someone else in the meantime.
+    pub fn run_schedule(&self, schedule: &Recurring, invoice: CachedStmt) -> Result<Option<i64>> {
+        let tx = self.connection.unchecked_transaction()?;
+        tx.execute(&invoice.query, rusqlite::params_from_iter(&invoice.params))?;
+        let invoice_id = tx.last_insert_rowid();
+        number_invoice(&tx, invoice_id)?;
+        let advanced = tx.execute(
+            "UPDATE recurring SET next_run = ? WHERE id = ? AND next_run = ?",
+            rusqlite::params![
+                schedule.following_run().format("%Y%m%d").to_string(),
+                schedule.id,
+                schedule.next_run,
+            ],
+        )?;
+        if advanced == 0 {
+            tx.rollback()?;
+            return Ok(None);
+        }
+        tx.commit()?;
+        Ok(Some(invoice_id))
+    }
+    /// Inserts an invoice and numbers it, in one transaction. Returns the
+    /// new invoice id. The stored invoice is passed to `check` before the
+    /// commit, and nothing is kept when it fails.
+    pub fn create_invoice(&self, invoice: CachedStmt, check: impl FnOnce(&Invoice) -> Result<()>) -> Result<i64> {
+        let tx = self.connection.unchecked_transaction()?;
+        tx.execute(&invoice.query, rusqlite::params_from_iter(&invoice.params))?;
+        let invoice_id = tx.last_insert_rowid();
+        number_invoice(&tx, invoice_id)?;
+        check(&self.get_invoice(&invoice_id)?)?;
+        tx.commit()?;
+        Ok(invoice_id)
+    }
+    /// Stores `value` for `setting`, or clears it when `value` is `None`.
+    pub fn set_setting(&self, setting: Setting, value: Option<&str>) -> Result<()> {
+        match value {
+            Some(value) => self.connection.execute(
+                "INSERT INTO settings (key, value) VALUES (?1, ?2)
+                    ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+                [setting.to_string().as_str(), value],
+            )?,
+            None => self.connection.execute("DELETE FROM settings WHERE key = ?", [setting.to_string()])?,
+        };
+        Ok(())
+    }
+    /// Stores the page options of a company, clearing them when `setup` is empty.
+    pub fn set_company_page_setup(&self, company_id: &i64, setup: &PageSetup) -> Result<()> {
+        let setup = if setup.is_empty() { None } else { Some(serde_json::to_string(setup)?) };
+        self.connection.execute(
+            "UPDATE company SET page_setup = ?1 WHERE id = ?2",
+            rusqlite::params![setup, company_id],
+        )?;
+        Ok(())
+    }
+    /// Stores the page options of a layout, clearing them when `setup` is empty.
+    pub fn set_layout_page_setup(&self, layout: &str, setup: &PageSetup) -> Result<()> {
+        if setup.is_empty() {
+            self.connection.execute("DELETE FROM layout_pages WHERE layout = ?", [layout])?;
+        } else {
+            self.connection.execute(
+                "INSERT INTO layout_pages (layout, page_setup) VALUES (?1, ?2)
+                    ON CONFLICT (layout) DO UPDATE SET page_setup = excluded.page_setup",
+                [layout, serde_json::to_string(setup)?.as_str()],
+            )?;
+        }
+        Ok(())
+    }
+    /// Numbers an invoice that reached the Invoice stage without a number,
+    /// e.g. a quote whose stage was edited. Returns the new number.
+    pub fn assign_number(&self, invoice_id: &i64) -> Result<Option<String>> {
+        let tx = self.connection.unchecked_transaction()?;
+        let number = number_invoice(&tx, *invoice_id)?;
+        tx.commit()?;
+        Ok(number)
+    }
+    /// Inserts a credit note and gives it the next number in the credit
+    /// note sequence, in one transaction so numbers are never skipped or
+    /// reused. Returns the new credit note id.
+    pub fn issue_credit_note(&self, credit: CachedStmt) -> Result<i64> {
+        let tx = self.connection.unchecked_transaction()?;
+        tx.execute(&credit.query, rusqlite::params_from_iter(&credit.params))?;
+        let credit_id = tx.last_insert_rowid();
+        tx.execute(
+            "UPDATE credit_notes
+             SET number = (SELECT COALESCE(MAX(number), 0) + 1 FROM credit_notes)
+             WHERE id = ?",
+            [credit_id],
+        )?;
+        tx.commit()?;
+        Ok(credit_id)
+    }
+    /// Inserts the invoice converted from `quote_id` and marks the quote
+    /// Accepted, in one transaction. Returns the new invoice id.
+    pub fn convert_quote(&self, quote_id: &i64, invoice: CachedStmt) -> Result<i64> {
+        let tx = self.connection.unchecked_transaction()?;
+        tx.execute(&invoice.query, rusqlite::params_from_iter(&invoice.params))?;
+        let invoice_id = tx.last_insert_rowid();
+        number_invoice(&tx, invoice_id)?;
+        tx.execute(
+            "UPDATE invoices SET quote_status = ? WHERE id = ?",
+            rusqlite::params![QuoteStatus::Accepted.to_string(), quote_id],
+        )?;
+        tx.commit()?;
+        Ok(invoice_id)
+    }
+    /// Moves Open quotes whose expiry date is before `today` to Expired,
+    /// returning the quotes that changed.
+    pub fn expire_quotes(&self, today: NaiveDate) -> Result<Vec<Invoice>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT id FROM invoices
+             WHERE stage = 'Quote' AND quote_status = 'Open' AND expires < ?
+             ORDER BY id",
+        )?;
+        let ids = stmt
+            .query_map([today.format("%Y%m%d").to_string()], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, rusqlite::Error>>()?;
+        let mut changed = Vec::new();
+        for id in ids {
+            let edit_invoice = EditInvoice {
+                id,
+                show_methods: None,
+                show_notes: None,
+                stage: None,
+                status: None,
+                notes: None,
+                discount: None,
+                quote_status: Some(QuoteStatus::Expired),
+                expires: None,
+            };
+            edit_invoice.prepare().execute(self)?;
+            match self.get_invoice(&id) {
+                Ok(quote) => changed.push(quote),
+                Err(e) => eprintln!("Quote #{} expired but could not be read: {}", id, e),
+            }
+        }
+        Ok(changed)
+    }
+    /// Moves Waiting invoices whose due date is before `today` to Past Due,
+    /// returning the invoices that changed. Invoices that cannot be read are
+    /// reported and left alone, so a bad row never stops the database opening.
+    pub fn refresh_past_due(&self, today: NaiveDate) -> Result<Vec<Invoice>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT id FROM invoices WHERE stage = 'Invoice' AND status = 'Waiting' ORDER BY id",
+        )?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, rusqlite::Error>>()?;
+        let mut changed = Vec::new();
+        for id in ids {
+            let invoice = match self.get_invoice(&id) {
+                Ok(invoice) => invoice,
+                Err(e) => {
+                    eprintln!("Skipping invoice #{} in the past due check: {}", id, e);
+                    continue;
+                }
+            };
+            if invoice.due_date() >= today || invoice.balance() <= Decimal::ZERO {
+                continue;
+            }
+            let edit_invoice = EditInvoice {
+                id,
+                show_methods: None,
+                show_notes: None,
+                stage: None,
+                status: Some(PaidStatus::PastDue { date: today.format("%Y%m%d").to_string() }),
+                notes: None,
+                discount: None,
+                quote_status: None,
+                expires: None,
+            };
+            edit_invoice.prepare().execute(self)?;
+            changed.push(self.get_invoice(&id)?);
+        }
+        Ok(changed)
+    }
+}
+
+/// Gives an Invoice-stage invoice without a number the next one in its
+/// company's sequence. Runs inside the caller's transaction, so a number is
+/// only used up when the invoice is kept. The unique index on company and
+/// number makes a concurrent run that picked the same number fail instead.
+fn number_invoice(conn: &Connection, invoice_id: i64) -> Result<Option<String>> {
+    let (stage, date, number, company_id, pattern) = conn.query_row(
+        "SELECT invoices.stage, invoices.date, invoices.number, company.id, company.number_pattern
+         FROM invoices
+         JOIN templates ON templates.id = invoices.template_id
+         JOIN company ON company.id = templates.company_id
+         WHERE invoices.id = ?",
+        [invoice_id],
+        |row| Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, i64>(3)?,
+            row.get::<_, Option<String>>(4)?,
+        )),
+    )?;
+    if stage != InvoiceStage::Invoice.to_string() || number.is_some() {
+        return Ok(None);
+    }
+    let pattern = pattern
+        .and_then(|pattern| NumberPattern::from_str(&pattern).ok())
+        .unwrap_or_default();
+    let year = &date[..4];
+    let seq: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(number_seq), 0) + 1 FROM invoices
+         WHERE company_id = ?1 AND (?2 = 0 OR substr(date, 1, 4) = ?3)",
+        rusqlite::params![company_id, pattern.resets_yearly(), year],
+        |row| row.get(0),
+    )?;
+    let number = pattern.format(year.parse()?, seq);
+    conn.execute(
+        "UPDATE invoices SET number = ?, number_seq = ?, company_id = ? WHERE id = ?",
+        rusqlite::params![number, seq, company_id, invoice_id],
+    )?;
+    Ok(Some(number))
 }
@@ -1,18 +1,21 @@
+/// Converts `decimal` to an integer count of `places`-digit units, e.g.
+/// cents for 2, rounding anything finer.
 #[macro_export]
 macro_rules! decimal_to_i64 {
-    ($decimal:expr) => {{
+    ($decimal:expr, $places:expr) => {{
         use rust_decimal::Decimal;
         use rust_decimal::prelude::ToPrimitive;
-        let factor = Decimal::from(100);
+        let factor = Decimal::from(10_i64.pow($places));
         let scaled_decimal = $decimal * factor;
         scaled_decimal.round().to_i64().expect("Value too large or NaN")
     }};
 }
 
+/// Converts an integer count of `places`-digit units back to a decimal.
 #[macro_export]
 macro_rules! i64_to_decimal {
-    ($integer:expr) => {{
+    ($integer:expr, $places:expr) => {{
         use rust_decimal::Decimal;
-        Decimal::from($integer) / Decimal::from(100)
+        Decimal::from($integer) / Decimal::from(10_i64.pow($places))
     }};
 }
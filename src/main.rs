@@ -14,7 +14,7 @@ pub mod validators;
 
 fn main() -> Result<()> {
     let paths = Paths::init()?;
-    let mut db = InvoiceDB::open(paths.db, 2)?;
+    let mut db = InvoiceDB::open(paths.db, 21)?;
     let renderer = TemplateEngine::new(&paths.templates)?;
     Cli::to_cmd(&mut db, &renderer)?;
     Ok(())
@@ -2,9 +2,11 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 use inquire::{MultiSelect, Text, InquireError};
+use inquire::validator::Validation;
 
-use crate::models::contact::Contact;
-use crate::models::{prompt_optional, EntityUpdater, EntityDeleter};
+use crate::models::contact::{parse_country, Contact};
+use crate::models::currency::parse_currency;
+use crate::models::{prompt_optional, EntityUpdater, EntityDeleter, Tabular};
 use crate::cli::edit::EditClient;
 use crate::cli::delete::DeleteClient;
 use crate::cli::contact::Contact as cli_contact;
@@ -15,13 +17,41 @@ pub struct Client {
     pub id: i64,
     pub name: String,
     pub contact: Contact,
+    /// VAT or other tax registration number.
+    pub tax_id: Option<String>,
+    /// ISO 4217 code new invoices to this client are in. None uses the
+    /// `currency` setting.
+    pub currency: Option<String>,
+}
+
+impl Tabular for Client {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Phone", "Email", "City", "State", "Currency"]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.contact.phone.clone().unwrap_or_default(),
+            self.contact.email.clone().unwrap_or_default(),
+            self.contact.city.clone().unwrap_or_default(),
+            self.contact.state.clone().unwrap_or_default(),
+            self.currency.clone().unwrap_or_default(),
+        ]
+    }
 }
 
 //moved to invoice-app/src/display
 impl fmt::Display for Client {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "ID:\t\t{}\n", self.id)?;
-        write!(f, "Name:\t\t{}\n", self.name)?;
+        writeln!(f, "ID:\t\t{}", self.id)?;
+        writeln!(f, "Name:\t\t{}", self.name)?;
+        if let Some(tax_id) = &self.tax_id {
+            writeln!(f, "Tax ID:\t\t{}", tax_id)?;
+        }
+        if let Some(currency) = &self.currency {
+            writeln!(f, "Currency:\t{}", currency)?;
+        }
         write!(f, "Contact Information:\n{}", self.contact)
     }
 }
@@ -39,18 +69,23 @@ impl EntityUpdater<Client> for Client {
         println!("{}", self);
         let fields = vec![
             "name",
+            "tax id",
+            "currency",
             "phone",
             "email",
             "addr1",
             "addr2",
             "city",
             "state",
-            "zip"];
+            "zip",
+            "country"];
         let selected_fields = MultiSelect::new("Select fields to update:", fields)
             .prompt()?;
         let mut edit_client = EditClient{
             id: self.id,
             name: None,
+            tax_id: None,
+            currency: None,
             contact: cli_contact{
                 phone: None,
                 email: None,
@@ -58,7 +93,8 @@ impl EntityUpdater<Client> for Client {
                 addr2: None,
                 city: None,
                 state: None,
-                zip: None
+                zip: None,
+                country: None,
             }
         };
         for field in selected_fields {
@@ -69,48 +105,74 @@ impl EntityUpdater<Client> for Client {
                         .prompt()?;
                     edit_client.name = Some(name);
                 },
+                "tax id" => {
+                    edit_client.tax_id = prompt_optional(
+                        "Enter new tax ID (type 'None' to clear):",
+                        self.tax_id.as_deref().unwrap_or("")
+                    )?;
+                },
+                "currency" => {
+                    let currency = Text::new("Enter new three-letter currency code:")
+                        .with_default(self.currency.as_deref().unwrap_or(""))
+                        .with_validator(|input: &str| match parse_currency(input) {
+                            Ok(_) => Ok(Validation::Valid),
+                            Err(err) => Ok(Validation::Invalid(err.into())),
+                        })
+                        .prompt()?;
+                    edit_client.currency = parse_currency(&currency).ok();
+                },
                 "phone" => {
                     edit_client.contact.phone = prompt_optional(
                         "Enter new phone number (type 'None' to clear):",
-                        &self.contact.phone.as_deref().unwrap_or("")
+                        self.contact.phone.as_deref().unwrap_or("")
                     )?;
                 },
                 "email" => {
                     edit_client.contact.email = prompt_optional(
                         "Enter new email address (type 'None' to clear):",
-                        &self.contact.email.as_deref().unwrap_or("")
+                        self.contact.email.as_deref().unwrap_or("")
                     )?;
                 },
                 "addr1" => {
                     edit_client.contact.addr1 = prompt_optional(
                         "Enter new address (type 'None' to clear):",
-                        &self.contact.addr1.as_deref().unwrap_or("")
+                        self.contact.addr1.as_deref().unwrap_or("")
                     )?;
                 },
                 "addr2" => {
                     edit_client.contact.addr2 = prompt_optional(
                         "Enter new additional address (type 'None' to clear):",
-                        &self.contact.addr2.as_deref().unwrap_or("")
+                        self.contact.addr2.as_deref().unwrap_or("")
                     )?;
                 },
                 "city" => {
                     edit_client.contact.city = prompt_optional(
                         "Enter new city (type 'None' to clear):",
-                        &self.contact.city.as_deref().unwrap_or("")
+                        self.contact.city.as_deref().unwrap_or("")
                     )?;
                 },
                 "state" => {
                     edit_client.contact.state = prompt_optional(
                         "Enter new state (type 'None' to clear):",
-                        &self.contact.state.as_deref().unwrap_or("")
+                        self.contact.state.as_deref().unwrap_or("")
                     )?;
                 },
                 "zip" => {
                     edit_client.contact.zip = prompt_optional(
                         "Enter new zip (type 'None' to clear):",
-                        &self.contact.zip.as_deref().unwrap_or("")
+                        self.contact.zip.as_deref().unwrap_or("")
                     )?;
                 },
+                "country" => {
+                    let country = Text::new("Enter new two-letter country code:")
+                        .with_default(self.contact.country.as_deref().unwrap_or(""))
+                        .with_validator(|input: &str| match parse_country(input) {
+                            Ok(_) => Ok(Validation::Valid),
+                            Err(err) => Ok(Validation::Invalid(err.into())),
+                        })
+                        .prompt()?;
+                    edit_client.contact.country = parse_country(&country).ok();
+                },
                 _ => {}
             }
         }
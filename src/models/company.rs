@@ -1,17 +1,21 @@
 use std::fmt;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use base64::{engine::general_purpose::STANDARD, Engine};
 use infer;
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
-use crate::models::contact::Contact;
-use crate::models::{prompt_optional, EntityUpdater, EntityDeleter};
+use crate::models::contact::{parse_country, Contact};
+use crate::models::numbering::NumberPattern;
+use crate::models::page::PageSetup;
+use crate::models::{prompt_optional, EntityUpdater, EntityDeleter, Tabular};
 use crate::cli::edit::EditCompany;
 use crate::cli::delete::DeleteCompany;
 use crate::cli::contact::Contact as cli_contact;
 
 use inquire::{MultiSelect, Text, InquireError};
+use inquire::validator::Validation;
 
 #[derive(Debug, Deserialize)]
 pub struct Company {
@@ -19,13 +23,42 @@ pub struct Company {
     pub name: String,
     pub logo: Option<Vec<u8>>,
     pub contact: Contact,
+    /// Invoice numbering pattern, `NumberPattern::DEFAULT` when unset.
+    pub number_pattern: Option<String>,
+    /// `PageSetup` as JSON, set with `page-setup set --company`.
+    pub page_setup: Option<String>,
+    /// VAT or other tax registration number.
+    pub tax_id: Option<String>,
+}
+
+impl Company {
+    pub fn number_pattern(&self) -> NumberPattern {
+        self.number_pattern
+            .as_deref()
+            .and_then(|pattern| NumberPattern::from_str(pattern).ok())
+            .unwrap_or_default()
+    }
+    pub fn page_setup(&self) -> PageSetup {
+        self.page_setup
+            .as_deref()
+            .and_then(|setup| serde_json::from_str(setup).ok())
+            .unwrap_or_default()
+    }
 }
 
 impl fmt::Display for Company {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "ID:\t\t{}\n", self.id)?;
-        write!(f, "Name:\t\t{}\n", self.name)?;
-        write!(f, "Has Logo:\t{}\n", self.logo.is_some())?;
+        writeln!(f, "ID:\t\t{}", self.id)?;
+        writeln!(f, "Name:\t\t{}", self.name)?;
+        writeln!(f, "Has Logo:\t{}", self.logo.is_some())?;
+        if let Some(tax_id) = &self.tax_id {
+            writeln!(f, "Tax ID:\t\t{}", tax_id)?;
+        }
+        writeln!(f, "Numbering:\t{}", self.number_pattern())?;
+        let page_setup = self.page_setup();
+        if !page_setup.is_empty() {
+            write!(f, "Page Setup:\n{}", page_setup)?;
+        }
         write!(f, "Contact Information:\n{}", self.contact)
     }
 }
@@ -45,19 +78,24 @@ impl EntityUpdater<Company> for Company {
         let fields = vec![
             "name",
             "logo",
+            "number pattern",
+            "tax id",
             "phone",
             "email",
             "addr1",
             "addr2",
             "city",
             "state",
-            "zip"];
+            "zip",
+            "country"];
         let selected_fields = MultiSelect::new("Select fields to update:", fields)
             .prompt()?;
         let mut edit_company = EditCompany{
             id: self.id,
             name: None,
             logo: None,
+            number_pattern: None,
+            tax_id: None,
             contact: cli_contact{
                 phone: None,
                 email: None,
@@ -65,7 +103,8 @@ impl EntityUpdater<Company> for Company {
                 addr2: None,
                 city: None,
                 state: None,
-                zip: None
+                zip: None,
+                country: None,
             }
         };
         for field in selected_fields {
@@ -86,48 +125,75 @@ impl EntityUpdater<Company> for Company {
                         edit_company.logo = Some(logo);
                     }
                 },
+                "number pattern" => {
+                    let pattern = Text::new("Enter new invoice number pattern:")
+                        .with_default(&self.number_pattern().to_string())
+                        .with_help_message("{year}, {seq} and {seq:N} for a counter padded to N digits")
+                        .with_validator(|input: &str| match NumberPattern::from_str(input) {
+                            Ok(_) => Ok(Validation::Valid),
+                            Err(err) => Ok(Validation::Invalid(err.into())),
+                        })
+                        .prompt()?;
+                    edit_company.number_pattern = Some(pattern.trim().to_string());
+                },
+                "tax id" => {
+                    edit_company.tax_id = prompt_optional(
+                        "Enter new tax ID (type 'None' to clear):",
+                        self.tax_id.as_deref().unwrap_or("")
+                    )?;
+                },
                 "phone" => {
                     edit_company.contact.phone = prompt_optional(
                         "Enter new phone number (type 'None' to clear):",
-                        &self.contact.phone.as_deref().unwrap_or("")
+                        self.contact.phone.as_deref().unwrap_or("")
                     )?;
                 },
                 "email" => {
                     edit_company.contact.email = prompt_optional(
                         "Enter new email address (type 'None' to clear):",
-                        &self.contact.email.as_deref().unwrap_or("")
+                        self.contact.email.as_deref().unwrap_or("")
                     )?;
                 },
                 "addr1" => {
                     edit_company.contact.addr1 = prompt_optional(
                         "Enter new address (type 'None' to clear):",
-                        &self.contact.addr1.as_deref().unwrap_or("")
+                        self.contact.addr1.as_deref().unwrap_or("")
                     )?;
                 },
                 "addr2" => {
                     edit_company.contact.addr2 = prompt_optional(
                         "Enter new additional address (type 'None' to clear):",
-                        &self.contact.addr2.as_deref().unwrap_or("")
+                        self.contact.addr2.as_deref().unwrap_or("")
                     )?;
                 },
                 "city" => {
                     edit_company.contact.city = prompt_optional(
                         "Enter new city (type 'None' to clear):",
-                        &self.contact.city.as_deref().unwrap_or("")
+                        self.contact.city.as_deref().unwrap_or("")
                     )?;
                 },
                 "state" => {
                     edit_company.contact.state = prompt_optional(
                         "Enter new state (type 'None' to clear):",
-                        &self.contact.state.as_deref().unwrap_or("")
+                        self.contact.state.as_deref().unwrap_or("")
                     )?;
                 },
                 "zip" => {
                     edit_company.contact.zip = prompt_optional(
                         "Enter new zip (type 'None' to clear):",
-                        &self.contact.zip.as_deref().unwrap_or("")
+                        self.contact.zip.as_deref().unwrap_or("")
                     )?;
                 },
+                "country" => {
+                    let country = Text::new("Enter new two-letter country code:")
+                        .with_default(self.contact.country.as_deref().unwrap_or(""))
+                        .with_validator(|input: &str| match parse_country(input) {
+                            Ok(_) => Ok(Validation::Valid),
+                            Err(err) => Ok(Validation::Invalid(err.into())),
+                        })
+                        .prompt()?;
+                    edit_company.contact.country = parse_country(&country).ok();
+                },
                 _ => {}
             }
         }
@@ -135,6 +201,22 @@ impl EntityUpdater<Company> for Company {
     }
 }
 
+impl Tabular for Company {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Phone", "Email", "City", "State"]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.contact.phone.clone().unwrap_or_default(),
+            self.contact.email.clone().unwrap_or_default(),
+            self.contact.city.clone().unwrap_or_default(),
+            self.contact.state.clone().unwrap_or_default(),
+        ]
+    }
+}
+
 impl Serialize for Company {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -157,6 +239,8 @@ impl Serialize for Company {
             state.serialize_field("logo", &None::<String>)?;
         }
         state.serialize_field("contact", &self.contact)?;
+        state.serialize_field("number_pattern", &self.number_pattern().to_string())?;
+        state.serialize_field("tax_id", &self.tax_id)?;
         state.end()
     }
 }
@@ -16,8 +16,8 @@ pub struct Config {
     pub fromname: String,
 }
 
-impl Config {
-    pub fn default() -> Self {
+impl Default for Config {
+    fn default() -> Self {
         Self {
             id: 0,
             smtp_server: "smtp.example.com".to_string(),
@@ -28,6 +28,9 @@ impl Config {
             fromname: String::new(),
         }
     }
+}
+
+impl Config {
     pub fn create(&self) -> Result<Self, InquireError> {
         let mut edit_config = Config {
             id: self.id,
@@ -69,12 +72,12 @@ impl Config {
 
 impl fmt::Display for Config {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "SMTP Server:\t\t{}\n", self.smtp_server)?;
-        write!(f, "Port:\t\t\t{}\n", self.port)?;
-        write!(f, "TLS:\t\t\t{}\n", self.tls)?;
-        write!(f, "Username:\t\t{}\n", self.username)?;
-        write!(f, "Password:\t\t{}\n", self.password)?;
-        write!(f, "From name:\t\t{}\n", self.fromname)
+        writeln!(f, "SMTP Server:\t\t{}", self.smtp_server)?;
+        writeln!(f, "Port:\t\t\t{}", self.port)?;
+        writeln!(f, "TLS:\t\t\t{}", self.tls)?;
+        writeln!(f, "Username:\t\t{}", self.username)?;
+        writeln!(f, "Password:\t\t{}", self.password)?;
+        writeln!(f, "From name:\t\t{}", self.fromname)
     }
 }
 
@@ -11,31 +11,46 @@ pub struct Contact {
     pub city: Option<String>,
     pub state: Option<String>,
     pub zip: Option<String>,
+    /// ISO 3166-1 alpha-2 code, e.g. `DE`.
+    pub country: Option<String>,
 }
 
 impl fmt::Display for Contact {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if let Some(ref phone) = self.phone {
-            write!(f, "Phone:\t\t{}\n", phone)?;
+            writeln!(f, "Phone:\t\t{}", phone)?;
         }
         if let Some(ref email) = self.email {
-            write!(f, "Email:\t\t{}\n", email)?;
+            writeln!(f, "Email:\t\t{}", email)?;
         }
         if let Some(ref addr1) = self.addr1 {
-            write!(f, "Addr1:\t\t{}\n", addr1)?;
+            writeln!(f, "Addr1:\t\t{}", addr1)?;
         }
         if let Some(ref addr2) = self.addr2 {
-            write!(f, "Addr2:\t\t{}\n", addr2)?;
+            writeln!(f, "Addr2:\t\t{}", addr2)?;
         }
         if let Some(ref city) = self.city {
-            write!(f, "City:\t\t{}\n", city)?;
+            writeln!(f, "City:\t\t{}", city)?;
         }
         if let Some(ref state) = self.state {
-            write!(f, "State:\t\t{}\n", state)?;
+            writeln!(f, "State:\t\t{}", state)?;
         }
         if let Some(ref zip) = self.zip {
-            write!(f, "Zip:\t\t{}\n", zip)?;
+            writeln!(f, "Zip:\t\t{}", zip)?;
+        }
+        if let Some(ref country) = self.country {
+            writeln!(f, "Country:\t{}", country)?;
         }
         Ok(())
     }
 }
+
+/// Checks an ISO 3166-1 alpha-2 country code and returns it upper-cased.
+pub fn parse_country(s: &str) -> Result<String, String> {
+    let code = s.trim().to_uppercase();
+    if code.len() == 2 && code.chars().all(|c| c.is_ascii_uppercase()) {
+        Ok(code)
+    } else {
+        Err(format!("Invalid country, expected a two-letter ISO code such as DE: {}", s))
+    }
+}
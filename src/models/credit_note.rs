@@ -0,0 +1,121 @@
+use std::fmt;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use crate::models::currency::Currency;
+use crate::models::invoice::{line_details, tax_details, InvoiceLine, TaxDetail};
+use crate::models::template::Template;
+use crate::models::Tabular;
+
+/// A document crediting part or all of an invoice back to the client. Lines
+/// carry negative quantities, so every total on a credit note is negative.
+#[derive(Debug)]
+pub struct CreditNote {
+    pub id: i64,
+    /// Sequence number, allocated separately from invoice ids.
+    pub number: i64,
+    pub invoice_id: i64,
+    /// Issue date of the credited invoice, YYYYMMDD.
+    pub invoice_date: String,
+    /// Number of the credited invoice, if it was given one.
+    pub invoice_number: Option<String>,
+    pub template: Template,
+    pub date: String,
+    pub reason: Option<String>,
+    pub items: Vec<InvoiceLine>,
+    /// Currency of the credited invoice.
+    pub currency: Currency,
+}
+
+impl CreditNote {
+    pub fn reference(&self) -> String {
+        format!("CN-{:04}", self.number)
+    }
+    pub fn invoice_reference(&self) -> String {
+        self.invoice_number.clone().unwrap_or_else(|| format!("#{}", self.invoice_id))
+    }
+    pub fn credit_date(&self) -> NaiveDate {
+        NaiveDate::parse_from_str(&self.date, "%Y%m%d").unwrap()
+    }
+    pub fn calculate_subtotal(&self) -> Decimal {
+        self.items.iter().map(|line| line.subtotal(&self.currency)).sum()
+    }
+    pub(crate) fn calculate_taxes(&self) -> Vec<TaxDetail> {
+        tax_details(&self.items, Decimal::ONE, &self.currency)
+    }
+    /// The credited amount including taxes, as a negative number.
+    pub fn calculate_total(&self) -> Decimal {
+        self.calculate_subtotal() + self.calculate_taxes().iter().map(|tax| tax.amount).sum::<Decimal>()
+    }
+}
+
+impl fmt::Display for CreditNote {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "ID:\t\t{}", self.id)?;
+        writeln!(f, "Credit note:\t{}", self.reference())?;
+        writeln!(f, "Date:\t\t{}", self.credit_date())?;
+        writeln!(f, "Invoice:\t{} (ID {})", self.invoice_reference(), self.invoice_id)?;
+        writeln!(f, "Client:\t\t{}", self.template.client.name)?;
+        if let Some(reason) = &self.reason {
+            writeln!(f, "Reason:\t\t{}", reason)?;
+        }
+        writeln!(f, "Lines:")?;
+        for line in &self.items {
+            writeln!(
+                f,
+                "  {} x {} @ {} = {}",
+                line.quantity.normalize(),
+                line.name,
+                self.currency.format(line.rate),
+                self.currency.format(line.subtotal(&self.currency))
+            )?;
+        }
+        writeln!(f, "Total:\t\t{}", self.currency.format(self.calculate_total()))
+    }
+}
+
+impl Tabular for CreditNote {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Number", "Date", "Invoice", "Client", "Currency", "Total", "Reason"]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.reference(),
+            self.credit_date().to_string(),
+            self.invoice_id.to_string(),
+            self.template.client.name.clone(),
+            self.currency.to_string(),
+            self.currency.round(self.calculate_total()).to_string(),
+            self.reason.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+impl Serialize for CreditNote {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let invoice_date = NaiveDate::parse_from_str(&self.invoice_date, "%Y%m%d")
+            .map(|date| date.format("%B %d, %Y").to_string())
+            .unwrap_or_else(|_| self.invoice_date.clone());
+        let mut state = serializer.serialize_struct("CreditNote", 13)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("number", &self.reference())?;
+        state.serialize_field("invoice_id", &self.invoice_id)?;
+        state.serialize_field("invoice_number", &self.invoice_reference())?;
+        state.serialize_field("invoice_date", &invoice_date)?;
+        state.serialize_field("template", &self.template)?;
+        state.serialize_field("date", &self.credit_date().format("%B %d, %Y").to_string())?;
+        state.serialize_field("reason", &self.reason)?;
+        state.serialize_field("currency", &self.currency)?;
+        state.serialize_field("items", &line_details(&self.items, &self.currency))?;
+        state.serialize_field("subtotal", &self.currency.round(self.calculate_subtotal()).to_string())?;
+        state.serialize_field("taxes", &self.calculate_taxes())?;
+        state.serialize_field("total", &self.currency.round(self.calculate_total()).to_string())?;
+        state.end()
+    }
+}
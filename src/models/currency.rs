@@ -0,0 +1,182 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+/// Active ISO 4217 currencies: code, number of minor units and the symbol
+/// amounts are written with. An empty symbol falls back to the code.
+const CURRENCIES: &[(&str, u32, &str)] = &[
+    ("AED", 2, ""), ("AFN", 2, "؋"), ("ALL", 2, ""), ("AMD", 2, "֏"), ("ANG", 2, ""), ("AOA", 2, ""),
+    ("ARS", 2, ""), ("AUD", 2, "A$"), ("AWG", 2, ""), ("AZN", 2, "₼"), ("BAM", 2, "KM"), ("BBD", 2, ""),
+    ("BDT", 2, "৳"), ("BGN", 2, ""), ("BHD", 3, ""), ("BIF", 0, ""), ("BMD", 2, ""), ("BND", 2, ""),
+    ("BOB", 2, "Bs"), ("BOV", 2, ""), ("BRL", 2, "R$"), ("BSD", 2, ""), ("BTN", 2, ""), ("BWP", 2, "P"),
+    ("BYN", 2, ""), ("BZD", 2, ""), ("CAD", 2, "CA$"), ("CDF", 2, ""), ("CHE", 2, ""), ("CHF", 2, ""),
+    ("CHW", 2, ""), ("CLF", 4, ""), ("CLP", 0, ""), ("CNY", 2, "CN¥"), ("COP", 2, ""), ("COU", 2, ""),
+    ("CRC", 2, "₡"), ("CUP", 2, ""), ("CVE", 2, ""), ("CZK", 2, "Kč"), ("DJF", 0, ""), ("DKK", 2, "kr"),
+    ("DOP", 2, ""), ("DZD", 2, ""), ("EGP", 2, "E£"), ("ERN", 2, ""), ("ETB", 2, ""), ("EUR", 2, "€"),
+    ("FJD", 2, ""), ("FKP", 2, ""), ("GBP", 2, "£"), ("GEL", 2, "₾"), ("GHS", 2, "GH₵"), ("GIP", 2, ""),
+    ("GMD", 2, ""), ("GNF", 0, ""), ("GTQ", 2, "Q"), ("GYD", 2, ""), ("HKD", 2, "HK$"), ("HNL", 2, ""),
+    ("HTG", 2, ""), ("HUF", 2, "Ft"), ("IDR", 2, "Rp"), ("ILS", 2, "₪"), ("INR", 2, "₹"), ("IQD", 3, ""),
+    ("IRR", 2, ""), ("ISK", 0, "kr"), ("JMD", 2, ""), ("JOD", 3, ""), ("JPY", 0, "¥"), ("KES", 2, ""),
+    ("KGS", 2, ""), ("KHR", 2, "៛"), ("KMF", 0, ""), ("KPW", 2, ""), ("KRW", 0, "₩"), ("KWD", 3, ""),
+    ("KYD", 2, ""), ("KZT", 2, "₸"), ("LAK", 2, "₭"), ("LBP", 2, ""), ("LKR", 2, ""), ("LRD", 2, ""),
+    ("LSL", 2, ""), ("LYD", 3, ""), ("MAD", 2, ""), ("MDL", 2, ""), ("MGA", 2, ""), ("MKD", 2, ""),
+    ("MMK", 2, ""), ("MNT", 2, "₮"), ("MOP", 2, ""), ("MRU", 2, ""), ("MUR", 2, ""), ("MVR", 2, ""),
+    ("MWK", 2, ""), ("MXN", 2, "MX$"), ("MXV", 2, ""), ("MYR", 2, "RM"), ("MZN", 2, ""), ("NAD", 2, ""),
+    ("NGN", 2, "₦"), ("NIO", 2, ""), ("NOK", 2, "kr"), ("NPR", 2, ""), ("NZD", 2, "NZ$"), ("OMR", 3, ""),
+    ("PAB", 2, ""), ("PEN", 2, ""), ("PGK", 2, ""), ("PHP", 2, "₱"), ("PKR", 2, ""), ("PLN", 2, "zł"),
+    ("PYG", 0, "₲"), ("QAR", 2, ""), ("RON", 2, "lei"), ("RSD", 2, ""), ("RUB", 2, "₽"), ("RWF", 0, ""),
+    ("SAR", 2, ""), ("SBD", 2, ""), ("SCR", 2, ""), ("SDG", 2, ""), ("SEK", 2, "kr"), ("SGD", 2, ""),
+    ("SHP", 2, ""), ("SLE", 2, ""), ("SOS", 2, ""), ("SRD", 2, ""), ("SSP", 2, ""), ("STN", 2, ""),
+    ("SVC", 2, ""), ("SYP", 2, ""), ("SZL", 2, ""), ("THB", 2, "฿"), ("TJS", 2, ""), ("TMT", 2, ""),
+    ("TND", 3, ""), ("TOP", 2, ""), ("TRY", 2, "₺"), ("TTD", 2, ""), ("TWD", 2, "NT$"), ("TZS", 2, ""),
+    ("UAH", 2, "₴"), ("UGX", 0, ""), ("USD", 2, "$"), ("USN", 2, ""), ("UYI", 0, ""), ("UYU", 2, ""),
+    ("UYW", 4, ""), ("UZS", 2, ""), ("VED", 2, ""), ("VES", 2, ""), ("VND", 0, "₫"), ("VUV", 0, ""),
+    ("WST", 2, ""), ("XAF", 0, "FCFA"), ("XCD", 2, "EC$"), ("XCG", 2, ""), ("XOF", 0, "F CFA"), ("XPF", 0, "CFPF"),
+    ("YER", 2, ""), ("ZAR", 2, "R"), ("ZMW", 2, ""), ("ZWG", 2, ""),
+];
+
+/// How numbers are written: the grouping and decimal separators, and which
+/// side of the number the currency goes on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    /// $1,234.56
+    #[default]
+    En,
+    /// 1.234,56 €
+    De,
+    /// 1 234,56 €
+    Fr,
+    /// CHF 1'234.56
+    DeCh,
+}
+
+impl Locale {
+    fn separators(&self) -> (&'static str, char) {
+        match self {
+            Locale::En => (",", '.'),
+            Locale::De => (".", ','),
+            Locale::Fr => (" ", ','),
+            Locale::DeCh => ("'", '.'),
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Locale::En => write!(f, "en"),
+            Locale::De => write!(f, "de"),
+            Locale::Fr => write!(f, "fr"),
+            Locale::DeCh => write!(f, "de-CH"),
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('_', "-").as_str() {
+            "en" => Ok(Locale::En),
+            "de" => Ok(Locale::De),
+            "fr" => Ok(Locale::Fr),
+            "de-ch" => Ok(Locale::DeCh),
+            _ => Err(format!("Invalid locale, expected en, de, fr or de-CH: {}", s)),
+        }
+    }
+}
+
+/// An ISO 4217 currency, and the locale its amounts are written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Currency {
+    code: &'static str,
+    minor_units: u32,
+    symbol: &'static str,
+    pub locale: Locale,
+}
+
+/// Checks an ISO 4217 currency code and returns it upper-cased.
+pub fn parse_currency(s: &str) -> Result<String, String> {
+    Currency::new(s, Locale::default()).map(|currency| currency.code.to_string())
+}
+
+impl Currency {
+    pub fn new(code: &str, locale: Locale) -> Result<Self, String> {
+        let upper = code.trim().to_uppercase();
+        CURRENCIES
+            .iter()
+            .find(|(iso, _, _)| *iso == upper)
+            .map(|&(code, minor_units, symbol)| Currency { code, minor_units, symbol, locale })
+            .ok_or_else(|| format!("Invalid currency, expected an ISO 4217 code such as EUR: {}", code))
+    }
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+    /// Digits after the decimal point, e.g. 2 for cents and 0 for yen.
+    pub fn minor_units(&self) -> u32 {
+        self.minor_units
+    }
+    pub fn symbol(&self) -> &'static str {
+        if self.symbol.is_empty() { self.code } else { self.symbol }
+    }
+    /// `amount` rounded to the minor unit, with exactly that many decimals.
+    /// Zero is never negative, so `-0.00` is written as `0.00`.
+    pub fn round(&self, amount: Decimal) -> Decimal {
+        let mut rounded = amount.round_dp(self.minor_units);
+        rounded.rescale(self.minor_units);
+        if rounded.is_zero() {
+            rounded.set_sign_positive(true);
+        }
+        rounded
+    }
+    /// `amount` with the currency symbol, written for the locale. Amounts
+    /// finer than the minor unit, such as rates, keep their extra decimals.
+    pub fn format(&self, amount: Decimal) -> String {
+        self.format_with(amount, self.symbol())
+    }
+    /// Like `format`, with `symbol` in place of the currency's own.
+    pub fn format_with(&self, amount: Decimal, symbol: &str) -> String {
+        let places = self.minor_units.max(amount.normalize().scale());
+        let digits = format!("{:.*}", places as usize, amount.abs());
+        let (whole, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+        let (group, decimal) = self.locale.separators();
+        let mut number = String::new();
+        for (index, digit) in whole.chars().enumerate() {
+            if index > 0 && (whole.len() - index) % 3 == 0 {
+                number.push_str(group);
+            }
+            number.push(digit);
+        }
+        if !fraction.is_empty() {
+            number.push(decimal);
+            number.push_str(fraction);
+        }
+        let sign = if amount.is_sign_negative() && !amount.is_zero() { "-" } else { "" };
+        match self.locale {
+            Locale::En if !symbol.ends_with(|c: char| c.is_alphabetic()) => format!("{}{}{}", sign, symbol, number),
+            Locale::En | Locale::DeCh => format!("{}{} {}", sign, symbol, number),
+            Locale::De | Locale::Fr => format!("{}{} {}", sign, number, symbol),
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.code)
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Currency", 4)?;
+        state.serialize_field("code", self.code)?;
+        state.serialize_field("symbol", self.symbol())?;
+        state.serialize_field("minor_units", &self.minor_units)?;
+        state.serialize_field("locale", &self.locale.to_string())?;
+        state.end()
+    }
+}
@@ -1,16 +1,20 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::str::FromStr;
 
-use chrono::{Duration, NaiveDate};
+use chrono::{Duration, Local, NaiveDate};
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 use rust_decimal::Decimal;
-use inquire::{MultiSelect, InquireError, Confirm, Select, DateSelect};
+use inquire::{MultiSelect, InquireError, Confirm, Select, DateSelect, Text};
+use inquire::validator::Validation;
 use pulldown_cmark::{html, Parser};
 
 use crate::models::{prompt_optional, editor_optional};
-use crate::models::{EntityDeleter, EntityUpdater};
-use crate::models::items::Items;
+use crate::models::{EntityDeleter, EntityUpdater, Tabular};
+use crate::models::credit_note::CreditNote;
+use crate::models::currency::Currency;
+use crate::models::payments::Payments;
+use crate::models::taxes::Taxes;
 use crate::models::template::Template;
 
 use crate::cli::delete::DeleteInvoice;
@@ -23,7 +27,22 @@ pub struct Invoice {
     pub attributes: InvoiceAttrs,
     pub date: String,
     pub notes: Option<String>,
-    pub items: HashMap<Items, i64>,
+    pub items: Vec<InvoiceLine>,
+    pub discount: Option<Discount>,
+    pub payments: Vec<Payments>,
+    pub credits: Vec<CreditNote>,
+    /// The quote this invoice was converted from.
+    pub source_quote: Option<i64>,
+    /// Only set on quotes.
+    pub quote_status: Option<QuoteStatus>,
+    /// Date a quote stops being valid, YYYYMMDD.
+    pub expires: Option<String>,
+    /// Allocated from the company's numbering pattern when the invoice
+    /// reaches the Invoice stage.
+    pub number: Option<String>,
+    /// Every amount on the invoice, its payments and credit notes is in
+    /// this currency.
+    pub currency: Currency,
 }
 
 #[derive(Debug, PartialEq)]
@@ -52,29 +71,88 @@ impl fmt::Display for InvoiceStage {
 impl FromStr for InvoiceStage {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "Quote" => Ok(InvoiceStage::Quote),
-            "Invoice" => Ok(InvoiceStage::Invoice),
+        match s.to_lowercase().as_str() {
+            "quote" => Ok(InvoiceStage::Quote),
+            "invoice" => Ok(InvoiceStage::Invoice),
             _ => Err(format!("Invalid InvoiceStage: {}", s)),
         }
     }
 }
 
+/// Where a quote stands with the client.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum QuoteStatus {
+    Open,
+    Accepted,
+    Declined,
+    Expired,
+}
+
+impl fmt::Display for QuoteStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Open => write!(f, "Open"),
+            Self::Accepted => write!(f, "Accepted"),
+            Self::Declined => write!(f, "Declined"),
+            Self::Expired => write!(f, "Expired"),
+        }
+    }
+}
+
+impl FromStr for QuoteStatus {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Open" => Ok(QuoteStatus::Open),
+            "Accepted" => Ok(QuoteStatus::Accepted),
+            "Declined" => Ok(QuoteStatus::Declined),
+            "Expired" => Ok(QuoteStatus::Expired),
+            _ => Err(format!("Invalid QuoteStatus: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub enum PaidStatus {
     Waiting,
-    PastDue,
+    PastDue { date: String },
     Paid { date: String, check: Option<String> },
     Failed { date: String},
     Refunded { date: String},
 }
 
+impl PaidStatus {
+    /// Maps user input such as `past-due` or `PAID` onto the stored status name.
+    pub fn canonical_name(s: &str) -> Result<&'static str, String> {
+        match s.to_lowercase().replace(['-', '_'], " ").as_str() {
+            "waiting" => Ok("Waiting"),
+            "past due" | "pastdue" => Ok("Past Due"),
+            "paid" => Ok("Paid"),
+            "failed" => Ok("Failed"),
+            "refunded" => Ok("Refunded"),
+            _ => Err(format!("Invalid PaidStatus: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for PaidStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Waiting => write!(f, "Waiting"),
+            Self::PastDue { .. } => write!(f, "Past Due"),
+            Self::Paid { .. } => write!(f, "Paid"),
+            Self::Failed { .. } => write!(f, "Failed"),
+            Self::Refunded { .. } => write!(f, "Refunded"),
+        }
+    }
+}
+
 impl FromStr for PaidStatus {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "Waiting" => Ok(PaidStatus::Waiting),
-            "Past Due" => Ok(PaidStatus::PastDue),
+            "Past Due" => Ok(PaidStatus::PastDue { date: Local::now().format("%Y%m%d").to_string() }),
             "Paid" => {
                 let date = DateSelect::new("Select payment date").prompt().unwrap();
                 let check = prompt_optional("Enter check number if applicable or enter 'None':", "").unwrap();
@@ -93,41 +171,338 @@ impl FromStr for PaidStatus {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// A reduction applied to a single line or to the invoice as a whole.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Discount {
+    Percent(Decimal),
+    Fixed(Decimal),
+}
+
+impl Discount {
+    /// The amount taken off `base`, never more than `base` itself.
+    pub fn amount(&self, base: Decimal, currency: &Currency) -> Decimal {
+        let amount = match self {
+            Self::Percent(percent) => base * percent / Decimal::from(100),
+            Self::Fixed(amount) => *amount,
+        };
+        currency.round(amount).min(base)
+    }
+    /// The discount as shown on a document, with fixed amounts in `currency`.
+    pub fn label(&self, currency: &Currency) -> String {
+        match self {
+            Self::Percent(_) => self.to_string(),
+            Self::Fixed(amount) => currency.format(*amount),
+        }
+    }
+    /// Rejects negative discounts and percentages over 100%.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Percent(value) | Self::Fixed(value) if value.is_sign_negative() => {
+                Err(format!("Discount cannot be negative: {}", self))
+            }
+            Self::Percent(value) if *value > Decimal::ONE_HUNDRED => {
+                Err(format!("Discount cannot be over 100%: {}", self))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Discount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Percent(percent) => write!(f, "{}%", percent),
+            Self::Fixed(amount) => write!(f, "{}", amount),
+        }
+    }
+}
+
+/// Parses `10%` or a plain amount such as `50`. Amounts take no currency
+/// symbol, as they are in the currency of whichever invoice they end up on.
+impl FromStr for Discount {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let parsed = match s.strip_suffix('%') {
+            Some(percent) => percent.trim().parse::<Decimal>().map(Discount::Percent),
+            None => s.parse::<Decimal>().map(Discount::Fixed),
+        };
+        let discount = parsed.map_err(|_| format!("Invalid Discount: {}", s))?;
+        discount.validate()?;
+        Ok(discount)
+    }
+}
+
+pub fn prompt_discount(prompt: &str, default: &str) -> Result<Option<Discount>, InquireError> {
+    let input = Text::new(prompt)
+        .with_default(default)
+        .with_help_message("Enter a percentage (10%) or a fixed amount (50), or 'None'")
+        .with_validator(|input: &str| {
+            if input.trim().is_empty() || input.trim().eq_ignore_ascii_case("None") {
+                return Ok(Validation::Valid);
+            }
+            match Discount::from_str(input) {
+                Ok(_) => Ok(Validation::Valid),
+                Err(err) => Ok(Validation::Invalid(err.into())),
+            }
+        })
+        .prompt()?;
+
+    if input.trim().is_empty() || input.trim().eq_ignore_ascii_case("None") {
+        Ok(None)
+    } else {
+        Discount::from_str(&input)
+            .map(Some)
+            .map_err(|err| InquireError::Custom(err.into()))
+    }
+}
+
+/// Fails on a quantity that is not positive. Refunds go on a credit note.
+pub fn check_quantity(quantity: Decimal) -> Result<Decimal, String> {
+    if quantity > Decimal::ZERO {
+        Ok(quantity)
+    } else {
+        Err(format!("Quantity must be greater than 0, not {}. Use `credit add` to refund a line", quantity))
+    }
+}
+
+/// A line as stored in `items_json`. The name and rate are copied from the
+/// catalog item when the invoice is generated so later edits to the item
+/// do not change historical invoices. Ad-hoc lines have no catalog item.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct InvoiceItem {
-    pub item: i64,
-    pub quantity: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item: Option<i64>,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub rate: Decimal,
+    pub quantity: Decimal,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    /// Copies of the taxes applied to this line, so later edits to a tax or
+    /// the template do not change historical invoices. `None` takes the
+    /// template taxes when the invoice is generated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taxes: Option<Vec<Taxes>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discount: Option<Discount>,
+}
+
+impl InvoiceItem {
+    /// This line with the template taxes pinned when it has none of its own.
+    pub fn with_taxes(mut self, template_taxes: &[Taxes]) -> Self {
+        self.taxes.get_or_insert_with(|| template_taxes.to_vec());
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct InvoiceLine {
+    pub item: Option<i64>,
+    pub name: String,
+    pub description: Option<String>,
+    pub rate: Decimal,
+    pub quantity: Decimal,
+    pub unit: Option<String>,
+    pub taxes: Vec<Taxes>,
+    pub discount: Option<Discount>,
+}
+
+impl InvoiceLine {
+    /// The stored form of this line, with its taxes pinned explicitly.
+    pub fn to_item(&self) -> InvoiceItem {
+        InvoiceItem {
+            item: self.item,
+            name: self.name.clone(),
+            description: self.description.clone(),
+            rate: self.rate,
+            quantity: self.quantity,
+            unit: self.unit.clone(),
+            taxes: Some(self.taxes.clone()),
+            discount: self.discount.clone(),
+        }
+    }
+    fn gross(&self, currency: &Currency) -> Decimal {
+        currency.round(self.rate * self.quantity)
+    }
+    pub(crate) fn discount_amount(&self, currency: &Currency) -> Decimal {
+        self.discount
+            .as_ref()
+            .map(|discount| discount.amount(self.gross(currency), currency))
+            .unwrap_or_default()
+    }
+    pub fn subtotal(&self, currency: &Currency) -> Decimal {
+        self.gross(currency) - self.discount_amount(currency)
+    }
 }
 
 #[derive(Debug, Serialize)]
-struct ItemDetail {
-    name: String,
-    rate: Decimal,
-    quantity: i64,
-    subtotal: Decimal,
+pub(crate) struct ItemDetail {
+    pub name: String,
+    pub description: Option<String>,
+    pub rate: Decimal,
+    pub quantity: Decimal,
+    pub unit: Option<String>,
+    pub discount: Option<String>,
+    pub discount_amount: Decimal,
+    pub subtotal: Decimal,
+    taxes: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct DiscountDetail {
+    label: String,
+    amount: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+struct CreditDetail {
+    number: String,
+    date: String,
+    amount: String,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct TaxDetail {
+    pub name: String,
+    pub rate: Decimal,
+    base: Decimal,
+    pub amount: Decimal,
+}
+
+/// Template-facing details of `lines`, sorted by name.
+pub(crate) fn line_details(lines: &[InvoiceLine], currency: &Currency) -> Vec<ItemDetail> {
+    let mut item_details: Vec<ItemDetail> = lines
+        .iter()
+        .map(|line| ItemDetail {
+            name: line.name.clone(),
+            description: line.description.clone(),
+            rate: line.rate,
+            quantity: line.quantity.normalize(),
+            unit: line.unit.clone(),
+            discount: line.discount.as_ref().map(|discount| discount.label(currency)),
+            discount_amount: line.discount_amount(currency),
+            subtotal: line.subtotal(currency),
+            taxes: line.taxes.iter().map(|tax| tax.name.clone()).collect(),
+        })
+        .collect();
+    item_details.sort_by(|a, b| a.name.cmp(&b.name));
+    item_details
+}
+
+/// Taxes owed on `lines`, with each line's subtotal scaled by `factor`.
+pub(crate) fn tax_details(lines: &[InvoiceLine], factor: Decimal, currency: &Currency) -> Vec<TaxDetail> {
+    let mut bases: BTreeMap<i64, (&Taxes, Decimal)> = BTreeMap::new();
+    for line in lines {
+        for tax in &line.taxes {
+            bases.entry(tax.id).or_insert((tax, Decimal::ZERO)).1 += line.subtotal(currency) * factor;
+        }
+    }
+    bases
+        .into_values()
+        .map(|(tax, base)| TaxDetail {
+            name: tax.name.clone(),
+            rate: tax.rate,
+            base: currency.round(base),
+            amount: currency.round(base * tax.rate / Decimal::from(100)),
+        })
+        .collect()
 }
 
 impl Invoice {
-    fn calculate_subtotals(&self) -> Vec<ItemDetail> {
-        let mut item_details: Vec<ItemDetail> = self.items
+    /// Sum of the line subtotals, before the invoice discount.
+    pub fn calculate_subtotal(&self) -> Decimal {
+        self.items.iter().map(|line| line.subtotal(&self.currency)).sum()
+    }
+    pub(crate) fn calculate_discount(&self) -> Decimal {
+        self.discount
+            .as_ref()
+            .map(|discount| discount.amount(self.calculate_subtotal(), &self.currency))
+            .unwrap_or_default()
+    }
+    /// Share of each line subtotal left after the invoice discount.
+    pub fn discount_factor(&self) -> Decimal {
+        let subtotal = self.calculate_subtotal();
+        if subtotal.is_zero() {
+            Decimal::ONE
+        } else {
+            (subtotal - self.calculate_discount()) / subtotal
+        }
+    }
+    pub(crate) fn calculate_taxes(&self) -> Vec<TaxDetail> {
+        // The invoice discount is spread over every line in proportion to its subtotal.
+        tax_details(&self.items, self.discount_factor(), &self.currency)
+    }
+    fn calculate_net(&self) -> Decimal {
+        self.calculate_subtotal() - self.calculate_discount()
+    }
+    pub fn calculate_total(&self) -> Decimal {
+        self.calculate_net() + self.calculate_taxes().iter().map(|tax| tax.amount).sum::<Decimal>()
+    }
+    pub fn calculate_paid(&self) -> Decimal {
+        self.payments.iter().map(|payment| payment.amount).sum()
+    }
+    /// Sum of the credit notes issued against this invoice, as a positive amount.
+    pub fn calculate_credited(&self) -> Decimal {
+        -self.credits.iter().map(|credit| credit.calculate_total()).sum::<Decimal>()
+    }
+    pub fn balance(&self) -> Decimal {
+        self.calculate_total() - self.calculate_paid() - self.calculate_credited()
+    }
+    /// The balance at the end of `date`, counting only the payments and
+    /// credit notes dated on or before it.
+    pub fn balance_as_of(&self, date: NaiveDate) -> Decimal {
+        let paid = self.payments
             .iter()
-            .map(|(item, &quantity)| ItemDetail {
-                name: item.name.clone(),
-                rate: item.rate,
-                quantity,
-                subtotal: item.rate * Decimal::from(quantity),
-            })
-            .collect();
-        item_details.sort_by(|a, b| a.name.cmp(&b.name));
-        item_details
+            .filter(|payment| payment.payment_date() <= date)
+            .map(|payment| payment.amount)
+            .sum::<Decimal>();
+        let credited = -self.credits
+            .iter()
+            .filter(|credit| credit.credit_date() <= date)
+            .map(|credit| credit.calculate_total())
+            .sum::<Decimal>();
+        self.calculate_total() - paid - credited
     }
-    fn calculate_total(&self) -> Decimal {
-        self.calculate_subtotals().iter().map(|item| item.subtotal).sum()
+    /// The status implied by the payment ledger, if it differs from the current one.
+    /// Refunded invoices keep their status whatever the ledger says.
+    pub fn settled_status(&self) -> Option<PaidStatus> {
+        if matches!(self.attributes.status, PaidStatus::Refunded { .. }) {
+            return None;
+        }
+        let paid = matches!(self.attributes.status, PaidStatus::Paid { .. });
+        if self.balance() <= Decimal::ZERO && !paid {
+            let last = self.payments.iter().max_by(|a, b| a.date.cmp(&b.date))?;
+            Some(PaidStatus::Paid { date: last.date.clone(), check: last.reference.clone() })
+        } else if self.balance() > Decimal::ZERO && paid && !self.payments.is_empty() {
+            Some(PaidStatus::Waiting)
+        } else {
+            None
+        }
+    }
+    /// The invoice number, or the id for quotes and invoices created
+    /// before numbering.
+    pub fn reference(&self) -> String {
+        self.number.clone().unwrap_or_else(|| format!("#{}", self.id))
+    }
+    /// Base name for rendered files, e.g. `INV-2026-0042_20260301.html`.
+    pub fn file_name(&self) -> String {
+        match &self.number {
+            Some(number) => format!("{}_{}.html", number, self.date),
+            None => format!("{}{}_{}.html", self.attributes.stage, self.id, self.date),
+        }
     }
     pub fn issue_date(&self) -> NaiveDate {
         NaiveDate::parse_from_str(&self.date, "%Y%m%d").unwrap()
     }
-    fn due_date(&self) -> NaiveDate {
+    pub fn expiry_date(&self) -> Option<NaiveDate> {
+        self.expires
+            .as_ref()
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+    }
+    pub fn due_date(&self) -> NaiveDate {
         self.issue_date() + Duration::days(self.template.terms.due)
     }
     pub fn summary(&self) -> String {
@@ -135,44 +510,59 @@ impl Invoice {
 
         format!(
             concat!(
-                "Invoice #{}\n",
+                "Invoice {}\n",
                 "Client: {}\n",
                 "Issued: {}\n",
                 "Due: {}\n",
-                "Status: {:?}\n",
-                "Total: ${}\n",
+                "Status: {}\n",
+                "Total: {}\n",
+                "Balance: {}\n",
             ),
-            self.id,
+            self.reference(),
             self.template.client.name,
             self.issue_date().to_string(),
             self.due_date().to_string(),
             self.attributes.status,
-            total
+            self.currency.format(total),
+            self.currency.format(self.balance())
         )
     }
 }
 
 impl fmt::Display for Invoice {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "ID:\t\t{}\n", self.id)?;
+        writeln!(f, "ID:\t\t{}", self.id)?;
+        if let Some(number) = &self.number {
+            writeln!(f, "Number:\t\t{}", number)?;
+        }
         write!(f, "Date:\t\t{}\n\n", self.date)?;
         write!(f, "Template Information:\n{}\n", self.template)?;
         match self.attributes.stage {
             InvoiceStage::Quote => {
-                write!(f, "Stage:\t\tQuote\n")?;
+                writeln!(f, "Stage:\t\tQuote")?;
+                if let Some(quote_status) = &self.quote_status {
+                    writeln!(f, "Quote status:\t{}", quote_status)?;
+                }
+                if let Some(expires) = self.expiry_date() {
+                    writeln!(f, "Expires:\t{}", expires)?;
+                }
             },
             InvoiceStage::Invoice => {
-                write!(f, "Stage:\t\tInvoice\n")?;
+                writeln!(f, "Stage:\t\tInvoice")?;
             }
         }
+        if let Some(quote) = self.source_quote {
+            writeln!(f, "From quote:\t#{}", quote)?;
+        }
+        writeln!(f, "Currency:\t{}", self.currency)?;
 
         write!(f, "Payment status:\t")?;
         match &self.attributes.status {
             PaidStatus::Waiting => {
-                write!(f, "Waiting for payment\n")?;
+                writeln!(f, "Waiting for payment")?;
             },
-            PaidStatus::PastDue => {
-                write!(f, "Payment is past due\n")?;
+            PaidStatus::PastDue { date } => {
+                write!(f, "Payment is past due\nSince:\t\t{}\n", date)?;
             }
             PaidStatus::Paid { date, check } => {
                 write!(f, "Paid\nDate:\t\t{}\n", date)?; 
@@ -188,28 +578,90 @@ impl fmt::Display for Invoice {
             }
         }
         if let Some(notes) = &self.notes {
-            write!(f, "Notes:\n{}\n\n", notes.to_string())?;
+            write!(f, "Notes:\n{}\n\n", notes)?;
         }
 
-        write!(f, "Invoice attributes:\n")?;
-        write!(f, "Show notes:\t\t{}\n", self.attributes.show_notes)?;
+        writeln!(f, "Invoice attributes:")?;
+        writeln!(f, "Show notes:\t\t{}", self.attributes.show_notes)?;
         write!(f, "Show payment methods:\t{}\n\n", self.attributes.show_methods)?;
 
-        write!(f, "Invoice Items:\n")?;
-        write!(f, "Item\t\t\t\t| Rate\t| Quantity\t| Subtotal\n")?;
-        for item in &self.calculate_subtotals() {
-            write!(f, "{}\t| {}\t| ${}\t\t| ${}\n",
+        writeln!(f, "Invoice Items:")?;
+        writeln!(f, "Item\t\t\t\t| Quantity\t| Rate\t\t| Subtotal")?;
+        let money = |amount: Decimal| self.currency.format(amount);
+        for item in &line_details(&self.items, &self.currency) {
+            let quantity = match &item.unit {
+                Some(unit) => format!("{} {}", item.quantity, unit),
+                None => item.quantity.to_string(),
+            };
+            writeln!(f, "{}\t| {}\t| {}\t\t| {}",
                         item.name,
-                        item.quantity,
-                        item.rate,
-                        item.subtotal)?;
+                        quantity,
+                        money(item.rate),
+                        money(item.subtotal))?;
+            if let Some(description) = &item.description {
+                writeln!(f, "  {}", description)?;
+            }
+            if let Some(discount) = &item.discount {
+                writeln!(f, "  Discount {}\t\t\t\t\t| {}", discount, money(-item.discount_amount))?;
+            }
+        }
+        let taxes = self.calculate_taxes();
+        if !taxes.is_empty() || self.discount.is_some() {
+            writeln!(f, "\t\t\t\t\tSubtotal:\t  {}", money(self.calculate_subtotal()))?;
+        }
+        if let Some(discount) = &self.discount {
+            writeln!(f, "\t\t\t\t\tDiscount {}:\t {}", discount.label(&self.currency), money(-self.calculate_discount()))?;
+        }
+        if !taxes.is_empty() {
+            for tax in &taxes {
+                writeln!(f, "\t\t\t\t\t{} ({}%):\t  {}", tax.name, tax.rate, money(tax.amount))?;
+            }
+        }
+        writeln!(f, "\t\t\t\t\tTotal:\t  {}", money(self.calculate_total()))?;
+        if !self.payments.is_empty() {
+            writeln!(f, "\nPayments:")?;
+            for payment in &self.payments {
+                writeln!(f, "{}", payment)?;
+            }
+        }
+        if !self.credits.is_empty() {
+            writeln!(f, "\nCredit notes:")?;
+            for credit in &self.credits {
+                writeln!(f, "ID: {} - {} {} {}", credit.id, credit.reference(), credit.credit_date(), money(credit.calculate_total()))?;
+            }
+        }
+        if !self.payments.is_empty() || !self.credits.is_empty() {
+            writeln!(f, "\t\t\t\t\tBalance:\t  {}", money(self.balance()))?;
         }
-        write!(f, "\t\t\t\t\tTotal:\t  ${}\n", &self.calculate_total())?;
         write!(f, "Due Date: {}", &self.due_date().format("%B %d, %Y").to_string())?;
         Ok(())
     }
 }
 
+impl Tabular for Invoice {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Number", "Date", "Template", "Client", "Stage", "Status", "Currency", "Total", "Balance", "Due"]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.number.clone().unwrap_or_default(),
+            self.issue_date().to_string(),
+            self.template.name.clone(),
+            self.template.client.name.clone(),
+            self.attributes.stage.to_string(),
+            match (&self.attributes.stage, &self.quote_status) {
+                (InvoiceStage::Quote, Some(quote_status)) => quote_status.to_string(),
+                _ => self.attributes.status.to_string(),
+            },
+            self.currency.to_string(),
+            self.currency.round(self.calculate_total()).to_string(),
+            self.currency.round(self.balance()).to_string(),
+            self.due_date().to_string(),
+        ]
+    }
+}
+
 impl EntityDeleter<Invoice> for Invoice {
     type Output = DeleteInvoice;
     fn delete(&self) -> Result<Self::Output, anyhow::Error> {
@@ -224,10 +676,33 @@ impl Serialize for Invoice {
     {
         let mut state = serializer.serialize_struct("Invoice", 4)?;
         state.serialize_field("id", &self.id)?;
+        state.serialize_field("number", &self.reference())?;
         state.serialize_field("template", &self.template)?;
         state.serialize_field("date", &self.issue_date().format("%B %d, %Y").to_string())?;
-        state.serialize_field("items", &self.calculate_subtotals())?;
+        state.serialize_field("currency", &self.currency)?;
+        state.serialize_field("items", &line_details(&self.items, &self.currency))?;
+        state.serialize_field("subtotal", &self.calculate_subtotal())?;
+        if let Some(discount) = &self.discount {
+            state.serialize_field("discount", &DiscountDetail {
+                label: discount.label(&self.currency),
+                amount: self.calculate_discount(),
+            })?;
+        }
+        state.serialize_field("net", &self.calculate_net())?;
+        state.serialize_field("taxes", &self.calculate_taxes())?;
         state.serialize_field("total", &self.calculate_total())?;
+        state.serialize_field("payments", &self.payments)?;
+        state.serialize_field("paid", &self.calculate_paid())?;
+        state.serialize_field("credits", &self.credits
+            .iter()
+            .map(|credit| CreditDetail {
+                number: credit.reference(),
+                date: credit.credit_date().format("%B %d, %Y").to_string(),
+                amount: self.currency.round(-credit.calculate_total()).to_string(),
+            })
+            .collect::<Vec<_>>())?;
+        state.serialize_field("credited", &self.currency.round(self.calculate_credited()).to_string())?;
+        state.serialize_field("balance", &self.balance())?;
         state.serialize_field("due_date", &self.due_date().format("%B %d, %Y").to_string())?;
         state.serialize_field("show_methods", &self.attributes.show_methods)?;
         state.serialize_field("show_notes", &self.attributes.show_notes)?;
@@ -236,9 +711,17 @@ impl Serialize for Invoice {
             InvoiceStage::Invoice => "Invoice",
         };
         state.serialize_field("invoice_stage", &stage_str)?;
+        state.serialize_field("quote_status", &self.quote_status)?;
+        state.serialize_field("expires", &self.expiry_date().map(|date| date.format("%B %d, %Y").to_string()))?;
+        state.serialize_field("source_quote", &self.source_quote)?;
         match &self.attributes.status {
             PaidStatus::Waiting => {}
-            PaidStatus::PastDue => { state.serialize_field("status", "Past Due")?; }
+            PaidStatus::PastDue { date } => {
+                state.serialize_field("status", "Past Due")?;
+                if let Ok(date_fmt) = NaiveDate::parse_from_str(date, "%Y%m%d") {
+                    state.serialize_field("status_date", &date_fmt.format("%B %d, %Y").to_string())?;
+                }
+            }
             PaidStatus::Paid { date, check } => {
                 let date_fmt = NaiveDate::parse_from_str(date, "%Y%m%d").unwrap();
                 state.serialize_field("status", "Paid")?;
@@ -270,7 +753,10 @@ impl EntityUpdater<Invoice> for Invoice {
     type Output = EditInvoice;
     fn update(&self) -> Result<Self::Output, InquireError> {
         println!("{}", self);
-        let fields = vec!["show methods", "show notes", "invoice stage", "payment status", "notes"];
+        let mut fields = vec!["show methods", "show notes", "invoice stage", "payment status", "notes", "discount"];
+        if self.attributes.stage == InvoiceStage::Quote {
+            fields.extend(["quote status", "expires"]);
+        }
         let selected_fields = MultiSelect::new("Select fields to update:", fields).prompt()?;
         let mut edit_invoice = EditInvoice {
             id: self.id,
@@ -279,6 +765,9 @@ impl EntityUpdater<Invoice> for Invoice {
             stage: None,
             status: None,
             notes: None,
+            discount: None,
+            quote_status: None,
+            expires: None,
         };
         for field in selected_fields {
             match field {
@@ -296,14 +785,14 @@ impl EntityUpdater<Invoice> for Invoice {
                 }
                 "invoice stage" => {
                     let stage_select = Select::new("Select invoice stage:", vec!["Quote", "Invoice"]).prompt()?;
-                    let stage = InvoiceStage::from_str(&stage_select)
+                    let stage = InvoiceStage::from_str(stage_select)
                         .map_err(|err| InquireError::Custom(err.to_string().into()))?;
                     edit_invoice.stage = Some(stage);
                 }
                 "payment status" => {
                     let statuses = vec!["Waiting", "Past Due", "Paid", "Failed", "Refunded"];
                     let selected_status = Select::new("Select payment status:", statuses).prompt()?;
-                    let status = PaidStatus::from_str(&selected_status)
+                    let status = PaidStatus::from_str(selected_status)
                         .map_err(|err| InquireError::Custom(err.to_string().into()))?;
                     edit_invoice.status = Some(status)
                 }
@@ -311,9 +800,85 @@ impl EntityUpdater<Invoice> for Invoice {
                     let notes = editor_optional("Enter new notes, or write 'None' to clear", &self.notes.clone().unwrap_or_default())?;
                     edit_invoice.notes = notes;
                 }
+                "discount" => {
+                    let default = self.discount.as_ref().map(|d| d.to_string()).unwrap_or_default();
+                    let discount = prompt_discount("Enter new invoice discount (type 'None' to clear):", &default)?;
+                    edit_invoice.discount = Some(discount);
+                }
+                "quote status" => {
+                    let statuses = vec!["Open", "Accepted", "Declined", "Expired"];
+                    let selected_status = Select::new("Select quote status:", statuses).prompt()?;
+                    let status = QuoteStatus::from_str(selected_status)
+                        .map_err(|err| InquireError::Custom(err.into()))?;
+                    edit_invoice.quote_status = Some(status);
+                }
+                "expires" => {
+                    let expires = DateSelect::new("Quote valid until")
+                        .with_default(self.expiry_date().unwrap_or(self.issue_date()))
+                        .prompt()?;
+                    edit_invoice.expires = Some(expires);
+                }
                 _ => {}
             }
         }
         Ok(edit_invoice)
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A line of `quantity` at `rate`, with the given `(id, rate)` taxes.
+    pub(crate) fn line(rate: &str, quantity: &str, taxes: &[(i64, &str)]) -> InvoiceLine {
+        InvoiceLine {
+            item: None,
+            name: "Service".to_string(),
+            description: None,
+            rate: Decimal::from_str(rate).unwrap(),
+            quantity: Decimal::from_str(quantity).unwrap(),
+            unit: None,
+            taxes: taxes
+                .iter()
+                .map(|(id, rate)| Taxes { id: *id, name: format!("VAT {}%", rate), rate: Decimal::from_str(rate).unwrap() })
+                .collect(),
+            discount: None,
+        }
+    }
+
+    /// An issued USD invoice with `items` and an invoice `discount`.
+    pub(crate) fn invoice(items: Vec<InvoiceLine>, discount: Option<Discount>) -> Invoice {
+        let template = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "name": "Template",
+            "company": { "id": 1, "name": "Seller", "contact": {} },
+            "client": { "id": 1, "name": "Buyer", "contact": {} },
+            "terms": { "id": 1, "name": "Net 30", "due": 30 },
+            "methods": [],
+            "taxes": [],
+        }))
+        .unwrap();
+        Invoice {
+            id: 1,
+            template,
+            attributes: InvoiceAttrs {
+                show_methods: false,
+                show_notes: false,
+                stage: InvoiceStage::Invoice,
+                status: PaidStatus::Waiting,
+            },
+            date: "20261001".to_string(),
+            notes: None,
+            items,
+            discount,
+            payments: Vec::new(),
+            credits: Vec::new(),
+            source_quote: None,
+            quote_status: None,
+            expires: None,
+            number: Some("INV-0001".to_string()),
+            currency: Currency::new("USD", Default::default()).unwrap(),
+        }
+    }
+}
+
@@ -4,22 +4,46 @@ use std::hash::{Hash, Hasher};
 use serde::{Deserialize, Serialize};
 use rust_decimal::Decimal;
 
-use crate::models::{EntityDeleter, EntityUpdater};
+use crate::models::{prompt_optional, EntityDeleter, EntityUpdater, Tabular};
 use crate::cli::edit::EditItem;
 use crate::cli::delete::DeleteItem;
 
 use inquire::{MultiSelect, Text, InquireError};
 
+/// Decimals kept on catalog rates, the most any ISO 4217 currency has, and
+/// on tax rates such as 8.875%. Item rates are rounded to the invoice
+/// currency when put on a line.
+pub const RATE_PLACES: u32 = 4;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Items {
     pub id: i64,
     pub name: String,
     pub rate: Decimal,
+    pub unit: Option<String>,
 }
 
 impl fmt::Display for Items {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} (Rate: {})", self.name, self.rate)
+        match &self.unit {
+            Some(unit) => write!(f, "{} (Rate: {} / {})", self.name, self.rate, unit),
+            None => write!(f, "{} (Rate: {})", self.name, self.rate),
+        }
+    }
+}
+
+impl Tabular for Items {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Rate", "Unit"]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            // Cents at least, and every stored place that is not a trailing zero.
+            format!("{:.*}", self.rate.normalize().scale().max(2) as usize, self.rate),
+            self.unit.clone().unwrap_or_default(),
+        ]
     }
 }
 
@@ -34,13 +58,14 @@ impl EntityUpdater<Items> for Items {
     type Output = EditItem;
     fn update(&self) -> Result<Self::Output, InquireError> {
         println!("{}", self);
-        let fields = vec!["name", "rate"];
+        let fields = vec!["name", "rate", "unit"];
         let selected_fields = MultiSelect::new("Select fields to update:", fields)
             .prompt()?;
         let mut edit_item = EditItem{
             id: self.id,
             name: None,
-            rate: None
+            rate: None,
+            unit: None
         };
         for field in selected_fields {
             match field {
@@ -58,6 +83,13 @@ impl EntityUpdater<Items> for Items {
                         .ok();
                     edit_item.rate = rate;
                 },
+                "unit" => {
+                    let unit = prompt_optional(
+                        "Enter new unit, e.g. hours, days, each (type 'None' to clear):",
+                        self.unit.as_deref().unwrap_or("")
+                    )?;
+                    edit_item.unit = Some(unit.filter(|unit| !unit.trim().is_empty()));
+                },
                 _ => {}
             }
         }
@@ -1,37 +1,576 @@
 use std::fmt;
 use std::path::PathBuf;
+use std::str::FromStr;
 
+use clap::ValueEnum;
+use rust_decimal::Decimal;
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 use base64::{engine::general_purpose::STANDARD, Engine};
 use infer;
 
-use crate::models::{prompt_optional, EntityDeleter, EntityUpdater};
+use crate::models::{prompt_optional, EntityDeleter, EntityUpdater, Tabular};
+use crate::models::contact::parse_country;
+use crate::models::currency::Currency;
 use crate::cli::edit::EditMethod;
 use crate::cli::delete::DeleteMethod;
 
-use inquire::{Text, InquireError, MultiSelect};
+use inquire::{Text, InquireError, MultiSelect, Select};
+use inquire::validator::Validation;
 
-#[derive(Debug, Deserialize)]
+/// What a payment method is, which decides the fields it has and how
+/// layouts show it. SEPA transfers and Swiss QR-bills carry bank details
+/// that a payment QR code is generated from for each invoice.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum MethodKind {
+    /// A link or an uploaded QR image
+    #[default]
+    Other,
+    /// A SEPA credit transfer to an IBAN
+    Sepa,
+    /// A Swiss QR-bill, printed as a payment part after the invoice
+    Qrbill,
+    /// A bank transfer to an account and routing number
+    Bank,
+    /// A link to a card payment page
+    Card,
+    /// A PayPal email address or paypal.me link
+    Paypal,
+    /// A check made out and mailed to the company
+    Check,
+    /// Cash, with optional instructions
+    Cash,
+    /// A crypto wallet address on a given network
+    Crypto,
+}
+
+impl MethodKind {
+    /// The fields a method of this kind is described by, each with whether
+    /// it is required. Any method can also have instructions and a QR image.
+    pub fn fields(&self) -> &'static [(&'static str, bool)] {
+        match self {
+            MethodKind::Other => &[("link", false)],
+            MethodKind::Sepa => &[("beneficiary", true), ("iban", true), ("bic", false)],
+            MethodKind::Qrbill => &[
+                ("beneficiary", true),
+                ("iban", true),
+                ("reference_type", false),
+                ("street", false),
+                ("postcode", true),
+                ("town", true),
+                ("country", true),
+            ],
+            MethodKind::Bank => &[
+                ("beneficiary", true),
+                ("bank_name", false),
+                ("account_number", true),
+                ("routing_number", false),
+                ("bic", false),
+            ],
+            MethodKind::Card => &[("link", true)],
+            MethodKind::Paypal => &[("paypal", true)],
+            MethodKind::Check => &[
+                ("beneficiary", true),
+                ("street", false),
+                ("postcode", false),
+                ("town", false),
+                ("country", false),
+            ],
+            MethodKind::Cash => &[],
+            MethodKind::Crypto => &[("network", true), ("wallet", true)],
+        }
+    }
+    /// Fails naming each field a method of this kind requires that `has`
+    /// says is missing.
+    pub fn require(&self, name: &str, mut has: impl FnMut(&str) -> bool) -> Result<(), anyhow::Error> {
+        let missing: Vec<&str> = self
+            .fields()
+            .iter()
+            .filter(|(field, required)| *required && !has(field))
+            .map(|(field, _)| *field)
+            .collect();
+        if !missing.is_empty() {
+            anyhow::bail!("Payment method {} is a {} method and needs: {}", name, self, missing.join(", "));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for MethodKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MethodKind::Other => write!(f, "other"),
+            MethodKind::Sepa => write!(f, "sepa"),
+            MethodKind::Qrbill => write!(f, "qrbill"),
+            MethodKind::Bank => write!(f, "bank"),
+            MethodKind::Card => write!(f, "card"),
+            MethodKind::Paypal => write!(f, "paypal"),
+            MethodKind::Check => write!(f, "check"),
+            MethodKind::Cash => write!(f, "cash"),
+            MethodKind::Crypto => write!(f, "crypto"),
+        }
+    }
+}
+
+impl FromStr for MethodKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "other" => Ok(MethodKind::Other),
+            "sepa" => Ok(MethodKind::Sepa),
+            "qrbill" => Ok(MethodKind::Qrbill),
+            "bank" => Ok(MethodKind::Bank),
+            "card" => Ok(MethodKind::Card),
+            "paypal" => Ok(MethodKind::Paypal),
+            "check" => Ok(MethodKind::Check),
+            "cash" => Ok(MethodKind::Cash),
+            "crypto" => Ok(MethodKind::Crypto),
+            _ => Err(format!(
+                "Invalid payment method kind, expected other, sepa, qrbill, bank, card, paypal, check, cash or crypto: {}",
+                s
+            )),
+        }
+    }
+}
+
+/// How a Swiss QR-bill refers to the invoice it pays.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ReferenceType {
+    /// QR reference of 27 digits, only with a QR-IBAN
+    Qrr,
+    /// ISO 11649 creditor reference starting with RF
+    Scor,
+    /// No reference, only with a regular IBAN
+    Non,
+}
+
+impl fmt::Display for ReferenceType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReferenceType::Qrr => write!(f, "QRR"),
+            ReferenceType::Scor => write!(f, "SCOR"),
+            ReferenceType::Non => write!(f, "NON"),
+        }
+    }
+}
+
+impl FromStr for ReferenceType {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "QRR" => Ok(ReferenceType::Qrr),
+            "SCOR" => Ok(ReferenceType::Scor),
+            "NON" => Ok(ReferenceType::Non),
+            _ => Err(format!("Invalid reference type, expected QRR, SCOR or NON: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
 pub struct Methods {
     pub id: i64,
     pub name: String,
     pub link: Option<String>,
     pub qr: Option<Vec<u8>>,
+    pub kind: MethodKind,
+    pub iban: Option<String>,
+    pub bic: Option<String>,
+    /// Account holder named in a SEPA transfer or a QR-bill.
+    pub beneficiary: Option<String>,
+    /// QR-bill reference, QRR with a QR-IBAN and SCOR otherwise when unset.
+    pub reference_type: Option<ReferenceType>,
+    /// The account holder's address, which a QR-bill states.
+    pub street: Option<String>,
+    pub postcode: Option<String>,
+    pub town: Option<String>,
+    pub country: Option<String>,
+    pub bank_name: Option<String>,
+    pub account_number: Option<String>,
+    /// Routing number, sort code or other bank code that goes with the
+    /// account number.
+    pub routing_number: Option<String>,
+    /// PayPal email address or `https://paypal.me/` link.
+    pub paypal: Option<String>,
+    pub wallet: Option<String>,
+    /// Crypto asset the wallet takes, e.g. BTC.
+    pub network: Option<String>,
+    /// Free text shown with any kind of method, e.g. where to pay cash.
+    pub instructions: Option<String>,
+}
+
+/// The fields of a method that hold free text, each checked by
+/// `parse_field`.
+pub const TEXT_FIELDS: [&str; 15] = [
+    "link",
+    "iban",
+    "bic",
+    "beneficiary",
+    "street",
+    "postcode",
+    "town",
+    "country",
+    "bank_name",
+    "account_number",
+    "routing_number",
+    "paypal",
+    "wallet",
+    "network",
+    "instructions",
+];
+
+/// Checks an IBAN's length and check digits and returns it without spaces,
+/// upper-cased.
+pub fn parse_iban(s: &str) -> Result<String, String> {
+    let iban: String = s.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase();
+    let well_formed = (15..=34).contains(&iban.len())
+        && iban.chars().all(|c| c.is_ascii_alphanumeric())
+        && iban[..2].chars().all(|c| c.is_ascii_uppercase())
+        && iban[2..4].chars().all(|c| c.is_ascii_digit());
+    if !well_formed {
+        return Err(format!("Invalid IBAN, expected a country code, check digits and account number: {}", s));
+    }
+    // Moved to the end with letters as numbers from A = 10, a valid IBAN is 1 mod 97.
+    let remainder = iban[4..].chars().chain(iban[..4].chars()).fold(0u32, |remainder, c| {
+        let value = c.to_digit(36).unwrap_or_default();
+        if value < 10 {
+            (remainder * 10 + value) % 97
+        } else {
+            (remainder * 100 + value) % 97
+        }
+    });
+    if remainder == 1 {
+        Ok(iban)
+    } else {
+        Err(format!("Invalid IBAN, the check digits do not match: {}", s))
+    }
+}
+
+/// Checks an 8 or 11 character BIC and returns it upper-cased.
+pub fn parse_bic(s: &str) -> Result<String, String> {
+    let bic = s.trim().to_uppercase();
+    if matches!(bic.len(), 8 | 11)
+        && bic[..6].chars().all(|c| c.is_ascii_uppercase())
+        && bic[6..].chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        Ok(bic)
+    } else {
+        Err(format!("Invalid BIC, expected 8 or 11 letters and digits such as DEUTDEFF: {}", s))
+    }
+}
+
+/// Whether `iban` is a Swiss or Liechtenstein QR-IBAN, whose institution
+/// ID in place of a bank's clearing number is 30000 to 31999.
+pub fn is_qr_iban(iban: &str) -> bool {
+    matches!(iban.get(..2), Some("CH" | "LI"))
+        && iban.get(4..9).and_then(|id| id.parse::<u32>().ok()).is_some_and(|id| (30000..=31999).contains(&id))
+}
+
+/// Trims `s` and checks it has 1 to `max` characters.
+fn bounded(s: &str, what: &str, max: usize) -> Result<String, String> {
+    let text = s.trim();
+    if !text.is_empty() && text.chars().count() <= max {
+        Ok(text.to_string())
+    } else {
+        Err(format!("Invalid {}, expected 1 to {} characters: {}", what, max, s))
+    }
+}
+
+/// Checks a SEPA beneficiary name, which the transfer limits to 70 characters.
+pub fn parse_beneficiary(s: &str) -> Result<String, String> {
+    bounded(s, "beneficiary", 70)
+}
+
+/// Checks a street and building number, at most 70 characters on a QR-bill.
+pub fn parse_street(s: &str) -> Result<String, String> {
+    bounded(s, "street", 70)
+}
+
+/// Checks a postcode, at most 16 characters on a QR-bill.
+pub fn parse_postcode(s: &str) -> Result<String, String> {
+    bounded(s, "postcode", 16)
+}
+
+/// Checks a town, at most 35 characters on a QR-bill.
+pub fn parse_town(s: &str) -> Result<String, String> {
+    bounded(s, "town", 35)
+}
+
+/// Checks a link to pay at, which must be an http or https URL.
+pub fn parse_link(s: &str) -> Result<String, String> {
+    let link = s.trim();
+    let rest = link.strip_prefix("https://").or_else(|| link.strip_prefix("http://")).unwrap_or_default();
+    if !rest.is_empty() && !link.contains(char::is_whitespace) {
+        Ok(link.to_string())
+    } else {
+        Err(format!("Invalid link, expected an http or https URL: {}", s))
+    }
+}
+
+/// Checks a bank's name, at most 70 characters.
+pub fn parse_bank_name(s: &str) -> Result<String, String> {
+    bounded(s, "bank name", 70)
+}
+
+/// Checks `s` has `min` to `max` letters and digits, grouped by single
+/// spaces or dashes.
+fn code(s: &str, what: &str, min: usize, max: usize) -> Result<String, String> {
+    let text = s.trim();
+    let count = text.chars().filter(char::is_ascii_alphanumeric).count();
+    let grouped = text.split([' ', '-']).all(|group| !group.is_empty() && group.chars().all(|c| c.is_ascii_alphanumeric()));
+    if grouped && (min..=max).contains(&count) {
+        Ok(text.to_uppercase())
+    } else {
+        Err(format!("Invalid {}, expected {} to {} letters and digits: {}", what, min, max, s))
+    }
+}
+
+/// Checks a bank account number, 4 to 34 letters and digits.
+pub fn parse_account_number(s: &str) -> Result<String, String> {
+    code(s, "account number", 4, 34)
+}
+
+/// Checks a routing number or sort code, 4 to 15 letters and digits.
+pub fn parse_routing_number(s: &str) -> Result<String, String> {
+    code(s, "routing number", 4, 15)
+}
+
+/// Checks a PayPal email address or paypal.me name and returns the address,
+/// or the name as a `https://paypal.me/` link.
+pub fn parse_paypal(s: &str) -> Result<String, String> {
+    let paypal = s.trim();
+    if let Some((user, domain)) = paypal.split_once('@') {
+        let (_, tld) = domain.rsplit_once('.').unwrap_or_default();
+        if !user.is_empty() && !tld.is_empty() && !paypal.contains(char::is_whitespace) {
+            return Ok(paypal.to_string());
+        }
+    } else {
+        let name = paypal.trim_start_matches("https://").trim_start_matches("http://").trim_start_matches("www.");
+        let name = name.strip_prefix("paypal.me/").or_else(|| name.strip_prefix("paypal.com/paypalme/")).unwrap_or(name);
+        let name = name.trim_end_matches('/');
+        if (1..=20).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Ok(format!("https://paypal.me/{}", name));
+        }
+    }
+    Err(format!("Invalid PayPal account, expected an email address or a paypal.me link: {}", s))
+}
+
+/// Checks a crypto wallet address, 20 to 128 letters and digits.
+pub fn parse_wallet(s: &str) -> Result<String, String> {
+    let wallet = s.trim();
+    if (20..=128).contains(&wallet.len()) && wallet.chars().all(|c| c.is_ascii_alphanumeric()) {
+        Ok(wallet.to_string())
+    } else {
+        Err(format!("Invalid wallet address, expected 20 to 128 letters and digits: {}", s))
+    }
+}
+
+/// Checks a crypto asset code such as BTC and returns it upper-cased.
+pub fn parse_network(s: &str) -> Result<String, String> {
+    let network = s.trim().to_uppercase();
+    if (2..=10).contains(&network.len()) && network.chars().all(|c| c.is_ascii_alphanumeric()) {
+        Ok(network)
+    } else {
+        Err(format!("Invalid network, expected a code of 2 to 10 letters and digits such as BTC: {}", s))
+    }
+}
+
+/// Checks payment instructions, at most 280 characters.
+pub fn parse_instructions(s: &str) -> Result<String, String> {
+    bounded(s, "instructions", 280)
+}
+
+/// Checks the value of the text field `field`, one of `TEXT_FIELDS`.
+pub fn parse_field(field: &str, s: &str) -> Result<String, String> {
+    match field {
+        "link" => parse_link(s),
+        "iban" => parse_iban(s),
+        "bic" => parse_bic(s),
+        "beneficiary" => parse_beneficiary(s),
+        "street" => parse_street(s),
+        "postcode" => parse_postcode(s),
+        "town" => parse_town(s),
+        "country" => parse_country(s),
+        "bank_name" => parse_bank_name(s),
+        "account_number" => parse_account_number(s),
+        "routing_number" => parse_routing_number(s),
+        "paypal" => parse_paypal(s),
+        "wallet" => parse_wallet(s),
+        "network" => parse_network(s),
+        "instructions" => parse_instructions(s),
+        _ => Err(format!("Unknown payment method field: {}", field)),
+    }
+}
+
+/// How a field is labelled when shown and when prompted for.
+fn label(field: &str) -> (&'static str, &'static str) {
+    match field {
+        "link" => ("Link", "link"),
+        "iban" => ("IBAN", "IBAN"),
+        "bic" => ("BIC", "BIC"),
+        "beneficiary" => ("Beneficiary", "beneficiary"),
+        "reference_type" => ("Reference", "QR-bill reference type"),
+        "street" => ("Street", "street"),
+        "postcode" => ("Postcode", "postcode"),
+        "town" => ("Town", "town"),
+        "country" => ("Country", "country code"),
+        "bank_name" => ("Bank", "bank name"),
+        "account_number" => ("Account", "account number"),
+        "routing_number" => ("Routing", "routing number or sort code"),
+        "paypal" => ("PayPal", "PayPal email address or paypal.me link"),
+        "wallet" => ("Wallet", "wallet address"),
+        "network" => ("Network", "network, e.g. BTC"),
+        "instructions" => ("Instructions", "instructions"),
+        _ => ("", ""),
+    }
+}
+
+impl Methods {
+    /// The value of `field` as text, None when it is unset.
+    pub fn value(&self, field: &str) -> Option<String> {
+        match field {
+            "link" => self.link.clone(),
+            "iban" => self.iban.clone(),
+            "bic" => self.bic.clone(),
+            "beneficiary" => self.beneficiary.clone(),
+            "reference_type" => self.reference_type.map(|reference_type| reference_type.to_string()),
+            "street" => self.street.clone(),
+            "postcode" => self.postcode.clone(),
+            "town" => self.town.clone(),
+            "country" => self.country.clone(),
+            "bank_name" => self.bank_name.clone(),
+            "account_number" => self.account_number.clone(),
+            "routing_number" => self.routing_number.clone(),
+            "paypal" => self.paypal.clone(),
+            "wallet" => self.wallet.clone(),
+            "network" => self.network.clone(),
+            "instructions" => self.instructions.clone(),
+            _ => None,
+        }
+    }
+    /// The account a transfer is paid into: the IBAN of a SEPA transfer or a
+    /// QR-bill, the account number of a bank transfer.
+    pub fn account(&self) -> Option<&str> {
+        match self.kind {
+            MethodKind::Sepa | MethodKind::Qrbill => self.iban.as_deref(),
+            MethodKind::Bank => self.account_number.as_deref(),
+            _ => None,
+        }
+    }
+    /// Text shown under the method's name, by kind, followed by the
+    /// instructions.
+    pub fn details(&self) -> Vec<String> {
+        let labelled = |prefix: &str, value: &Option<String>| value.as_ref().map(|value| format!("{} {}", prefix, value));
+        let mut lines: Vec<String> = match self.kind {
+            MethodKind::Sepa | MethodKind::Qrbill => [
+                self.beneficiary.clone(),
+                labelled("IBAN", &self.iban),
+                labelled("BIC", &self.bic),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            MethodKind::Bank => [
+                self.beneficiary.clone(),
+                self.bank_name.clone(),
+                labelled("Account", &self.account_number),
+                labelled("Routing", &self.routing_number),
+                labelled("SWIFT/BIC", &self.bic),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            MethodKind::Check => {
+                let town = [self.postcode.as_deref(), self.town.as_deref()].into_iter().flatten().collect::<Vec<_>>().join(" ");
+                let address = [self.street.clone(), Some(town).filter(|town| !town.is_empty()), self.country.clone()]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>();
+                [labelled("Payable to", &self.beneficiary), (!address.is_empty()).then(|| format!("Mail to {}", address.join(", ")))]
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            }
+            MethodKind::Paypal => self.paypal.iter().cloned().collect(),
+            MethodKind::Crypto => [self.network.clone(), self.wallet.clone()].into_iter().flatten().collect(),
+            MethodKind::Cash => Vec::new(),
+            MethodKind::Other | MethodKind::Card => self.link.iter().cloned().collect(),
+        };
+        lines.extend(self.instructions.clone());
+        lines
+    }
+    /// The EPC069-12 payload of a SEPA transfer of `amount` euros with
+    /// `reference` as the remittance information. None for other methods
+    /// and for amounts that leave nothing to pay.
+    pub fn epc_payload(&self, amount: Decimal, currency: &Currency, reference: &str) -> Result<Option<String>, String> {
+        if self.kind != MethodKind::Sepa || amount <= Decimal::ZERO {
+            return Ok(None);
+        }
+        if currency.code() != "EUR" {
+            return Err(format!("SEPA payment codes request euros, the invoice is in {}", currency));
+        }
+        let (Some(iban), Some(beneficiary)) = (&self.iban, &self.beneficiary) else {
+            return Err(format!("SEPA method {} needs an IBAN and a beneficiary", self.name));
+        };
+        if amount > Decimal::new(99_999_999_999, 2) {
+            return Err(format!("{:.2} is more than a SEPA payment code can request", amount));
+        }
+        let remittance: String = reference.chars().take(140).collect();
+        let lines = [
+            "BCD",
+            "002",
+            "1",
+            "SCT",
+            self.bic.as_deref().unwrap_or(""),
+            beneficiary,
+            iban,
+            &format!("EUR{}", currency.round(amount)),
+            "",
+            "",
+            &remittance,
+        ];
+        Ok(Some(lines.join("\n")))
+    }
 }
 
 impl fmt::Display for Methods {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "ID:\t\t{}\n", self.id)?;
-        write!(f, "Name:\t\t{}\n", self.name)?;
+        writeln!(f, "ID:\t\t{}", self.id)?;
+        writeln!(f, "Name:\t\t{}", self.name)?;
+        writeln!(f, "Kind:\t\t{}", self.kind)?;
+        for (field, _) in self.kind.fields().iter().filter(|(field, _)| *field != "link") {
+            let heading = format!("{}:", label(field).0);
+            let tabs = if heading.len() < 8 { "\t\t" } else { "\t" };
+            let unset = if *field == "reference_type" { "Default" } else { "None" };
+            writeln!(f, "{}{}{}", heading, tabs, self.value(field).as_deref().unwrap_or(unset))?;
+        }
+        if let Some(ref instructions) = self.instructions {
+            writeln!(f, "Instructions:\t{}", instructions)?;
+        }
         if let Some(ref link) = self.link {
-            write!(f, "Link:\t\t{}\n", link)?;
+            writeln!(f, "Link:\t\t{}", link)?;
         } else {
-            write!(f, "Link:\t\tNone\n")?;
+            writeln!(f, "Link:\t\tNone")?;
         }
         write!(f, "Has QR:\t\t{}", self.qr.is_some())
     }
 }
 
+impl Tabular for Methods {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Kind", "Link", "Has QR"]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.kind.to_string(),
+            self.link.clone().unwrap_or_default(),
+            self.qr.is_some().to_string(),
+        ]
+    }
+}
+
 impl EntityDeleter<Methods> for Methods {
     type Output = DeleteMethod;
     fn delete(&self) -> Result<Self::Output, anyhow::Error> {
@@ -43,15 +582,20 @@ impl EntityUpdater<Methods> for Methods {
     type Output = EditMethod;
     fn update(&self) -> Result<Self::Output, InquireError> {
         println!("{}", self);
-        let fields = vec!["name", "link", "qr"];
-        let mut edit_method = EditMethod{
+        let mut fields = vec!["name", "kind"];
+        fields.extend(self.kind.fields().iter().map(|(field, _)| *field));
+        fields.extend(["instructions", "qr"]);
+        let mut edit_method = EditMethod {
             id: self.id,
-            name: None,
-            link: None,
-            qr: None
+            ..Default::default()
         };
-        let selected_fields = MultiSelect::new("Select fields to update:", fields).prompt()?;
-        for field in selected_fields {
+        let mut selected_fields = MultiSelect::new("Select fields to update:", fields).prompt()?;
+        // Changing the kind asks for the new kind's fields too.
+        let mut index = 0;
+        while index < selected_fields.len() {
+            let field = selected_fields[index];
+            index += 1;
+            let kind = edit_method.kind.unwrap_or(self.kind);
             match field {
                 "name" => {
                     let name = Text::new("Enter new name:")
@@ -59,10 +603,10 @@ impl EntityUpdater<Methods> for Methods {
                         .prompt()?;
                     edit_method.name = Some(name);
                 },
-                "link" => {
+                "link" if kind != MethodKind::Card => {
                     edit_method.link = prompt_optional(
                         "Enter new link (type 'None' to clear):",
-                        &self.link.as_deref().unwrap_or("")
+                        self.link.as_deref().unwrap_or("")
                         )?;
                 }
                 "qr" => {
@@ -74,7 +618,39 @@ impl EntityUpdater<Methods> for Methods {
                         edit_method.qr = Some(qr);
                     }
                 }
-                _ => {}
+                "kind" => {
+                    let kinds = MethodKind::value_variants().to_vec();
+                    let cursor = kinds.iter().position(|kind| *kind == self.kind).unwrap_or_default();
+                    let kind = Select::new("Select kind:", kinds)
+                        .with_starting_cursor(cursor)
+                        .prompt()?;
+                    for (field, _) in kind.fields() {
+                        if !selected_fields.contains(field) && (kind != self.kind || self.value(field).is_none()) {
+                            selected_fields.push(field);
+                        }
+                    }
+                    edit_method.kind = Some(kind);
+                }
+                "reference_type" => {
+                    let types = vec![ReferenceType::Qrr, ReferenceType::Scor, ReferenceType::Non];
+                    let cursor = types.iter().position(|t| Some(*t) == self.reference_type).unwrap_or(1);
+                    let reference_type = Select::new("Select QR-bill reference type:", types)
+                        .with_starting_cursor(cursor)
+                        .prompt()?;
+                    edit_method.reference_type = Some(reference_type);
+                }
+                field => {
+                    let value = Text::new(&format!("Enter new {}:", label(field).1))
+                        .with_default(self.value(field).as_deref().unwrap_or(""))
+                        .with_validator(move |input: &str| match parse_field(field, input) {
+                            Ok(_) => Ok(Validation::Valid),
+                            Err(err) => Ok(Validation::Invalid(err.into())),
+                        })
+                        .prompt()?;
+                    if let Some(slot) = edit_method.text_field(field) {
+                        *slot = parse_field(field, &value).ok();
+                    }
+                }
             }
         }
         Ok(edit_method)
@@ -86,9 +662,25 @@ impl Serialize for Methods {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("Method", 3)?;
+        let mut state = serializer.serialize_struct("Method", 20)?;
         state.serialize_field("id", &self.id)?;
         state.serialize_field("name", &self.name)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("iban", &self.iban)?;
+        state.serialize_field("bic", &self.bic)?;
+        state.serialize_field("beneficiary", &self.beneficiary)?;
+        state.serialize_field("reference_type", &self.reference_type)?;
+        state.serialize_field("street", &self.street)?;
+        state.serialize_field("postcode", &self.postcode)?;
+        state.serialize_field("town", &self.town)?;
+        state.serialize_field("country", &self.country)?;
+        state.serialize_field("bank_name", &self.bank_name)?;
+        state.serialize_field("account_number", &self.account_number)?;
+        state.serialize_field("routing_number", &self.routing_number)?;
+        state.serialize_field("paypal", &self.paypal)?;
+        state.serialize_field("wallet", &self.wallet)?;
+        state.serialize_field("network", &self.network)?;
+        state.serialize_field("instructions", &self.instructions)?;
         if let Some(ref link) = self.link {
             state.serialize_field("link", link)?;
         } else {
@@ -111,4 +703,50 @@ impl Serialize for Methods {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::currency::Locale;
 
+    fn sepa() -> Methods {
+        Methods {
+            name: "Bank transfer".to_string(),
+            kind: MethodKind::Sepa,
+            iban: Some("DE89370400440532013000".to_string()),
+            bic: Some("COBADEFFXXX".to_string()),
+            beneficiary: Some("Acme GmbH".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn epc_payload_follows_epc069_12() {
+        let euro = Currency::new("EUR", Locale::default()).unwrap();
+        let payload = sepa().epc_payload(Decimal::new(12345, 1), &euro, "INV-2026-0042").unwrap();
+        assert_eq!(
+            payload.as_deref(),
+            Some("BCD\n002\n1\nSCT\nCOBADEFFXXX\nAcme GmbH\nDE89370400440532013000\nEUR1234.50\n\n\nINV-2026-0042"),
+        );
+    }
+
+    #[test]
+    fn epc_payload_needs_euros_and_a_balance() {
+        let euro = Currency::new("EUR", Locale::default()).unwrap();
+        let franc = Currency::new("CHF", Locale::default()).unwrap();
+        assert_eq!(sepa().epc_payload(Decimal::ZERO, &euro, "INV-1"), Ok(None));
+        assert!(sepa().epc_payload(Decimal::ONE, &franc, "INV-1").is_err());
+    }
+
+    #[test]
+    fn edits_keep_the_fields_the_kind_requires() {
+        let method = sepa();
+        let mut rename = EditMethod { id: 1, name: Some("Transfer".to_string()), ..Default::default() };
+        assert!(rename.check(&method).is_ok());
+        let mut to_crypto = EditMethod { id: 1, kind: Some(MethodKind::Crypto), ..Default::default() };
+        let err = to_crypto.check(&method).unwrap_err();
+        assert_eq!(err.to_string(), "Payment method Bank transfer is a crypto method and needs: network, wallet");
+        to_crypto.network = Some("BTC".to_string());
+        to_crypto.wallet = Some("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq".to_string());
+        assert!(to_crypto.check(&method).is_ok());
+    }
+}
@@ -6,10 +6,19 @@ pub mod config;
 pub mod client;
 pub mod company;
 pub mod contact;
+pub mod credit_note;
+pub mod currency;
 pub mod invoice;
 pub mod template;
 pub mod items;
 pub mod methods;
+pub mod numbering;
+pub mod page;
+pub mod payments;
+pub mod recurring;
+pub mod report;
+pub mod settings;
+pub mod taxes;
 pub mod terms;
 
 pub struct ShortList {
@@ -33,6 +42,12 @@ pub trait EntityDeleter<T> {
     fn delete(&self) -> Result<Self::Output, anyhow::Error>;
 }
 
+/// Flattens an entity into the columns shown by `list` in table and csv form.
+pub trait Tabular {
+    fn headers() -> Vec<&'static str>;
+    fn row(&self) -> Vec<String>;
+}
+
 pub fn prompt_optional(prompt: &str, default: &str) -> Result<Option<String>, InquireError> {
     let input = Text::new(prompt)
         .with_default(default)
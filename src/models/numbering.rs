@@ -0,0 +1,86 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// How a company numbers its invoices, e.g. `INV-{year}-{seq:4}`.
+///
+/// `{seq}` is the counter and is required; `{seq:N}` zero-pads it to N
+/// digits. `{year}` is the year of the invoice date, and a pattern that
+/// contains it restarts the counter at 1 every year. Everything else is
+/// copied as is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberPattern {
+    pattern: String,
+}
+
+impl NumberPattern {
+    pub const DEFAULT: &'static str = "INV-{year}-{seq:4}";
+
+    pub fn resets_yearly(&self) -> bool {
+        self.pattern.contains("{year}")
+    }
+    pub fn format(&self, year: i32, seq: i64) -> String {
+        let mut number = String::new();
+        let mut rest = self.pattern.as_str();
+        while let Some(start) = rest.find('{') {
+            number.push_str(&rest[..start]);
+            let end = rest[start..].find('}').map(|end| start + end).unwrap_or(rest.len() - 1);
+            match &rest[start + 1..end] {
+                "year" => number.push_str(&year.to_string()),
+                "seq" => number.push_str(&seq.to_string()),
+                token => {
+                    let width = token
+                        .strip_prefix("seq:")
+                        .and_then(|width| width.parse::<usize>().ok())
+                        .unwrap_or(0);
+                    number.push_str(&format!("{:0width$}", seq, width = width));
+                }
+            }
+            rest = &rest[end + 1..];
+        }
+        number.push_str(rest);
+        number
+    }
+}
+
+impl Default for NumberPattern {
+    fn default() -> Self {
+        NumberPattern { pattern: Self::DEFAULT.to_string() }
+    }
+}
+
+impl fmt::Display for NumberPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+impl FromStr for NumberPattern {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.contains(['/', '\\']) {
+            return Err(format!("Invalid number pattern, numbers are used in file names: {}", s));
+        }
+        let mut has_seq = false;
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| format!("Invalid number pattern, unclosed '{{': {}", s))?;
+            match &rest[start + 1..end] {
+                "year" => {}
+                "seq" => has_seq = true,
+                token => match token.strip_prefix("seq:").map(|width| width.parse::<usize>()) {
+                    Some(Ok(width)) if width <= 12 => has_seq = true,
+                    _ => return Err(format!("Invalid number pattern token {{{}}}: {}", token, s)),
+                },
+            }
+            rest = &rest[end + 1..];
+        }
+        if !has_seq {
+            return Err(format!("Invalid number pattern, {{seq}} is required: {}", s));
+        }
+        Ok(NumberPattern { pattern: s.to_string() })
+    }
+}
@@ -0,0 +1,298 @@
+use std::fmt;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::models::credit_note::CreditNote;
+use crate::models::invoice::Invoice;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum PaperSize {
+    A4,
+    #[default]
+    Letter,
+    Legal,
+}
+
+impl PaperSize {
+    /// Portrait width and height in points.
+    pub fn points(&self) -> (f32, f32) {
+        match self {
+            PaperSize::A4 => (595.28, 841.89),
+            PaperSize::Letter => (612.0, 792.0),
+            PaperSize::Legal => (612.0, 1008.0),
+        }
+    }
+}
+
+impl fmt::Display for PaperSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PaperSize::A4 => write!(f, "A4"),
+            PaperSize::Letter => write!(f, "Letter"),
+            PaperSize::Legal => write!(f, "Legal"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Orientation::Portrait => write!(f, "portrait"),
+            Orientation::Landscape => write!(f, "landscape"),
+        }
+    }
+}
+
+/// Page margins in points, given on the command line like CSS margins:
+/// one to four lengths in `mm`, `cm`, `in` or `pt`, e.g. `20mm` or
+/// `0.5in 0.75in`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Margins {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl Margins {
+    pub fn uniform(length: f32) -> Self {
+        Margins { top: length, right: length, bottom: length, left: length }
+    }
+}
+
+fn parse_length(s: &str) -> Result<f32, String> {
+    let (number, factor) = if let Some(number) = s.strip_suffix("mm") {
+        (number, 72.0 / 25.4)
+    } else if let Some(number) = s.strip_suffix("cm") {
+        (number, 72.0 / 2.54)
+    } else if let Some(number) = s.strip_suffix("in") {
+        (number, 72.0)
+    } else if let Some(number) = s.strip_suffix("pt") {
+        (number, 1.0)
+    } else {
+        return Err(format!("Invalid length, expected a unit of mm, cm, in or pt: {}", s));
+    };
+    match number.trim().parse::<f32>() {
+        Ok(length) if (0.0..=288.0).contains(&(length * factor)) => Ok(length * factor),
+        _ => Err(format!("Invalid length, expected 0 to 4in: {}", s)),
+    }
+}
+
+impl FromStr for Margins {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lengths = s
+            .split([' ', ','])
+            .filter(|part| !part.is_empty())
+            .map(parse_length)
+            .collect::<Result<Vec<_>, _>>()?;
+        match lengths[..] {
+            [all] => Ok(Margins::uniform(all)),
+            [vertical, horizontal] => Ok(Margins { top: vertical, right: horizontal, bottom: vertical, left: horizontal }),
+            [top, horizontal, bottom] => Ok(Margins { top, right: horizontal, bottom, left: horizontal }),
+            [top, right, bottom, left] => Ok(Margins { top, right, bottom, left }),
+            _ => Err(format!("Invalid margins, expected one to four lengths: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Margins {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let points = |length: f32| (length * 100.0).round() / 100.0;
+        write!(f, "{}pt {}pt {}pt {}pt", points(self.top), points(self.right), points(self.bottom), points(self.left))
+    }
+}
+
+/// Values a header or footer template can use besides the page numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageFields {
+    pub number: String,
+    pub date: String,
+    pub company: String,
+    pub client: String,
+}
+
+impl PageFields {
+    pub fn invoice(invoice: &Invoice) -> Self {
+        PageFields {
+            number: invoice.reference(),
+            date: invoice.issue_date().format("%B %d, %Y").to_string(),
+            company: invoice.template.company.name.clone(),
+            client: invoice.template.client.name.clone(),
+        }
+    }
+    pub fn credit_note(credit: &CreditNote) -> Self {
+        PageFields {
+            number: credit.reference(),
+            date: credit.credit_date().format("%B %d, %Y").to_string(),
+            company: credit.template.company.name.clone(),
+            client: credit.template.client.name.clone(),
+        }
+    }
+}
+
+/// Fills in a header or footer template. `{page}` and `{pages}` become
+/// `page` and `pages`, the other placeholders the matching field passed
+/// through `escape`, and the text between them is escaped too.
+pub fn expand_template(template: &str, fields: &PageFields, page: &str, pages: &str, escape: fn(&str) -> String) -> String {
+    let mut expanded = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&escape(&rest[..start]));
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        match &rest[start + 1..end] {
+            "page" => expanded.push_str(page),
+            "pages" => expanded.push_str(pages),
+            "number" => expanded.push_str(&escape(&fields.number)),
+            "date" => expanded.push_str(&escape(&fields.date)),
+            "company" => expanded.push_str(&escape(&fields.company)),
+            "client" => expanded.push_str(&escape(&fields.client)),
+            token => expanded.push_str(&escape(&format!("{{{}}}", token))),
+        }
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(&escape(rest));
+    expanded
+}
+
+fn validate_template(template: &str) -> Result<String, String> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| format!("Invalid header or footer, unclosed '{{': {}", template))?;
+        match &rest[start + 1..end] {
+            "page" | "pages" | "number" | "date" | "company" | "client" => {}
+            token => return Err(format!(
+                "Invalid placeholder {{{}}}, expected {{page}}, {{pages}}, {{number}}, {{date}}, {{company}} or {{client}}",
+                token
+            )),
+        }
+        rest = &rest[end + 1..];
+    }
+    Ok(template.to_string())
+}
+
+/// Header or footer text, checked for unknown placeholders.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PageTemplate(pub String);
+
+impl FromStr for PageTemplate {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        validate_template(s).map(PageTemplate)
+    }
+}
+
+/// Page options stored for a company or a layout. Unset options fall
+/// through to the next level: defaults, then the company, then the layout.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PageSetup {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paper: Option<PaperSize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<Orientation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub margins: Option<Margins>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<PageTemplate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub footer: Option<PageTemplate>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum PageOption {
+    Paper,
+    Orientation,
+    Margins,
+    Scale,
+    Header,
+    Footer,
+}
+
+impl PageSetup {
+    pub fn is_empty(&self) -> bool {
+        *self == PageSetup::default()
+    }
+    /// `self` with every option set in `other` replaced by it.
+    pub fn overlay(&self, other: &PageSetup) -> PageSetup {
+        PageSetup {
+            paper: other.paper.or(self.paper),
+            orientation: other.orientation.or(self.orientation),
+            margins: other.margins.or(self.margins),
+            scale: other.scale.or(self.scale),
+            header: other.header.clone().or_else(|| self.header.clone()),
+            footer: other.footer.clone().or_else(|| self.footer.clone()),
+        }
+    }
+    pub fn clear(&mut self, option: PageOption) {
+        match option {
+            PageOption::Paper => self.paper = None,
+            PageOption::Orientation => self.orientation = None,
+            PageOption::Margins => self.margins = None,
+            PageOption::Scale => self.scale = None,
+            PageOption::Header => self.header = None,
+            PageOption::Footer => self.footer = None,
+        }
+    }
+    /// Page width and height in points.
+    pub fn page_size(&self) -> (f32, f32) {
+        let (width, height) = self.paper.unwrap_or_default().points();
+        match self.orientation.unwrap_or_default() {
+            Orientation::Portrait => (width, height),
+            Orientation::Landscape => (height, width),
+        }
+    }
+    pub fn scale(&self) -> f32 {
+        self.scale.unwrap_or(1.0)
+    }
+}
+
+pub fn parse_scale(s: &str) -> Result<f32, String> {
+    match s.trim().parse::<f32>() {
+        Ok(scale) if (0.1..=2.0).contains(&scale) => Ok(scale),
+        _ => Err(format!("Invalid scale, expected 0.1 to 2: {}", s)),
+    }
+}
+
+impl fmt::Display for PageSetup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(paper) = &self.paper {
+            writeln!(f, "Paper:\t\t{}", paper)?;
+        }
+        if let Some(orientation) = &self.orientation {
+            writeln!(f, "Orientation:\t{}", orientation)?;
+        }
+        if let Some(margins) = &self.margins {
+            writeln!(f, "Margins:\t{}", margins)?;
+        }
+        if let Some(scale) = &self.scale {
+            writeln!(f, "Scale:\t\t{}", scale)?;
+        }
+        if let Some(header) = &self.header {
+            writeln!(f, "Header:\t\t{}", header.0)?;
+        }
+        if let Some(footer) = &self.footer {
+            writeln!(f, "Footer:\t\t{}", footer.0)?;
+        }
+        Ok(())
+    }
+}
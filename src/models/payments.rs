@@ -0,0 +1,81 @@
+use std::fmt;
+
+use chrono::NaiveDate;
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use rust_decimal::Decimal;
+
+use crate::models::currency::Currency;
+use crate::models::{EntityDeleter, Tabular};
+use crate::cli::delete::DeletePayment;
+
+#[derive(Debug)]
+pub struct Payments {
+    pub id: i64,
+    pub invoice_id: i64,
+    pub amount: Decimal,
+    /// Currency of the invoice the payment is against.
+    pub currency: Currency,
+    pub date: String,
+    pub method_id: Option<i64>,
+    pub method: Option<String>,
+    pub reference: Option<String>,
+}
+
+impl Payments {
+    pub fn payment_date(&self) -> NaiveDate {
+        NaiveDate::parse_from_str(&self.date, "%Y%m%d").unwrap()
+    }
+}
+
+impl fmt::Display for Payments {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ID: {} - {} {}", self.id, self.payment_date(), self.currency.format(self.amount))?;
+        if let Some(method) = &self.method {
+            write!(f, " via {}", method)?;
+        }
+        if let Some(reference) = &self.reference {
+            write!(f, " (Ref: {})", reference)?;
+        }
+        Ok(())
+    }
+}
+
+impl Tabular for Payments {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Invoice", "Date", "Amount", "Currency", "Method", "Reference"]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.invoice_id.to_string(),
+            self.payment_date().to_string(),
+            self.currency.round(self.amount).to_string(),
+            self.currency.to_string(),
+            self.method.clone().unwrap_or_default(),
+            self.reference.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+impl EntityDeleter<Payments> for Payments {
+    type Output = DeletePayment;
+    fn delete(&self) -> Result<Self::Output, anyhow::Error> {
+        Ok(DeletePayment { id: self.id })
+    }
+}
+
+impl Serialize for Payments {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Payment", 6)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("invoice_id", &self.invoice_id)?;
+        state.serialize_field("amount", &self.currency.round(self.amount).to_string())?;
+        state.serialize_field("date", &self.payment_date().format("%B %d, %Y").to_string())?;
+        state.serialize_field("method", &self.method)?;
+        state.serialize_field("reference", &self.reference)?;
+        state.end()
+    }
+}
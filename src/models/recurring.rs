@@ -0,0 +1,139 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::models::invoice::InvoiceItem;
+use crate::models::{EntityDeleter, Tabular};
+use crate::cli::delete::DeleteRecurring;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Interval {
+    Weekly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Weekly => write!(f, "weekly"),
+            Self::Monthly => write!(f, "monthly"),
+            Self::Quarterly => write!(f, "quarterly"),
+            Self::Yearly => write!(f, "yearly"),
+        }
+    }
+}
+
+impl FromStr for Interval {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "weekly" => Ok(Interval::Weekly),
+            "monthly" => Ok(Interval::Monthly),
+            "quarterly" => Ok(Interval::Quarterly),
+            "yearly" => Ok(Interval::Yearly),
+            _ => Err(format!("Invalid Interval: {}", s)),
+        }
+    }
+}
+
+impl Interval {
+    /// The run after `date`. Monthly and longer intervals land on `day`,
+    /// or the last day of the month when it is shorter.
+    pub fn advance(&self, date: NaiveDate, day: Option<u32>) -> NaiveDate {
+        let months = match self {
+            Self::Weekly => return date + Duration::days(7),
+            Self::Monthly => 1,
+            Self::Quarterly => 3,
+            Self::Yearly => 12,
+        };
+        let next = date + Months::new(months);
+        let day = day.unwrap_or(date.day());
+        (1..=day)
+            .rev()
+            .find_map(|day| next.with_day(day))
+            .unwrap_or(next)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Recurring {
+    pub id: i64,
+    pub name: String,
+    pub template_id: i64,
+    pub template: String,
+    pub items: Vec<InvoiceItem>,
+    pub interval: Interval,
+    pub day: Option<u32>,
+    pub next_run: String,
+    pub end_date: Option<String>,
+}
+
+impl Recurring {
+    pub fn next_run_date(&self) -> NaiveDate {
+        NaiveDate::parse_from_str(&self.next_run, "%Y%m%d").unwrap()
+    }
+    pub fn end(&self) -> Option<NaiveDate> {
+        self.end_date
+            .as_ref()
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+    }
+    /// Whether an invoice is owed as of `today`.
+    pub fn is_due(&self, today: NaiveDate) -> bool {
+        let next = self.next_run_date();
+        next <= today && self.end().is_none_or(|end| next <= end)
+    }
+    pub fn following_run(&self) -> NaiveDate {
+        self.interval.advance(self.next_run_date(), self.day)
+    }
+}
+
+impl fmt::Display for Recurring {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "ID:\t\t{}", self.id)?;
+        writeln!(f, "Name:\t\t{}", self.name)?;
+        writeln!(f, "Template:\t{} - {}", self.template_id, self.template)?;
+        match self.day {
+            Some(day) => writeln!(f, "Interval:\t{} on day {}", self.interval, day)?,
+            None => writeln!(f, "Interval:\t{}", self.interval)?,
+        }
+        writeln!(f, "Next run:\t{}", self.next_run_date())?;
+        if let Some(end) = self.end() {
+            writeln!(f, "Ends:\t\t{}", end)?;
+        }
+        writeln!(f, "Items:")?;
+        for item in &self.items {
+            writeln!(f, "\t{} x {}", item.quantity, item.name)?;
+        }
+        Ok(())
+    }
+}
+
+impl Tabular for Recurring {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Template", "Interval", "Next Run", "Ends", "Lines"]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.template.clone(),
+            self.interval.to_string(),
+            self.next_run_date().to_string(),
+            self.end().map(|end| end.to_string()).unwrap_or_default(),
+            self.items.len().to_string(),
+        ]
+    }
+}
+
+impl EntityDeleter<Recurring> for Recurring {
+    type Output = DeleteRecurring;
+    fn delete(&self) -> Result<Self::Output, anyhow::Error> {
+        Ok(DeleteRecurring { id: self.id })
+    }
+}
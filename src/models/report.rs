@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate};
+use clap::ValueEnum;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::models::currency::{Currency, Locale};
+use crate::models::invoice::{Invoice, InvoiceStage, PaidStatus};
+use crate::models::Tabular;
+
+/// `amount` with the decimals of the currency `code`.
+fn money(code: &str, amount: Decimal) -> String {
+    match Currency::new(code, Locale::default()) {
+        Ok(currency) => currency.round(amount).to_string(),
+        Err(_) => format!("{:.2}", amount),
+    }
+}
+
+/// Outstanding balances for one client in one currency, bucketed by days
+/// past due.
+#[derive(Debug, Default, Serialize)]
+pub struct AgingRow {
+    pub client: String,
+    pub currency: String,
+    pub current: Decimal,
+    pub days_0_30: Decimal,
+    pub days_31_60: Decimal,
+    pub days_61_90: Decimal,
+    pub days_90_plus: Decimal,
+    pub total: Decimal,
+}
+
+impl AgingRow {
+    fn add(&mut self, days_past_due: i64, amount: Decimal) {
+        let bucket = match days_past_due {
+            i64::MIN..=-1 => &mut self.current,
+            0..=30 => &mut self.days_0_30,
+            31..=60 => &mut self.days_31_60,
+            61..=90 => &mut self.days_61_90,
+            _ => &mut self.days_90_plus,
+        };
+        *bucket += amount;
+        self.total += amount;
+    }
+}
+
+impl Tabular for AgingRow {
+    fn headers() -> Vec<&'static str> {
+        vec!["Client", "Currency", "Current", "0-30", "31-60", "61-90", "90+", "Total"]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.client.clone(),
+            self.currency.clone(),
+            money(&self.currency, self.current),
+            money(&self.currency, self.days_0_30),
+            money(&self.currency, self.days_31_60),
+            money(&self.currency, self.days_61_90),
+            money(&self.currency, self.days_90_plus),
+            money(&self.currency, self.total),
+        ]
+    }
+}
+
+/// Buckets the outstanding balance of every unpaid Invoice-stage invoice by
+/// client and currency, measured in days past its due date as of `as_of`.
+/// Invoices, payments and credit notes dated after `as_of` are left out, as
+/// are invoices marked Paid or Refunded by then.
+/// The last rows hold the totals across all clients, one per currency.
+pub fn aging(invoices: &[Invoice], as_of: NaiveDate) -> Vec<AgingRow> {
+    let mut clients: BTreeMap<(String, String), AgingRow> = BTreeMap::new();
+    let mut totals: BTreeMap<String, AgingRow> = BTreeMap::new();
+    for invoice in invoices {
+        let settled = match &invoice.attributes.status {
+            PaidStatus::Paid { date, .. } | PaidStatus::Refunded { date } => {
+                NaiveDate::parse_from_str(date, "%Y%m%d").map_or(true, |date| date <= as_of)
+            }
+            _ => false,
+        };
+        if invoice.attributes.stage != InvoiceStage::Invoice || settled || invoice.issue_date() > as_of {
+            continue;
+        }
+        let balance = invoice.balance_as_of(as_of);
+        if balance <= Decimal::ZERO {
+            continue;
+        }
+        let days_past_due = (as_of - invoice.due_date()).num_days();
+        let client = &invoice.template.client.name;
+        let currency = invoice.currency.to_string();
+        clients
+            .entry((client.clone(), currency.clone()))
+            .or_insert_with(|| AgingRow { client: client.clone(), currency: currency.clone(), ..Default::default() })
+            .add(days_past_due, balance);
+        totals
+            .entry(currency.clone())
+            .or_insert_with(|| AgingRow { client: "Total".to_string(), currency, ..Default::default() })
+            .add(days_past_due, balance);
+    }
+    let mut rows = clients.into_values().collect::<Vec<_>>();
+    rows.extend(totals.into_values());
+    rows
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum RevenueGroup {
+    Month,
+    Quarter,
+    Year,
+    Client,
+    Item,
+}
+
+/// Invoiced (accrual) and received (cash) amounts for one group in one
+/// currency.
+#[derive(Debug, Default, Serialize)]
+pub struct RevenueRow {
+    pub group: String,
+    pub currency: String,
+    pub invoiced: Decimal,
+    pub paid: Decimal,
+}
+
+impl Tabular for RevenueRow {
+    fn headers() -> Vec<&'static str> {
+        vec!["Group", "Currency", "Invoiced", "Paid"]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.group.clone(),
+            self.currency.clone(),
+            money(&self.currency, self.invoiced),
+            money(&self.currency, self.paid),
+        ]
+    }
+}
+
+impl RevenueGroup {
+    fn period(&self, date: NaiveDate) -> String {
+        match self {
+            Self::Month => date.format("%Y-%m").to_string(),
+            Self::Quarter => format!("{}-Q{}", date.year(), date.month0() / 3 + 1),
+            _ => date.year().to_string(),
+        }
+    }
+}
+
+/// Money received against an invoice: the payment ledger when there is one,
+/// otherwise the full total on the date it was marked Paid.
+fn receipts(invoice: &Invoice) -> Vec<(NaiveDate, Decimal)> {
+    if !invoice.payments.is_empty() {
+        return invoice.payments
+            .iter()
+            .map(|payment| (payment.payment_date(), payment.amount))
+            .collect();
+    }
+    match &invoice.attributes.status {
+        PaidStatus::Paid { date, .. } => NaiveDate::parse_from_str(date, "%Y%m%d")
+            .map(|date| vec![(date, invoice.calculate_total())])
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Aggregates Invoice-stage invoices by `group` and currency. Invoiced amounts
+/// count on the invoice date and paid amounts on the date received; each is
+/// limited to the `from`..=`to` range. The last rows hold the totals, one per
+/// currency.
+pub fn revenue(
+    invoices: &[Invoice],
+    group: RevenueGroup,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Vec<RevenueRow> {
+    let in_range = |date: NaiveDate| from.is_none_or(|from| date >= from) && to.is_none_or(|to| date <= to);
+    let mut groups: BTreeMap<(String, String), RevenueRow> = BTreeMap::new();
+    for invoice in invoices {
+        if invoice.attributes.stage != InvoiceStage::Invoice {
+            continue;
+        }
+        let currency = &invoice.currency;
+        let mut add = |key: String, invoiced: Decimal, paid: Decimal| {
+            let row = groups
+                .entry((key.clone(), currency.to_string()))
+                .or_insert_with(|| RevenueRow { group: key, currency: currency.to_string(), ..Default::default() });
+            row.invoiced += invoiced;
+            row.paid += paid;
+        };
+        let issued = invoice.issue_date();
+        let total = if in_range(issued) { invoice.calculate_total() } else { Decimal::ZERO };
+        let received = receipts(invoice)
+            .into_iter()
+            .filter(|(date, _)| in_range(*date))
+            .collect::<Vec<_>>();
+        match group {
+            RevenueGroup::Month | RevenueGroup::Quarter | RevenueGroup::Year => {
+                add(group.period(issued), total, Decimal::ZERO);
+                for (date, amount) in received {
+                    add(group.period(date), Decimal::ZERO, amount);
+                }
+            }
+            RevenueGroup::Client => {
+                let paid = received.iter().map(|(_, amount)| *amount).sum();
+                add(invoice.template.client.name.clone(), total, paid);
+            }
+            RevenueGroup::Item => {
+                // Invoice totals and payments are shared out by each line's
+                // share of the subtotal, so taxes and discounts follow the lines.
+                let paid: Decimal = received.iter().map(|(_, amount)| *amount).sum();
+                let subtotal = invoice.calculate_subtotal();
+                for line in &invoice.items {
+                    let share = if subtotal.is_zero() {
+                        Decimal::ONE / Decimal::from(invoice.items.len())
+                    } else {
+                        line.subtotal(currency) / subtotal
+                    };
+                    add(line.name.clone(), currency.round(total * share), currency.round(paid * share));
+                }
+            }
+        }
+        // Credit notes reduce the invoiced amount on the date they were issued.
+        for credit in invoice.credits.iter().filter(|credit| in_range(credit.credit_date())) {
+            let total = credit.calculate_total();
+            match group {
+                RevenueGroup::Month | RevenueGroup::Quarter | RevenueGroup::Year => {
+                    add(group.period(credit.credit_date()), total, Decimal::ZERO);
+                }
+                RevenueGroup::Client => {
+                    add(invoice.template.client.name.clone(), total, Decimal::ZERO);
+                }
+                RevenueGroup::Item => {
+                    let subtotal = credit.calculate_subtotal();
+                    for line in &credit.items {
+                        let share = if subtotal.is_zero() {
+                            Decimal::ONE / Decimal::from(credit.items.len())
+                        } else {
+                            line.subtotal(currency) / subtotal
+                        };
+                        add(line.name.clone(), currency.round(total * share), Decimal::ZERO);
+                    }
+                }
+            }
+        }
+    }
+    let mut rows = groups.into_values().collect::<Vec<_>>();
+    rows.retain(|row| !row.invoiced.is_zero() || !row.paid.is_zero());
+    let mut totals: BTreeMap<String, RevenueRow> = BTreeMap::new();
+    for row in &rows {
+        let total = totals
+            .entry(row.currency.clone())
+            .or_insert_with(|| RevenueRow { group: "Total".to_string(), currency: row.currency.clone(), ..Default::default() });
+        total.invoiced += row.invoiced;
+        total.paid += row.paid;
+    }
+    rows.extend(totals.into_values());
+    rows
+}
@@ -0,0 +1,85 @@
+use std::fmt;
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use crate::models::currency::{parse_currency, Locale};
+use crate::render::PdfEngine;
+
+/// Regular and bold fonts looked for when the pdf-font settings are unset.
+pub const FONT_SEARCH_PATH: &[(&str, &str)] = &[
+    ("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf", "/usr/share/fonts/truetype/dejavu/DejaVuSans-Bold.ttf"),
+    ("/usr/share/fonts/TTF/DejaVuSans.ttf", "/usr/share/fonts/TTF/DejaVuSans-Bold.ttf"),
+    ("/usr/share/fonts/dejavu/DejaVuSans.ttf", "/usr/share/fonts/dejavu/DejaVuSans-Bold.ttf"),
+    ("/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf", "/usr/share/fonts/truetype/liberation/LiberationSans-Bold.ttf"),
+    ("/Library/Fonts/Arial.ttf", "/Library/Fonts/Arial Bold.ttf"),
+    ("C:\\Windows\\Fonts\\arial.ttf", "C:\\Windows\\Fonts\\arialbd.ttf"),
+];
+
+/// The first pair in `FONT_SEARCH_PATH` that is installed.
+pub fn system_fonts() -> Option<(&'static str, &'static str)> {
+    FONT_SEARCH_PATH
+        .iter()
+        .find(|(regular, bold)| Path::new(regular).is_file() && Path::new(bold).is_file())
+        .copied()
+}
+
+/// Preferences stored in the `settings` table and changed with
+/// `settings set`. Values are stored as text and checked when set.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Setting {
+    /// PDF engine used when --pdf-engine is not given: chrome or native
+    PdfEngine,
+    /// ISO 4217 code of the currency for clients without one of their own, e.g. EUR
+    Currency,
+    /// How amounts are written: en, de, fr or de-CH
+    Locale,
+    /// TrueType font embedded by the native engine when a PDF/A is required
+    PdfFont,
+    /// Bold TrueType font embedded alongside pdf-font
+    PdfFontBold,
+}
+
+impl Setting {
+    pub fn all() -> &'static [Setting] {
+        &[Setting::PdfEngine, Setting::Currency, Setting::Locale, Setting::PdfFont, Setting::PdfFontBold]
+    }
+    /// Value used while the setting is unset.
+    pub fn default_value(&self) -> String {
+        match self {
+            Setting::PdfEngine => PdfEngine::default().to_string(),
+            Setting::Currency => "USD".to_string(),
+            Setting::Locale => Locale::default().to_string(),
+            Setting::PdfFont => system_fonts().map_or("none found".to_string(), |(regular, _)| regular.to_string()),
+            Setting::PdfFontBold => system_fonts().map_or("none found".to_string(), |(_, bold)| bold.to_string()),
+        }
+    }
+    /// Checks `value` and returns it in its stored form.
+    pub fn parse(&self, value: &str) -> Result<String, String> {
+        let value = value.trim();
+        match self {
+            Setting::PdfEngine => value.to_lowercase().parse::<PdfEngine>().map(|engine| engine.to_string()),
+            Setting::Currency => parse_currency(value),
+            Setting::Locale => value.parse::<Locale>().map(|locale| locale.to_string()),
+            Setting::PdfFont | Setting::PdfFontBold => {
+                if Path::new(value).is_file() {
+                    Ok(value.to_string())
+                } else {
+                    Err(format!("Font file not found: {}", value))
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Setting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Setting::PdfEngine => write!(f, "pdf-engine"),
+            Setting::Currency => write!(f, "currency"),
+            Setting::Locale => write!(f, "locale"),
+            Setting::PdfFont => write!(f, "pdf-font"),
+            Setting::PdfFontBold => write!(f, "pdf-font-bold"),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Serialize};
+use rust_decimal::Decimal;
+
+use crate::models::{EntityDeleter, EntityUpdater, Tabular};
+use crate::cli::edit::EditTax;
+use crate::cli::delete::DeleteTax;
+
+use inquire::{MultiSelect, Text, InquireError};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Taxes {
+    pub id: i64,
+    pub name: String,
+    pub rate: Decimal,
+}
+
+impl fmt::Display for Taxes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (Rate: {}%)", self.name, self.rate)
+    }
+}
+
+impl Tabular for Taxes {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Rate (%)"]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![self.id.to_string(), self.name.clone(), self.rate.to_string()]
+    }
+}
+
+impl EntityDeleter<Taxes> for Taxes {
+    type Output = DeleteTax;
+    fn delete(&self) -> Result<Self::Output, anyhow::Error> {
+        Ok(DeleteTax { id: self.id })
+    }
+}
+
+impl EntityUpdater<Taxes> for Taxes {
+    type Output = EditTax;
+    fn update(&self) -> Result<Self::Output, InquireError> {
+        println!("{}", self);
+        let fields = vec!["name", "rate"];
+        let selected_fields = MultiSelect::new("Select fields to update:", fields)
+            .prompt()?;
+        let mut edit_tax = EditTax{
+            id: self.id,
+            name: None,
+            rate: None
+        };
+        for field in selected_fields {
+            match field {
+                "name" => {
+                    let name = Text::new("Enter new name:")
+                        .with_default(&self.name)
+                        .prompt()?;
+                    edit_tax.name = Some(name);
+                },
+                "rate" => {
+                    let rate = Text::new("Enter new rate (percent):")
+                        .with_default(&self.rate.to_string())
+                        .prompt()?
+                        .parse::<Decimal>()
+                        .ok();
+                    edit_tax.rate = rate;
+                },
+                _ => {}
+            }
+        }
+        Ok(edit_tax)
+    }
+}
+
+impl PartialEq for Taxes {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Taxes {}
+
+impl Hash for Taxes {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
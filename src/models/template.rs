@@ -6,8 +6,9 @@ use serde::{Deserialize, Serialize};
 use crate::models::client::Client;
 use crate::models::company::Company;
 use crate::models::methods::Methods;
+use crate::models::taxes::Taxes;
 use crate::models::terms::Terms;
-use crate::models::{EntityDeleter, EntityUpdater};
+use crate::models::{EntityDeleter, EntityUpdater, Tabular};
 
 use crate::cli::delete::DeleteTemplate;
 use crate::cli::edit::EditTemplate;
@@ -20,24 +21,59 @@ pub struct Template {
     pub client: Client,
     pub terms: Terms,
     pub methods: Vec<Methods>,
+    pub taxes: Vec<Taxes>,
+    /// Tera file in the templates directory used to render invoices,
+    /// `default.html` when unset.
+    pub layout: Option<String>,
+}
+
+impl Template {
+    pub const DEFAULT_LAYOUT: &'static str = "default.html";
+
+    pub fn layout(&self) -> &str {
+        self.layout.as_deref().unwrap_or(Self::DEFAULT_LAYOUT)
+    }
 }
 
 impl fmt::Display for Template {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "ID:\t\t{}\n", self.id)?;
+        writeln!(f, "ID:\t\t{}", self.id)?;
         write!(f, "Name:\t\t{}\n\n", self.name)?;
         write!(f, "Company Information:\n{}\n", self.company)?;
         write!(f, "Client Information:\n{}\n", self.client)?;
-        write!(f, "Terms:\n")?;
-        write!(f, "{}\n", self.terms)?;
-        write!(f, "Payment Methods:\n")?;
+        writeln!(f, "Terms:")?;
+        writeln!(f, "{}", self.terms)?;
+        writeln!(f, "Payment Methods:")?;
         for method in &self.methods {
-            write!(f, "{}\n", method)?;
+            writeln!(f, "{}", method)?;
+        }
+        writeln!(f, "Taxes:")?;
+        for tax in &self.taxes {
+            writeln!(f, "{}", tax)?;
         }
+        writeln!(f, "Layout:\t\t{}", self.layout())?;
         Ok(())
     }
 }
 
+impl Tabular for Template {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Company", "Client", "Terms", "Methods", "Taxes", "Layout"]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.company.name.clone(),
+            self.client.name.clone(),
+            self.terms.name.clone(),
+            self.methods.iter().map(|method| method.name.clone()).collect::<Vec<_>>().join(", "),
+            self.taxes.iter().map(|tax| tax.name.clone()).collect::<Vec<_>>().join(", "),
+            self.layout().to_string(),
+        ]
+    }
+}
+
 impl EntityDeleter<Template> for Template {
     type Output = DeleteTemplate;
     fn delete(&self) -> Result<Self::Output, anyhow::Error> {
@@ -49,14 +85,16 @@ impl EntityUpdater<Template> for Template {
     type Output = EditTemplate;
     fn update(&self) -> Result<Self::Output, InquireError> {
         println!("{}", self);
-        let fields = vec!["name", "company", "client", "terms", "methods"];
+        let fields = vec!["name", "company", "client", "terms", "methods", "taxes", "layout"];
         let mut edit_template = EditTemplate {
             id: self.id,
             name: None,
             company: None,
             client: None,
             terms: None,
-            methods: None
+            methods: None,
+            taxes: None,
+            layout: None,
         };
         let selected_fields = MultiSelect::new("Select fields to update:", fields).prompt()?;
         for field in selected_fields {
@@ -80,6 +118,16 @@ impl EntityUpdater<Template> for Template {
                 "methods" => {
                     //let methods_selection = EntitySelector::new(db, "methods", "Select Payment Methods:", true).multi_select_entity()?;
                 }
+                "taxes" => {
+                    //let taxes_selection = EntitySelector::new(db, "taxes", "Select Taxes:", true).multi_select_entity()?;
+                }
+                "layout" => {
+                    let layout = Text::new("Enter new layout file name:")
+                        .with_default(self.layout())
+                        .with_help_message("A file in the templates directory, see `layouts list`")
+                        .prompt()?;
+                    edit_template.layout = Some(layout.trim().to_string());
+                }
                 _ => {}
             }
         }
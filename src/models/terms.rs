@@ -2,7 +2,7 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
-use crate::models::{EntityDeleter, EntityUpdater};
+use crate::models::{EntityDeleter, EntityUpdater, Tabular};
 use crate::cli::edit::EditTerms;
 use crate::cli::delete::DeleteTerms;
 
@@ -25,6 +25,15 @@ impl fmt::Display for Terms {
     }
 }
 
+impl Tabular for Terms {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Due (days)"]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![self.id.to_string(), self.name.clone(), self.due.to_string()]
+    }
+}
+
 impl EntityDeleter<Terms> for Terms {
     type Output = DeleteTerms;
     fn delete(&self) -> Result<Self::Output, anyhow::Error> {
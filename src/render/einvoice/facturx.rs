@@ -0,0 +1,360 @@
+use std::fmt;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+
+use crate::models::contact::Contact;
+use crate::models::invoice::Invoice;
+use crate::render::einvoice::{
+    amount, check_parties, is_vat_id, percent, unit_code, vat_category, Problems, Totals, Xml,
+};
+use crate::render::pdf::{escape_xml, Archival, Archive, Attachment};
+
+/// How much of the invoice the embedded XML describes. MINIMUM only has the
+/// parties and totals, BASIC adds the lines, and EN16931 is the complete
+/// European standard invoice.
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum FacturxProfile {
+    Minimum,
+    Basic,
+    #[default]
+    En16931,
+}
+
+impl FacturxProfile {
+    fn guideline(&self) -> &'static str {
+        match self {
+            FacturxProfile::Minimum => "urn:factur-x.eu:1p0:minimum",
+            FacturxProfile::Basic => "urn:cen.eu:en16931:2017#compliant#urn:factur-x.eu:1p0:basic",
+            FacturxProfile::En16931 => "urn:cen.eu:en16931:2017",
+        }
+    }
+    /// The conformance level named in the XMP metadata.
+    fn conformance(&self) -> &'static str {
+        match self {
+            FacturxProfile::Minimum => "MINIMUM",
+            FacturxProfile::Basic => "BASIC",
+            FacturxProfile::En16931 => "EN 16931",
+        }
+    }
+    /// MINIMUM is not a full invoice, so its XML only supplements the PDF.
+    fn relationship(&self) -> &'static str {
+        match self {
+            FacturxProfile::Minimum => "Data",
+            _ => "Alternative",
+        }
+    }
+    fn has_lines(&self) -> bool {
+        *self != FacturxProfile::Minimum
+    }
+}
+
+impl fmt::Display for FacturxProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FacturxProfile::Minimum => write!(f, "minimum"),
+            FacturxProfile::Basic => write!(f, "basic"),
+            FacturxProfile::En16931 => write!(f, "en16931"),
+        }
+    }
+}
+
+impl FromStr for FacturxProfile {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "minimum" => Ok(FacturxProfile::Minimum),
+            "basic" => Ok(FacturxProfile::Basic),
+            "en16931" => Ok(FacturxProfile::En16931),
+            _ => Err(format!("Invalid Factur-X profile, expected minimum, basic or en16931: {}", s)),
+        }
+    }
+}
+
+const FILE_NAME: &str = "factur-x.xml";
+
+/// A Factur-X invoice: the PDF drawn by the native engine as a PDF/A-3b,
+/// with the invoice as Cross Industry Invoice XML attached.
+pub struct FacturX {
+    pub profile: FacturxProfile,
+    /// Regular and bold TrueType fonts, which PDF/A requires embedded.
+    pub fonts: (Vec<u8>, Vec<u8>),
+}
+
+impl FacturX {
+    /// Checks that `invoice` has everything the profile requires.
+    pub fn check(&self, invoice: &Invoice) -> Result<(), anyhow::Error> {
+        let mut problems = Problems::default();
+        check_parties(invoice, &mut problems);
+        // The tax total of every profile is worked out per VAT rate, one per line.
+        for line in &invoice.items {
+            problems.require(
+                line.taxes.len() <= 1,
+                format!("line {} has more than one tax, e-invoice lines carry a single VAT rate", line.name),
+            );
+        }
+        if self.profile.has_lines() {
+            let client = &invoice.template.client;
+            problems.require(client.contact.country.is_some(), format!("client {} has no country", client.name));
+            problems.require(!invoice.items.is_empty(), "the invoice has no lines");
+            if let Some(tax_id) = &invoice.template.company.tax_id {
+                problems.require(
+                    is_vat_id(tax_id),
+                    format!("the company tax ID {} is not a VAT ID with a country prefix, e.g. DE123456789", tax_id),
+                );
+            }
+        }
+        problems.into_result(&format!("a Factur-X {} invoice", self.profile.conformance()))
+    }
+
+    /// The invoice as CII XML for the profile.
+    pub fn xml(&self, invoice: &Invoice) -> String {
+        let profile = self.profile;
+        let totals = Totals::new(invoice);
+        let mut xml = Xml::new();
+        xml.open(concat!(
+            "rsm:CrossIndustryInvoice",
+            " xmlns:rsm=\"urn:un:unece:uncefact:data:standard:CrossIndustryInvoice:100\"",
+            " xmlns:qdt=\"urn:un:unece:uncefact:data:standard:QualifiedDataType:100\"",
+            " xmlns:ram=\"urn:un:unece:uncefact:data:standard:ReusableAggregateBusinessInformationEntity:100\"",
+            " xmlns:udt=\"urn:un:unece:uncefact:data:standard:UnqualifiedDataType:100\"",
+        ));
+        xml.open("rsm:ExchangedDocumentContext");
+        xml.open("ram:GuidelineSpecifiedDocumentContextParameter");
+        xml.leaf("ram:ID", profile.guideline());
+        xml.close("ram:GuidelineSpecifiedDocumentContextParameter");
+        xml.close("rsm:ExchangedDocumentContext");
+
+        xml.open("rsm:ExchangedDocument");
+        xml.leaf("ram:ID", &invoice.reference());
+        xml.leaf("ram:TypeCode", "380");
+        xml.open("ram:IssueDateTime");
+        xml.leaf("udt:DateTimeString format=\"102\"", &invoice.date);
+        xml.close("ram:IssueDateTime");
+        if let (true, Some(notes)) = (profile.has_lines(), &invoice.notes) {
+            xml.open("ram:IncludedNote");
+            xml.leaf("ram:Content", notes);
+            xml.close("ram:IncludedNote");
+        }
+        xml.close("rsm:ExchangedDocument");
+
+        xml.open("rsm:SupplyChainTradeTransaction");
+        if profile.has_lines() {
+            self.lines(&mut xml, invoice);
+        }
+
+        let company = &invoice.template.company;
+        let client = &invoice.template.client;
+        xml.open("ram:ApplicableHeaderTradeAgreement");
+        xml.open("ram:SellerTradeParty");
+        xml.leaf("ram:Name", &company.name);
+        self.address(&mut xml, &company.contact);
+        if let Some(tax_id) = &company.tax_id {
+            xml.open("ram:SpecifiedTaxRegistration");
+            let scheme = if is_vat_id(tax_id) { "VA" } else { "FC" };
+            xml.leaf(&format!("ram:ID schemeID=\"{}\"", scheme), tax_id);
+            xml.close("ram:SpecifiedTaxRegistration");
+        }
+        xml.close("ram:SellerTradeParty");
+        xml.open("ram:BuyerTradeParty");
+        xml.leaf("ram:Name", &client.name);
+        if profile.has_lines() {
+            self.address(&mut xml, &client.contact);
+            if let Some(tax_id) = client.tax_id.as_ref().filter(|tax_id| is_vat_id(tax_id)) {
+                xml.open("ram:SpecifiedTaxRegistration");
+                xml.leaf("ram:ID schemeID=\"VA\"", tax_id);
+                xml.close("ram:SpecifiedTaxRegistration");
+            }
+        }
+        xml.close("ram:BuyerTradeParty");
+        xml.close("ram:ApplicableHeaderTradeAgreement");
+        xml.empty("ram:ApplicableHeaderTradeDelivery");
+
+        let currency = format!("ram:TaxTotalAmount currencyID=\"{}\"", invoice.currency);
+        xml.open("ram:ApplicableHeaderTradeSettlement");
+        if profile.has_lines() {
+            xml.leaf("ram:PaymentReference", &invoice.reference());
+        }
+        xml.leaf("ram:InvoiceCurrencyCode", invoice.currency.code());
+        if profile.has_lines() {
+            for category in &totals.categories {
+                xml.open("ram:ApplicableTradeTax");
+                xml.leaf("ram:CalculatedAmount", &amount(category.tax));
+                xml.leaf("ram:TypeCode", "VAT");
+                xml.leaf("ram:BasisAmount", &amount(category.basis));
+                xml.leaf("ram:CategoryCode", category.code);
+                xml.leaf("ram:RateApplicablePercent", &percent(category.rate));
+                xml.close("ram:ApplicableTradeTax");
+            }
+            for category in totals.categories.iter().filter(|category| !category.allowance.is_zero()) {
+                xml.open("ram:SpecifiedTradeAllowanceCharge");
+                xml.open("ram:ChargeIndicator");
+                xml.leaf("udt:Indicator", "false");
+                xml.close("ram:ChargeIndicator");
+                xml.leaf("ram:ActualAmount", &amount(category.allowance));
+                xml.leaf("ram:Reason", "Discount");
+                xml.open("ram:CategoryTradeTax");
+                xml.leaf("ram:TypeCode", "VAT");
+                xml.leaf("ram:CategoryCode", category.code);
+                xml.leaf("ram:RateApplicablePercent", &percent(category.rate));
+                xml.close("ram:CategoryTradeTax");
+                xml.close("ram:SpecifiedTradeAllowanceCharge");
+            }
+            xml.open("ram:SpecifiedTradePaymentTerms");
+            xml.leaf("ram:Description", &invoice.template.terms.name);
+            xml.open("ram:DueDateDateTime");
+            xml.leaf("udt:DateTimeString format=\"102\"", &invoice.due_date().format("%Y%m%d").to_string());
+            xml.close("ram:DueDateDateTime");
+            xml.close("ram:SpecifiedTradePaymentTerms");
+        }
+        xml.open("ram:SpecifiedTradeSettlementHeaderMonetarySummation");
+        if profile.has_lines() {
+            xml.leaf("ram:LineTotalAmount", &amount(totals.lines));
+            xml.leaf("ram:AllowanceTotalAmount", &amount(totals.allowances));
+        }
+        xml.leaf("ram:TaxBasisTotalAmount", &amount(totals.basis));
+        xml.leaf(&currency, &amount(totals.tax));
+        xml.leaf("ram:GrandTotalAmount", &amount(totals.grand));
+        if profile.has_lines() && !totals.prepaid.is_zero() {
+            xml.leaf("ram:TotalPrepaidAmount", &amount(totals.prepaid));
+        }
+        xml.leaf("ram:DuePayableAmount", &amount(totals.due));
+        xml.close("ram:SpecifiedTradeSettlementHeaderMonetarySummation");
+        xml.close("ram:ApplicableHeaderTradeSettlement");
+        xml.close("rsm:SupplyChainTradeTransaction");
+        xml.close("rsm:CrossIndustryInvoice");
+        xml.finish()
+    }
+
+    fn lines(&self, xml: &mut Xml, invoice: &Invoice) {
+        for (index, line) in invoice.items.iter().enumerate() {
+            let (category, rate) = vat_category(line);
+            xml.open("ram:IncludedSupplyChainTradeLineItem");
+            xml.open("ram:AssociatedDocumentLineDocument");
+            xml.leaf("ram:LineID", &(index + 1).to_string());
+            xml.close("ram:AssociatedDocumentLineDocument");
+            xml.open("ram:SpecifiedTradeProduct");
+            xml.leaf("ram:Name", &line.name);
+            if let (FacturxProfile::En16931, Some(description)) = (self.profile, &line.description) {
+                xml.leaf("ram:Description", description);
+            }
+            xml.close("ram:SpecifiedTradeProduct");
+            xml.open("ram:SpecifiedLineTradeAgreement");
+            xml.open("ram:NetPriceProductTradePrice");
+            xml.leaf("ram:ChargeAmount", &line.rate.normalize().to_string());
+            xml.close("ram:NetPriceProductTradePrice");
+            xml.close("ram:SpecifiedLineTradeAgreement");
+            xml.open("ram:SpecifiedLineTradeDelivery");
+            xml.leaf(
+                &format!("ram:BilledQuantity unitCode=\"{}\"", unit_code(line.unit.as_deref())),
+                &line.quantity.normalize().to_string(),
+            );
+            xml.close("ram:SpecifiedLineTradeDelivery");
+            xml.open("ram:SpecifiedLineTradeSettlement");
+            xml.open("ram:ApplicableTradeTax");
+            xml.leaf("ram:TypeCode", "VAT");
+            xml.leaf("ram:CategoryCode", category);
+            xml.leaf("ram:RateApplicablePercent", &percent(rate));
+            xml.close("ram:ApplicableTradeTax");
+            let discount = line.discount_amount(&invoice.currency);
+            if !discount.is_zero() {
+                xml.open("ram:SpecifiedTradeAllowanceCharge");
+                xml.open("ram:ChargeIndicator");
+                xml.leaf("udt:Indicator", "false");
+                xml.close("ram:ChargeIndicator");
+                xml.leaf("ram:ActualAmount", &amount(discount));
+                xml.leaf("ram:Reason", "Discount");
+                xml.close("ram:SpecifiedTradeAllowanceCharge");
+            }
+            xml.open("ram:SpecifiedTradeSettlementLineMonetarySummation");
+            xml.leaf("ram:LineTotalAmount", &amount(line.subtotal(&invoice.currency)));
+            xml.close("ram:SpecifiedTradeSettlementLineMonetarySummation");
+            xml.close("ram:SpecifiedLineTradeSettlement");
+            xml.close("ram:IncludedSupplyChainTradeLineItem");
+        }
+    }
+
+    /// A party's postal address, which MINIMUM reduces to the country.
+    fn address(&self, xml: &mut Xml, contact: &Contact) {
+        let Some(country) = &contact.country else {
+            return;
+        };
+        xml.open("ram:PostalTradeAddress");
+        if self.profile.has_lines() {
+            let parts = [
+                ("ram:PostcodeCode", &contact.zip),
+                ("ram:LineOne", &contact.addr1),
+                ("ram:LineTwo", &contact.addr2),
+                ("ram:CityName", &contact.city),
+            ];
+            for (tag, value) in parts {
+                if let Some(value) = value {
+                    xml.leaf(tag, value);
+                }
+            }
+        }
+        xml.leaf("ram:CountryID", country);
+        if let (true, Some(state)) = (self.profile.has_lines(), &contact.state) {
+            xml.leaf("ram:CountrySubDivisionName", state);
+        }
+        xml.close("ram:PostalTradeAddress");
+    }
+
+    /// The fonts, XML attachment and XMP extension schema that make the
+    /// native PDF of `invoice` a Factur-X file.
+    pub fn archival(self, invoice: &Invoice) -> Archival {
+        let metadata = format!(
+            concat!(
+                "<rdf:Description rdf:about=\"\" xmlns:fx=\"urn:factur-x:pdfa:CrossIndustryDocument:invoice:1p0#\">\n",
+                "<fx:DocumentType>INVOICE</fx:DocumentType>\n",
+                "<fx:DocumentFileName>{}</fx:DocumentFileName>\n",
+                "<fx:Version>1.0</fx:Version>\n",
+                "<fx:ConformanceLevel>{}</fx:ConformanceLevel>\n",
+                "</rdf:Description>\n",
+                "<rdf:Description rdf:about=\"\"",
+                " xmlns:pdfaExtension=\"http://www.aiim.org/pdfa/ns/extension/\"",
+                " xmlns:pdfaSchema=\"http://www.aiim.org/pdfa/ns/schema#\"",
+                " xmlns:pdfaProperty=\"http://www.aiim.org/pdfa/ns/property#\">\n",
+                "<pdfaExtension:schemas><rdf:Bag><rdf:li rdf:parseType=\"Resource\">\n",
+                "<pdfaSchema:schema>Factur-X PDFA Extension Schema</pdfaSchema:schema>\n",
+                "<pdfaSchema:namespaceURI>urn:factur-x:pdfa:CrossIndustryDocument:invoice:1p0#</pdfaSchema:namespaceURI>\n",
+                "<pdfaSchema:prefix>fx</pdfaSchema:prefix>\n",
+                "<pdfaSchema:property><rdf:Seq>\n",
+                "{}",
+                "</rdf:Seq></pdfaSchema:property>\n",
+                "</rdf:li></rdf:Bag></pdfaExtension:schemas>\n",
+                "</rdf:Description>\n",
+            ),
+            FILE_NAME,
+            escape_xml(self.profile.conformance()),
+            [
+                ("DocumentFileName", "The name of the embedded XML document"),
+                ("DocumentType", "The type of the hybrid document in capital letters, e.g. INVOICE"),
+                ("Version", "The version of the standard applying to the embedded XML document"),
+                ("ConformanceLevel", "The conformance level of the embedded XML document"),
+            ]
+            .iter()
+            .map(|(name, description)| {
+                format!(
+                    concat!(
+                        "<rdf:li rdf:parseType=\"Resource\"><pdfaProperty:name>{}</pdfaProperty:name>",
+                        "<pdfaProperty:valueType>Text</pdfaProperty:valueType>",
+                        "<pdfaProperty:category>external</pdfaProperty:category>",
+                        "<pdfaProperty:description>{}</pdfaProperty:description></rdf:li>\n"
+                    ),
+                    name, description
+                )
+            })
+            .collect::<String>()
+        );
+        let attachment = Attachment {
+            name: FILE_NAME.to_string(),
+            mime: "text/xml".to_string(),
+            description: format!("Factur-X {} invoice data", self.profile.conformance()),
+            relationship: self.profile.relationship().to_string(),
+            data: self.xml(invoice).into_bytes(),
+        };
+        let (regular, bold) = self.fonts;
+        Archival { regular, bold, archive: Archive { metadata, attachments: vec![attachment] } }
+    }
+}
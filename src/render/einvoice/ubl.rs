@@ -0,0 +1,300 @@
+use crate::models::contact::Contact;
+use crate::models::invoice::Invoice;
+use crate::models::methods::{MethodKind, Methods};
+use crate::render::einvoice::{
+    amount, check_parties, is_vat_id, percent, unit_code, vat_category, Problems, Totals, Xml,
+};
+use crate::render::pdf::escape_xml;
+
+const CUSTOMIZATION: &str = "urn:cen.eu:en16931:2017#compliant#urn:fdc:peppol.eu:2017:poacc:billing:3.0";
+const PROFILE: &str = "urn:fdc:peppol.eu:2017:poacc:billing:01:1.0";
+
+/// Peppol electronic address scheme (EAS) of a VAT ID, by its prefix.
+fn vat_scheme(tax_id: &str) -> Option<&'static str> {
+    let scheme = match tax_id.get(..2)?.to_uppercase().as_str() {
+        "AT" => "9914",
+        "BE" => "9925",
+        "DE" => "9930",
+        "EE" => "9931",
+        "EL" => "9933",
+        "ES" => "9920",
+        "FI" => "0213",
+        "FR" => "9957",
+        "GB" => "9932",
+        "HR" => "9934",
+        "HU" => "9910",
+        "IE" => "9935",
+        "IT" => "0211",
+        "LT" => "9937",
+        "LU" => "9938",
+        "LV" => "9939",
+        "MT" => "9943",
+        "NL" => "9944",
+        "PL" => "9945",
+        "PT" => "9946",
+        "RO" => "9947",
+        "SI" => "9949",
+        "SK" => "9950",
+        _ => return None,
+    };
+    Some(scheme)
+}
+
+/// Where a party receives documents on the Peppol network: its VAT ID when
+/// the country has a VAT scheme, otherwise its email address.
+fn endpoint<'a>(tax_id: Option<&'a String>, contact: &'a Contact) -> Option<(&'static str, &'a str)> {
+    tax_id
+        .filter(|tax_id| is_vat_id(tax_id))
+        .and_then(|tax_id| vat_scheme(tax_id).map(|scheme| (scheme, tax_id.as_str())))
+        .or_else(|| contact.email.as_deref().map(|email| ("EM", email)))
+}
+
+/// UNCL 4461 payment means code of a method by its kind, guessed from its
+/// name for other methods, `1` ("not defined") when nothing matches.
+fn means_code(method: &Methods) -> &'static str {
+    match method.kind {
+        MethodKind::Sepa => return "58",
+        MethodKind::Qrbill | MethodKind::Bank => return "30",
+        MethodKind::Card => return "48",
+        MethodKind::Paypal => return "68",
+        MethodKind::Check => return "20",
+        MethodKind::Cash => return "10",
+        MethodKind::Crypto | MethodKind::Other => {}
+    }
+    let name = method.name.to_lowercase();
+    if name.contains("card") {
+        "48"
+    } else if name.contains("paypal") || name.contains("online") {
+        "68"
+    } else if name.contains("check") || name.contains("cheque") {
+        "20"
+    } else if name.contains("cash") {
+        "10"
+    } else {
+        "1"
+    }
+}
+
+/// A UBL 2.1 invoice following the Peppol BIS Billing 3.0 rules, for
+/// procurement portals that take XML rather than PDF.
+pub struct Ubl;
+
+impl Ubl {
+    /// Checks that `invoice` has the fields Peppol BIS makes mandatory.
+    pub fn check(&self, invoice: &Invoice) -> Result<(), anyhow::Error> {
+        let mut problems = Problems::default();
+        check_parties(invoice, &mut problems);
+        let company = &invoice.template.company;
+        let client = &invoice.template.client;
+        problems.require(client.contact.country.is_some(), format!("client {} has no country", client.name));
+        if let Some(tax_id) = &company.tax_id {
+            problems.require(
+                is_vat_id(tax_id),
+                format!("the company tax ID {} is not a VAT ID with a country prefix, e.g. DE123456789", tax_id),
+            );
+        }
+        problems.require(
+            endpoint(company.tax_id.as_ref(), &company.contact).is_some(),
+            format!("company {} needs an EU VAT ID or an email address to be reached on Peppol", company.name),
+        );
+        problems.require(
+            endpoint(client.tax_id.as_ref(), &client.contact).is_some(),
+            format!("client {} needs an EU VAT ID or an email address to be reached on Peppol", client.name),
+        );
+        problems.require(!invoice.items.is_empty(), "the invoice has no lines");
+        for line in &invoice.items {
+            problems.require(
+                line.taxes.len() <= 1,
+                format!("line {} has more than one tax, e-invoice lines carry a single VAT rate", line.name),
+            );
+            problems.require(!line.rate.is_sign_negative(), format!("line {} has a negative price", line.name));
+        }
+        problems.into_result("a UBL invoice")
+    }
+
+    /// The invoice as UBL XML.
+    pub fn xml(&self, invoice: &Invoice) -> String {
+        let totals = Totals::new(invoice);
+        let money = |tag: &str| format!("{} currencyID=\"{}\"", tag, invoice.currency);
+        let mut xml = Xml::new();
+        xml.open(concat!(
+            "Invoice",
+            " xmlns=\"urn:oasis:names:specification:ubl:schema:xsd:Invoice-2\"",
+            " xmlns:cac=\"urn:oasis:names:specification:ubl:schema:xsd:CommonAggregateComponents-2\"",
+            " xmlns:cbc=\"urn:oasis:names:specification:ubl:schema:xsd:CommonBasicComponents-2\"",
+        ));
+        xml.leaf("cbc:CustomizationID", CUSTOMIZATION);
+        xml.leaf("cbc:ProfileID", PROFILE);
+        xml.leaf("cbc:ID", &invoice.reference());
+        xml.leaf("cbc:IssueDate", &invoice.issue_date().format("%Y-%m-%d").to_string());
+        xml.leaf("cbc:DueDate", &invoice.due_date().format("%Y-%m-%d").to_string());
+        xml.leaf("cbc:InvoiceTypeCode", "380");
+        if let Some(notes) = &invoice.notes {
+            xml.leaf("cbc:Note", notes);
+        }
+        xml.leaf("cbc:DocumentCurrencyCode", invoice.currency.code());
+        xml.leaf("cbc:BuyerReference", &invoice.reference());
+
+        let company = &invoice.template.company;
+        let client = &invoice.template.client;
+        xml.open("cac:AccountingSupplierParty");
+        self.party(&mut xml, &company.name, company.tax_id.as_ref(), &company.contact);
+        xml.close("cac:AccountingSupplierParty");
+        xml.open("cac:AccountingCustomerParty");
+        self.party(&mut xml, &client.name, client.tax_id.as_ref(), &client.contact);
+        xml.close("cac:AccountingCustomerParty");
+
+        for method in &invoice.template.methods {
+            xml.open("cac:PaymentMeans");
+            xml.leaf(&format!("cbc:PaymentMeansCode name=\"{}\"", escape_xml(&method.name)), means_code(method));
+            xml.leaf("cbc:PaymentID", &invoice.reference());
+            if let Some(account) = method.account() {
+                xml.open("cac:PayeeFinancialAccount");
+                xml.leaf("cbc:ID", &account.replace([' ', '-'], ""));
+                if let Some(beneficiary) = &method.beneficiary {
+                    xml.leaf("cbc:Name", beneficiary);
+                }
+                if let Some(bic) = &method.bic {
+                    xml.open("cac:FinancialInstitutionBranch");
+                    xml.leaf("cbc:ID", bic);
+                    xml.close("cac:FinancialInstitutionBranch");
+                }
+                xml.close("cac:PayeeFinancialAccount");
+            }
+            xml.close("cac:PaymentMeans");
+        }
+        xml.open("cac:PaymentTerms");
+        xml.leaf("cbc:Note", &invoice.template.terms.name);
+        xml.close("cac:PaymentTerms");
+
+        for category in totals.categories.iter().filter(|category| !category.allowance.is_zero()) {
+            xml.open("cac:AllowanceCharge");
+            xml.leaf("cbc:ChargeIndicator", "false");
+            xml.leaf("cbc:AllowanceChargeReason", "Discount");
+            xml.leaf(&money("cbc:Amount"), &amount(category.allowance));
+            xml.open("cac:TaxCategory");
+            xml.leaf("cbc:ID", category.code);
+            xml.leaf("cbc:Percent", &percent(category.rate));
+            self.tax_scheme(&mut xml);
+            xml.close("cac:TaxCategory");
+            xml.close("cac:AllowanceCharge");
+        }
+
+        xml.open("cac:TaxTotal");
+        xml.leaf(&money("cbc:TaxAmount"), &amount(totals.tax));
+        for category in &totals.categories {
+            xml.open("cac:TaxSubtotal");
+            xml.leaf(&money("cbc:TaxableAmount"), &amount(category.basis));
+            xml.leaf(&money("cbc:TaxAmount"), &amount(category.tax));
+            xml.open("cac:TaxCategory");
+            xml.leaf("cbc:ID", category.code);
+            xml.leaf("cbc:Percent", &percent(category.rate));
+            self.tax_scheme(&mut xml);
+            xml.close("cac:TaxCategory");
+            xml.close("cac:TaxSubtotal");
+        }
+        xml.close("cac:TaxTotal");
+
+        xml.open("cac:LegalMonetaryTotal");
+        xml.leaf(&money("cbc:LineExtensionAmount"), &amount(totals.lines));
+        xml.leaf(&money("cbc:TaxExclusiveAmount"), &amount(totals.basis));
+        xml.leaf(&money("cbc:TaxInclusiveAmount"), &amount(totals.grand));
+        xml.leaf(&money("cbc:AllowanceTotalAmount"), &amount(totals.allowances));
+        if !totals.prepaid.is_zero() {
+            xml.leaf(&money("cbc:PrepaidAmount"), &amount(totals.prepaid));
+        }
+        xml.leaf(&money("cbc:PayableAmount"), &amount(totals.due));
+        xml.close("cac:LegalMonetaryTotal");
+
+        for (index, line) in invoice.items.iter().enumerate() {
+            let (category, rate) = vat_category(line);
+            xml.open("cac:InvoiceLine");
+            xml.leaf("cbc:ID", &(index + 1).to_string());
+            xml.leaf(
+                &format!("cbc:InvoicedQuantity unitCode=\"{}\"", unit_code(line.unit.as_deref())),
+                &line.quantity.normalize().to_string(),
+            );
+            xml.leaf(&money("cbc:LineExtensionAmount"), &amount(line.subtotal(&invoice.currency)));
+            let discount = line.discount_amount(&invoice.currency);
+            if !discount.is_zero() {
+                xml.open("cac:AllowanceCharge");
+                xml.leaf("cbc:ChargeIndicator", "false");
+                xml.leaf("cbc:AllowanceChargeReason", "Discount");
+                xml.leaf(&money("cbc:Amount"), &amount(discount));
+                xml.close("cac:AllowanceCharge");
+            }
+            xml.open("cac:Item");
+            if let Some(description) = &line.description {
+                xml.leaf("cbc:Description", description);
+            }
+            xml.leaf("cbc:Name", &line.name);
+            xml.open("cac:ClassifiedTaxCategory");
+            xml.leaf("cbc:ID", category);
+            xml.leaf("cbc:Percent", &percent(rate));
+            self.tax_scheme(&mut xml);
+            xml.close("cac:ClassifiedTaxCategory");
+            xml.close("cac:Item");
+            xml.open("cac:Price");
+            xml.leaf(&money("cbc:PriceAmount"), &line.rate.normalize().to_string());
+            xml.close("cac:Price");
+            xml.close("cac:InvoiceLine");
+        }
+        xml.close("Invoice");
+        xml.finish()
+    }
+
+    fn party(&self, xml: &mut Xml, name: &str, tax_id: Option<&String>, contact: &Contact) {
+        xml.open("cac:Party");
+        if let Some((scheme, id)) = endpoint(tax_id, contact) {
+            xml.leaf(&format!("cbc:EndpointID schemeID=\"{}\"", scheme), id);
+        }
+        xml.open("cac:PartyName");
+        xml.leaf("cbc:Name", name);
+        xml.close("cac:PartyName");
+        xml.open("cac:PostalAddress");
+        let parts = [
+            ("cbc:StreetName", &contact.addr1),
+            ("cbc:AdditionalStreetName", &contact.addr2),
+            ("cbc:CityName", &contact.city),
+            ("cbc:PostalZone", &contact.zip),
+            ("cbc:CountrySubentity", &contact.state),
+        ];
+        for (tag, value) in parts {
+            if let Some(value) = value {
+                xml.leaf(tag, value);
+            }
+        }
+        if let Some(country) = &contact.country {
+            xml.open("cac:Country");
+            xml.leaf("cbc:IdentificationCode", country);
+            xml.close("cac:Country");
+        }
+        xml.close("cac:PostalAddress");
+        if let Some(tax_id) = tax_id.filter(|tax_id| is_vat_id(tax_id)) {
+            xml.open("cac:PartyTaxScheme");
+            xml.leaf("cbc:CompanyID", tax_id);
+            self.tax_scheme(xml);
+            xml.close("cac:PartyTaxScheme");
+        }
+        xml.open("cac:PartyLegalEntity");
+        xml.leaf("cbc:RegistrationName", name);
+        xml.close("cac:PartyLegalEntity");
+        if contact.phone.is_some() || contact.email.is_some() {
+            xml.open("cac:Contact");
+            if let Some(phone) = &contact.phone {
+                xml.leaf("cbc:Telephone", phone);
+            }
+            if let Some(email) = &contact.email {
+                xml.leaf("cbc:ElectronicMail", email);
+            }
+            xml.close("cac:Contact");
+        }
+        xml.close("cac:Party");
+    }
+
+    fn tax_scheme(&self, xml: &mut Xml) {
+        xml.open("cac:TaxScheme");
+        xml.leaf("cbc:ID", "VAT");
+        xml.close("cac:TaxScheme");
+    }
+}
@@ -1,35 +1,158 @@
+use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
+use crate::models::credit_note::CreditNote;
+use crate::models::currency::Currency;
 use crate::models::invoice::Invoice;
+use crate::models::methods::Methods;
+use crate::render::einvoice::FacturX;
+use crate::models::page::{expand_template, Orientation, PageFields, PageSetup, PageTemplate};
 use anyhow::{Error, Result};
-use tera::{Context, Tera};
+use clap::ValueEnum;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tera::{Context, Tera, Value};
 use headless_chrome::{Browser, LaunchOptions};
 use headless_chrome::types::PrintToPdfOptions;
 
+pub mod einvoice;
+mod pdf;
+mod qr;
+mod qrbill;
+mod sample;
+
+/// How PDFs are produced from rendered documents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum PdfEngine {
+    /// Print the HTML layout with headless Chrome
+    #[default]
+    Chrome,
+    /// Draw the document directly, without a browser. Ignores the HTML layout
+    Native,
+}
+
+impl fmt::Display for PdfEngine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PdfEngine::Chrome => write!(f, "chrome"),
+            PdfEngine::Native => write!(f, "native"),
+        }
+    }
+}
+
+impl FromStr for PdfEngine {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chrome" => Ok(PdfEngine::Chrome),
+            "native" => Ok(PdfEngine::Native),
+            _ => Err(format!("Invalid PDF engine, expected chrome or native: {}", s)),
+        }
+    }
+}
+
 pub struct TemplateEngine {
     tera: Tera,
 }
 
 impl TemplateEngine {
-    pub fn new(template_path: &PathBuf) -> Result<Self> {
+    pub fn new(template_path: &Path) -> Result<Self> {
         let template_str = &template_path.to_str().unwrap();
         let glob_pattern = format!("{}/**/*", template_str);
         let tera = Tera::new(&glob_pattern)
             .map_err(|e| Error::msg(format!("Failed to initalize Tera: {}", e)))?;
         Ok(TemplateEngine { tera })
     }
-    pub fn render(&self, invoice: &Invoice) -> Result<String> {
-        let context = Context::from_serialize(invoice)
+    /// Renders `invoice` with `layout`, followed by its QR-bill payment part
+    /// when it is paid by one.
+    pub fn render(&self, invoice: &Invoice, layout: &str) -> Result<String> {
+        let rendered = self.render_context(layout, &invoice_context(invoice)?, invoice.currency)?;
+        let bill = match qrbill::QrBill::new(invoice).and_then(|bill| bill.map(|bill| bill.html()).transpose()) {
+            Ok(bill) => bill,
+            Err(e) => {
+                eprintln!("Leaving out the QR-bill payment part of {}: {}", invoice.reference(), e);
+                None
+            }
+        };
+        Ok(match bill {
+            Some(bill) => match rendered.rfind("</body>") {
+                Some(end) => format!("{}{}{}", &rendered[..end], bill, &rendered[end..]),
+                None => rendered + &bill,
+            },
+            None => rendered,
+        })
+    }
+    pub fn render_credit_note(&self, credit: &CreditNote) -> Result<String> {
+        self.render_template("credit_note.html", credit, credit.currency)
+    }
+    /// Every file in the templates directory, sorted by name.
+    pub fn layouts(&self) -> Vec<String> {
+        let mut layouts = self.tera.get_template_names().map(String::from).collect::<Vec<_>>();
+        layouts.sort();
+        layouts
+    }
+    pub fn require_layout(&self, layout: &str) -> Result<()> {
+        if self.layouts().iter().any(|name| name == layout) {
+            Ok(())
+        } else {
+            Err(Error::msg(format!("Unknown layout {}, see `layouts list`", layout)))
+        }
+    }
+    /// Test-renders `layout` against a sample document. Layouts named
+    /// `credit_note*` get a sample credit note, all others a sample invoice.
+    pub fn validate(&self, layout: &str) -> Result<()> {
+        let (context, currency) = if layout.starts_with("credit_note") {
+            let credit = sample::credit_note();
+            let context = Context::from_serialize(&credit).map_err(|e| Error::msg(format!("Context error: {}", e)))?;
+            (context, credit.currency)
+        } else {
+            let invoice = sample::invoice();
+            (invoice_context(&invoice)?, invoice.currency)
+        };
+        self.with_money(currency).render(layout, &context).map_err(|e| {
+            let mut message = e.to_string();
+            let mut source = std::error::Error::source(&e);
+            while let Some(cause) = source {
+                message.push_str(&format!(": {}", cause));
+                source = cause.source();
+            }
+            Error::msg(message)
+        })?;
+        Ok(())
+    }
+    fn render_template<T: Serialize>(&self, name: &str, document: &T, currency: Currency) -> Result<String> {
+        let context = Context::from_serialize(document)
             .map_err(|e| Error::msg(format!("Context error: {}", e)))?;
-
-        let rendered = self.tera.render("default.html", &context).map_err(|e| {
+        self.render_context(name, &context, currency)
+    }
+    fn render_context(&self, name: &str, context: &Context, currency: Currency) -> Result<String> {
+        let rendered = self.with_money(currency).render(name, context).map_err(|e| {
             eprintln!("Detailed error: {:?}", e);
             Error::msg(format!("Template rendering error: {}", e))
         })?;
         Ok(rendered)
     }
+    /// The layouts with a `money` filter writing amounts in `currency`,
+    /// e.g. `{{ total | money }}`.
+    fn with_money(&self, currency: Currency) -> Tera {
+        let mut tera = self.tera.clone();
+        tera.register_filter("money", move |value: &Value, _: &HashMap<String, Value>| {
+            let amount = match value {
+                Value::String(text) => text.parse::<Decimal>().ok(),
+                Value::Number(number) => number.to_string().parse::<Decimal>().ok(),
+                _ => None,
+            };
+            amount
+                .map(|amount| Value::String(currency.format(amount)))
+                .ok_or_else(|| tera::Error::msg(format!("money expects an amount, got {}", value)))
+        });
+        tera
+    }
     pub fn to_file(&self, rendered: &String, output_file: &PathBuf) -> Result<()> {
         let mut file = File::create(output_file)
             .map_err(|e| Error::msg(format!("Failed to create output file: {}", e)))?;
@@ -39,31 +162,59 @@ impl TemplateEngine {
 
         Ok(())
     }
-    pub fn to_pdf(&self, input_file: &PathBuf) -> Result<PathBuf, anyhow::Error> {
+    /// Writes the PDF for `invoice`, whose HTML was written to `html_file`,
+    /// alongside it. A Factur-X invoice is always drawn by the native engine.
+    pub fn invoice_pdf(
+        &self,
+        invoice: &Invoice,
+        html_file: &Path,
+        engine: PdfEngine,
+        page: &PageSetup,
+        facturx: Option<FacturX>,
+    ) -> Result<PathBuf> {
+        match (engine, facturx) {
+            (_, Some(facturx)) => pdf::write_invoice(invoice, html_file, page, Some(facturx.archival(invoice))),
+            (PdfEngine::Chrome, None) => self.to_pdf(html_file, page, &PageFields::invoice(invoice)),
+            (PdfEngine::Native, None) => pdf::write_invoice(invoice, html_file, page, None),
+        }
+    }
+    pub fn credit_note_pdf(&self, credit: &CreditNote, html_file: &Path, engine: PdfEngine, page: &PageSetup) -> Result<PathBuf> {
+        match engine {
+            PdfEngine::Chrome => self.to_pdf(html_file, page, &PageFields::credit_note(credit)),
+            PdfEngine::Native => pdf::write_credit_note(credit, html_file, page),
+        }
+    }
+    /// Prints `input_file` with headless Chrome. Options the page setup
+    /// leaves unset keep Chrome's defaults, and `@page` rules in the layout
+    /// take precedence over the margins.
+    pub fn to_pdf(&self, input_file: &Path, page: &PageSetup, fields: &PageFields) -> Result<PathBuf, anyhow::Error> {
         let input = format!("file://{}", input_file.canonicalize()?.display());
 
-        let mut output_file = input_file.clone();
+        let mut output_file = input_file.to_path_buf();
         output_file.set_extension("pdf");
 
         let browser = Browser::new(LaunchOptions::default_builder().build().unwrap())?;
         let tab = browser.new_tab()?;
         tab.navigate_to(&input)?.wait_until_navigated()?;
 
+        let inches = |points: f32| (points / 72.0) as f64;
+        let paper = page.paper.map(|paper| paper.points());
+        let decorated = page.header.is_some() || page.footer.is_some();
         let pdf_options = PrintToPdfOptions {
-            landscape: None,
-            display_header_footer: None,
+            landscape: page.orientation.map(|orientation| orientation == Orientation::Landscape),
+            display_header_footer: decorated.then_some(true),
             print_background: Some(true),
-            scale: None,
-            paper_width: None,
-            paper_height: None,
-            margin_top: None,
-            margin_bottom: None,
-            margin_left: None,
-            margin_right: None,
+            scale: page.scale.map(|scale| scale as f64),
+            paper_width: paper.map(|(width, _)| inches(width)),
+            paper_height: paper.map(|(_, height)| inches(height)),
+            margin_top: page.margins.map(|margins| inches(margins.top)),
+            margin_bottom: page.margins.map(|margins| inches(margins.bottom)),
+            margin_left: page.margins.map(|margins| inches(margins.left)),
+            margin_right: page.margins.map(|margins| inches(margins.right)),
             page_ranges: None,
             ignore_invalid_page_ranges: None,
-            header_template: None,
-            footer_template: None,
+            header_template: decorated.then(|| chrome_template(page.header.as_ref(), fields)),
+            footer_template: decorated.then(|| chrome_template(page.footer.as_ref(), fields)),
             prefer_css_page_size: None,
             transfer_mode: None,
         };
@@ -75,3 +226,57 @@ impl TemplateEngine {
         Ok(output_file)
     }
 }
+
+/// The SEPA payment QR code of `method` for what is left to pay on
+/// `invoice`, as a PNG. None for other methods and settled invoices.
+pub(crate) fn payment_qr(invoice: &Invoice, method: &Methods) -> Result<Option<Vec<u8>>> {
+    let Some(payload) = method.epc_payload(invoice.balance(), &invoice.currency, &invoice.reference()).map_err(Error::msg)? else {
+        return Ok(None);
+    };
+    Ok(Some(qr::QrCode::encode(payload.as_bytes(), 13)?.png()?))
+}
+
+/// The Tera context of an invoice. Each payment method gains `epc_qr`, a
+/// data URI of its generated payment code, or null when it has none.
+fn invoice_context(invoice: &Invoice) -> Result<Context> {
+    let mut value = serde_json::to_value(invoice).map_err(|e| Error::msg(format!("Context error: {}", e)))?;
+    if let Some(methods) = value.pointer_mut("/template/methods").and_then(Value::as_array_mut) {
+        for (method, entry) in invoice.template.methods.iter().zip(methods) {
+            let code = match payment_qr(invoice, method) {
+                Ok(code) => code,
+                Err(e) => {
+                    eprintln!("Leaving out the payment QR code for {}: {}", method.name, e);
+                    None
+                }
+            };
+            entry["epc_qr"] = code
+                .map(|png| Value::String(format!("data:image/png;base64,{}", STANDARD.encode(png))))
+                .unwrap_or(Value::Null);
+        }
+    }
+    Context::from_value(value).map_err(|e| Error::msg(format!("Context error: {}", e)))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// A header or footer as the HTML snippet Chrome expects, using its
+/// `pageNumber` and `totalPages` classes for the page numbers. An empty
+/// snippet hides Chrome's default header or footer.
+fn chrome_template(template: Option<&PageTemplate>, fields: &PageFields) -> String {
+    let text = match template {
+        Some(template) => expand_template(
+            &template.0,
+            fields,
+            "<span class=\"pageNumber\"></span>",
+            "<span class=\"totalPages\"></span>",
+            escape_html,
+        ),
+        None => String::new(),
+    };
+    format!(
+        "<div style=\"width: 100%; font-size: 9px; font-family: sans-serif; color: #606060; text-align: center;\">{}</div>",
+        text
+    )
+}
@@ -0,0 +1,141 @@
+// Pieces a PDF/A-3b file needs beyond ordinary drawing: XMP metadata that
+// declares the conformance, an sRGB output intent, and files attached with
+// their relationship to the document.
+
+/// A file carried inside the PDF, such as the XML of an e-invoice.
+#[derive(Debug)]
+pub struct Attachment {
+    pub name: String,
+    pub mime: String,
+    pub description: String,
+    /// The PDF/A-3 AFRelationship, e.g. `Data` or `Alternative`.
+    pub relationship: String,
+    pub data: Vec<u8>,
+}
+
+/// Turns a document into PDF/A-3b when set with `Document::archive`.
+#[derive(Debug, Default)]
+pub struct Archive {
+    /// Extra `rdf:Description` elements for the XMP metadata, e.g. an
+    /// extension schema describing the attachments.
+    pub metadata: String,
+    pub attachments: Vec<Attachment>,
+}
+
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// The XMP packet of a PDF/A-3b file. `title`, `producer` and `date` must
+/// match the document information dictionary.
+pub fn xmp(title: &str, producer: &str, date: &str, extra: &str) -> String {
+    format!(
+        concat!(
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n",
+            "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n",
+            "<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n",
+            "<rdf:Description rdf:about=\"\" xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\">\n",
+            "<pdfaid:part>3</pdfaid:part>\n",
+            "<pdfaid:conformance>B</pdfaid:conformance>\n",
+            "</rdf:Description>\n",
+            "<rdf:Description rdf:about=\"\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n",
+            "<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:title>\n",
+            "</rdf:Description>\n",
+            "<rdf:Description rdf:about=\"\" xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\">\n",
+            "<pdf:Producer>{}</pdf:Producer>\n",
+            "</rdf:Description>\n",
+            "<rdf:Description rdf:about=\"\" xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\">\n",
+            "<xmp:CreateDate>{}</xmp:CreateDate>\n",
+            "<xmp:ModifyDate>{}</xmp:ModifyDate>\n",
+            "</rdf:Description>\n",
+            "{}",
+            "</rdf:RDF>\n",
+            "</x:xmpmeta>\n",
+            "<?xpacket end=\"w\"?>"
+        ),
+        escape_xml(title),
+        escape_xml(producer),
+        date,
+        date,
+        extra
+    )
+}
+
+/// A minimal ICC v2 display profile for sRGB: D50-adapted primaries and a
+/// 2.2 gamma curve, enough to serve as the PDF/A output intent.
+pub fn srgb_profile() -> Vec<u8> {
+    fn fixed(value: f64) -> [u8; 4] {
+        ((value * 65536.0).round() as i32).to_be_bytes()
+    }
+    fn xyz(x: f64, y: f64, z: f64) -> Vec<u8> {
+        let mut tag = b"XYZ \0\0\0\0".to_vec();
+        for value in [x, y, z] {
+            tag.extend_from_slice(&fixed(value));
+        }
+        tag
+    }
+    let description = b"sRGB IEC61966-2.1";
+    let mut desc = b"desc\0\0\0\0".to_vec();
+    desc.extend_from_slice(&(description.len() as u32 + 1).to_be_bytes());
+    desc.extend_from_slice(description);
+    desc.push(0);
+    // Empty Unicode and ScriptCode descriptions.
+    desc.extend_from_slice(&[0; 8]);
+    desc.extend_from_slice(&[0; 3]);
+    desc.extend_from_slice(&[0; 67]);
+    let mut copyright = b"text\0\0\0\0".to_vec();
+    copyright.extend_from_slice(b"No copyright, use freely\0");
+    // curveType with a single u8Fixed8 gamma of 2.2.
+    let curve = b"curv\0\0\0\0\0\0\0\x01\x02\x33".to_vec();
+
+    let tags: Vec<(&[u8; 4], Vec<u8>)> = vec![
+        (b"desc", desc),
+        (b"cprt", copyright),
+        (b"wtpt", xyz(0.9642, 1.0, 0.8249)),
+        (b"rXYZ", xyz(0.4361, 0.2225, 0.0139)),
+        (b"gXYZ", xyz(0.3851, 0.7169, 0.0971)),
+        (b"bXYZ", xyz(0.1431, 0.0606, 0.7141)),
+        (b"rTRC", curve.clone()),
+        (b"gTRC", curve.clone()),
+        (b"bTRC", curve),
+    ];
+    let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+    let mut data = Vec::new();
+    let mut offset = 128 + 4 + tags.len() * 12;
+    for (signature, tag) in &tags {
+        table.extend_from_slice(*signature);
+        table.extend_from_slice(&(offset as u32).to_be_bytes());
+        table.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+        data.extend_from_slice(tag);
+        while data.len() % 4 != 0 {
+            data.push(0);
+        }
+        offset = 128 + 4 + tags.len() * 12 + data.len();
+    }
+
+    let mut header = Vec::with_capacity(128);
+    header.extend_from_slice(&((128 + table.len() + data.len()) as u32).to_be_bytes());
+    header.extend_from_slice(&[0; 4]);
+    header.extend_from_slice(&[2, 0x10, 0, 0]);
+    header.extend_from_slice(b"mntrRGB XYZ ");
+    for part in [2026u16, 1, 1, 0, 0, 0] {
+        header.extend_from_slice(&part.to_be_bytes());
+    }
+    header.extend_from_slice(b"acsp");
+    header.extend_from_slice(&[0; 24]);
+    // Perceptual intent and the D50 illuminant of the connection space.
+    header.extend_from_slice(&[0; 4]);
+    header.extend_from_slice(&fixed(0.9642));
+    header.extend_from_slice(&fixed(1.0));
+    header.extend_from_slice(&fixed(0.8249));
+    header.resize(128, 0);
+
+    let mut profile = header;
+    profile.extend_from_slice(&table);
+    profile.extend_from_slice(&data);
+    profile
+}
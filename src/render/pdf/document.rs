@@ -0,0 +1,621 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Write as _;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use crate::render::pdf::archive::{srgb_profile, xmp, Archive};
+use crate::render::pdf::image::Image;
+use crate::render::pdf::truetype::TrueType;
+
+/// The faces used by the native layout. They are the standard Helvetica
+/// fonts built into every PDF reader unless `Document::embed_fonts`
+/// replaces them with TrueType fonts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Font {
+    Regular,
+    Bold,
+}
+
+// Advance widths of the printable ASCII range, in 1/1000 em, from the
+// Helvetica and Helvetica-Bold AFM files.
+const REGULAR_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+const BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611,
+    975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556,
+    333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611,
+    611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+impl Font {
+    fn resource(&self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        }
+    }
+    fn base_font(&self) -> &'static str {
+        match self {
+            Font::Regular => "Helvetica",
+            Font::Bold => "Helvetica-Bold",
+        }
+    }
+    /// Width of the WinAnsi `code` in 1/1000 em.
+    fn standard_width(&self, code: u8) -> u16 {
+        let widths = match self {
+            Font::Regular => &REGULAR_WIDTHS,
+            Font::Bold => &BOLD_WIDTHS,
+        };
+        match code {
+            32..=126 => widths[(code - 32) as usize],
+            _ => 556,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color(pub u8, pub u8, pub u8);
+
+impl Color {
+    pub const BLACK: Color = Color(0, 0, 0);
+    pub const WHITE: Color = Color(255, 255, 255);
+    pub const GREY: Color = Color(96, 96, 96);
+
+    fn components(&self) -> String {
+        format!("{:.3} {:.3} {:.3}", self.0 as f32 / 255.0, self.1 as f32 / 255.0, self.2 as f32 / 255.0)
+    }
+}
+
+/// Maps `text` to WinAnsiEncoding, the encoding the standard fonts use.
+/// Characters it cannot represent are replaced by `?`.
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' => c as u8,
+            '\u{a0}'..='\u{ff}' => c as u32 as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            '\t' => b' ',
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// Whether every character of `text` survives `encode`.
+pub fn encodable(text: &str) -> bool {
+    text.chars().zip(encode(text)).all(|(c, code)| code != b'?' || c == '?')
+}
+
+/// The character `encode` maps to `code`, if any.
+pub fn decode(code: u8) -> Option<char> {
+    match code {
+        b' '..=b'~' | 0xa0..=0xff => Some(code as char),
+        0x80 => Some('€'),
+        0x85 => Some('…'),
+        0x91 => Some('‘'),
+        0x92 => Some('’'),
+        0x93 => Some('“'),
+        0x94 => Some('”'),
+        0x95 => Some('•'),
+        0x96 => Some('–'),
+        0x97 => Some('—'),
+        _ => None,
+    }
+}
+
+/// `text` as a PDF text string, in UTF-16 so any character survives.
+fn text_string(text: &str) -> String {
+    let units: String = text.encode_utf16().map(|unit| format!("{:04X}", unit)).collect();
+    format!("<FEFF{}>", units)
+}
+
+/// One page of drawing operators. Coordinates are in points from the bottom
+/// left corner, as in PDF itself.
+#[derive(Debug, Default)]
+pub struct Page {
+    content: String,
+}
+
+impl Page {
+    pub fn text(&mut self, x: f32, y: f32, font: Font, size: f32, color: Color, text: &str) {
+        let hex: String = encode(text).iter().map(|byte| format!("{:02X}", byte)).collect();
+        let _ = writeln!(
+            self.content,
+            "BT /{} {:.2} Tf {} rg {:.2} {:.2} Td <{}> Tj ET",
+            font.resource(),
+            size,
+            color.components(),
+            x,
+            y,
+            hex
+        );
+    }
+    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        let _ = writeln!(self.content, "{} rg {:.2} {:.2} {:.2} {:.2} re f", color.components(), x, y, width, height);
+    }
+    /// Strokes a black line `width` thick through `points`, dashed with
+    /// dashes and gaps `dash` long when that is set.
+    pub fn line(&mut self, points: &[(f32, f32)], width: f32, dash: Option<f32>) {
+        let mut path = String::new();
+        for (index, (x, y)) in points.iter().enumerate() {
+            let _ = write!(path, "{:.2} {:.2} {} ", x, y, if index == 0 { "m" } else { "l" });
+        }
+        let dash = dash.map(|dash| format!("[{:.2}] 0 d ", dash)).unwrap_or_default();
+        let _ = writeln!(self.content, "q 0 G {:.2} w {}{}S Q", width, dash, path);
+    }
+    /// Draws the image added to the document as `image`, scaled to the box
+    /// with its lower left corner at `x`, `y`.
+    pub fn image(&mut self, image: usize, x: f32, y: f32, width: f32, height: f32) {
+        let _ = writeln!(self.content, "q {:.2} 0 0 {:.2} {:.2} {:.2} cm /Im{} Do Q", width, height, x, y, image);
+    }
+}
+
+/// A PDF under construction, written out with `finish`.
+#[derive(Debug)]
+pub struct Document {
+    pub width: f32,
+    pub height: f32,
+    /// Factor every page's drawing is scaled by.
+    pub scale: f32,
+    pub pages: Vec<Page>,
+    images: Vec<Image>,
+    title: String,
+    fonts: Option<Box<[TrueType; 2]>>,
+    archive: Option<Archive>,
+}
+
+impl Document {
+    pub fn new(width: f32, height: f32, scale: f32, title: &str) -> Self {
+        Document {
+            width,
+            height,
+            scale,
+            pages: Vec::new(),
+            images: Vec::new(),
+            title: title.to_string(),
+            fonts: None,
+            archive: None,
+        }
+    }
+    /// Sets text in `regular` and `bold` instead of the standard fonts.
+    /// Must be called before anything is measured.
+    pub fn embed_fonts(&mut self, regular: TrueType, bold: TrueType) {
+        self.fonts = Some(Box::new([regular, bold]));
+    }
+    /// Writes the document as PDF/A-3b with `archive`'s metadata and
+    /// attachments. Needs embedded fonts.
+    pub fn archive(&mut self, archive: Archive) {
+        self.archive = Some(archive);
+    }
+    /// Width of `text` set in `font` at `size`, in points.
+    pub fn width(&self, font: Font, text: &str, size: f32) -> f32 {
+        let units: u32 = encode(text)
+            .iter()
+            .map(|&code| match &self.fonts {
+                Some(fonts) => fonts[font as usize].widths[code as usize] as u32,
+                None => font.standard_width(code) as u32,
+            })
+            .sum();
+        units as f32 * size / 1000.0
+    }
+    /// Splits `text` into lines no wider than `max_width`, breaking on spaces
+    /// and, for words that do not fit on a line of their own, between letters.
+    pub fn wrap(&self, font: Font, text: &str, size: f32, max_width: f32) -> Vec<String> {
+        let mut lines = Vec::new();
+        for paragraph in text.lines() {
+            let mut line = String::new();
+            for word in paragraph.split_whitespace() {
+                let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+                if self.width(font, &candidate, size) <= max_width {
+                    line = candidate;
+                    continue;
+                }
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                for c in word.chars() {
+                    line.push(c);
+                    if self.width(font, &line, size) > max_width && line.chars().count() > 1 {
+                        line.pop();
+                        lines.push(std::mem::replace(&mut line, c.to_string()));
+                    }
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
+    /// Adds a page and returns its index.
+    pub fn add_page(&mut self) -> usize {
+        self.pages.push(Page::default());
+        self.pages.len() - 1
+    }
+    /// Adds an image that pages can draw, returning the id to draw it by.
+    pub fn add_image(&mut self, image: Image) -> usize {
+        self.images.push(image);
+        self.images.len() - 1
+    }
+    pub fn finish(self) -> Result<Vec<u8>> {
+        if self.archive.is_some() && self.fonts.is_none() {
+            bail!("PDF/A output needs embedded fonts");
+        }
+        let mut writer = ObjectWriter::new(if self.archive.is_some() { "1.7" } else { "1.4" });
+        // Object numbers: 1 catalog, 2 page tree, 3 and 4 fonts, 5 info, then
+        // font descriptors and files, the archive's metadata, output profile
+        // and attachments, the images with their soft masks, and finally
+        // each page and its content.
+        let mut next = 6;
+        let mut allocate = |count: usize| {
+            next += count;
+            next - count
+        };
+        let font_ids = self.fonts.as_ref().map(|_| allocate(4));
+        let archive_ids = self
+            .archive
+            .as_ref()
+            .map(|archive| (allocate(1), allocate(1), allocate(archive.attachments.len() * 2)));
+        let mut image_ids = Vec::new();
+        for image in &self.images {
+            let id = allocate(1);
+            image_ids.push((id, image.smask.as_ref().map(|_| allocate(1))));
+        }
+        let page_ids: Vec<usize> = self.pages.iter().map(|_| allocate(2)).collect();
+        let last_id = allocate(0) - 1;
+
+        let created = Utc::now();
+        let pdf_date = created.format("D:%Y%m%d%H%M%S+00'00'").to_string();
+        let mut catalog = "<< /Type /Catalog /Pages 2 0 R".to_string();
+        if let (Some(archive), Some((metadata, profile, attachments))) = (&self.archive, archive_ids) {
+            let xmp_date = created.format("%Y-%m-%dT%H:%M:%S+00:00").to_string();
+            let packet = xmp(&self.title, PRODUCER, &xmp_date, &archive.metadata);
+            writer.stream(metadata, "/Type /Metadata /Subtype /XML", packet.as_bytes());
+            writer.stream(profile, "/N 3 /Filter /FlateDecode", &deflate(&srgb_profile())?);
+            catalog.push_str(&format!(
+                concat!(
+                    " /Metadata {} 0 R /OutputIntents [<< /Type /OutputIntent /S /GTS_PDFA1",
+                    " /OutputConditionIdentifier (sRGB IEC61966-2.1) /Info (sRGB IEC61966-2.1)",
+                    " /DestOutputProfile {} 0 R >>]"
+                ),
+                metadata, profile
+            ));
+            let mut names = Vec::new();
+            let mut specs = Vec::new();
+            for (index, attachment) in archive.attachments.iter().enumerate() {
+                let (file, spec) = (attachments + index * 2, attachments + index * 2 + 1);
+                let mime = attachment.mime.replace('/', "#2F");
+                writer.stream(
+                    file,
+                    &format!(
+                        "/Type /EmbeddedFile /Subtype /{} /Params << /ModDate ({}) /Size {} >> /Filter /FlateDecode",
+                        mime,
+                        pdf_date,
+                        attachment.data.len()
+                    ),
+                    &deflate(&attachment.data)?,
+                );
+                let name = text_string(&attachment.name);
+                writer.object(
+                    spec,
+                    format!(
+                        "<< /Type /Filespec /F {} /UF {} /Desc {} /AFRelationship /{} /EF << /F {} 0 R /UF {} 0 R >> >>",
+                        name,
+                        name,
+                        text_string(&attachment.description),
+                        attachment.relationship,
+                        file,
+                        file
+                    )
+                    .as_bytes(),
+                );
+                names.push(format!("{} {} 0 R", name, spec));
+                specs.push(format!("{} 0 R", spec));
+            }
+            if !specs.is_empty() {
+                catalog.push_str(&format!(
+                    " /Names << /EmbeddedFiles << /Names [{}] >> >> /AF [{}]",
+                    names.join(" "),
+                    specs.join(" ")
+                ));
+            }
+        }
+        catalog.push_str(" >>");
+        writer.object(1, catalog.as_bytes());
+
+        let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+        writer.object(2, format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), page_ids.len()).as_bytes());
+        for font in [Font::Regular, Font::Bold] {
+            let id = if font == Font::Regular { 3 } else { 4 };
+            let (Some(fonts), Some(font_ids)) = (&self.fonts, font_ids) else {
+                writer.object(
+                    id,
+                    format!("<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>", font.base_font())
+                        .as_bytes(),
+                );
+                continue;
+            };
+            let face = &fonts[font as usize];
+            let (descriptor, file) = (font_ids + font as usize * 2, font_ids + font as usize * 2 + 1);
+            let widths: Vec<String> = face.widths[32..].iter().map(|width| width.to_string()).collect();
+            writer.object(
+                id,
+                format!(
+                    concat!(
+                        "<< /Type /Font /Subtype /TrueType /BaseFont /{} /Encoding /WinAnsiEncoding",
+                        " /FirstChar 32 /LastChar 255 /Widths [{}] /FontDescriptor {} 0 R >>"
+                    ),
+                    face.name,
+                    widths.join(" "),
+                    descriptor
+                )
+                .as_bytes(),
+            );
+            writer.object(
+                descriptor,
+                format!(
+                    concat!(
+                        "<< /Type /FontDescriptor /FontName /{} /Flags {} /FontBBox [{} {} {} {}]",
+                        " /ItalicAngle {:.2} /Ascent {} /Descent {} /CapHeight {} /StemV {} /FontFile2 {} 0 R >>"
+                    ),
+                    face.name,
+                    face.flags,
+                    face.bbox[0],
+                    face.bbox[1],
+                    face.bbox[2],
+                    face.bbox[3],
+                    face.italic_angle,
+                    face.ascent,
+                    face.descent,
+                    face.cap_height,
+                    face.stem_v,
+                    file
+                )
+                .as_bytes(),
+            );
+            writer.stream(
+                file,
+                &format!("/Length1 {} /Filter /FlateDecode", face.data.len()),
+                &deflate(&face.data)?,
+            );
+        }
+        let mut info = format!("<< /Title {} /Producer ({})", text_string(&self.title), PRODUCER);
+        if self.archive.is_some() {
+            info.push_str(&format!(" /CreationDate ({}) /ModDate ({})", pdf_date, pdf_date));
+        }
+        info.push_str(" >>");
+        writer.object(5, info.as_bytes());
+
+        for (image, (id, smask)) in self.images.iter().zip(&image_ids) {
+            let mut dict = image.dictionary();
+            if let Some(smask) = smask {
+                dict.push_str(&format!(" /SMask {} 0 R", smask));
+            }
+            writer.stream(*id, &dict, &image.data);
+            if let (Some(smask), Some(alpha)) = (smask, &image.smask) {
+                let dict = format!(
+                    "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceGray /BitsPerComponent 8 /Filter /FlateDecode",
+                    image.width, image.height
+                );
+                writer.stream(*smask, &dict, alpha);
+            }
+        }
+
+        let xobjects: String = image_ids.iter().enumerate().map(|(index, (id, _))| format!(" /Im{} {} 0 R", index, id)).collect();
+        let resources = format!("<< /Font << /F1 3 0 R /F2 4 0 R >> /XObject <<{} >> >>", xobjects);
+        for (page, id) in self.pages.iter().zip(&page_ids) {
+            writer.object(
+                *id,
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources {} /Contents {} 0 R >>",
+                    self.width,
+                    self.height,
+                    resources,
+                    id + 1
+                )
+                .as_bytes(),
+            );
+            let content = format!("q {:.4} 0 0 {:.4} 0 0 cm\n{}Q\n", self.scale, self.scale, page.content);
+            writer.stream(id + 1, "/Filter /FlateDecode", &deflate(content.as_bytes())?);
+        }
+
+        // The file identifier only has to be unique, not reproducible.
+        let mut hasher = DefaultHasher::new();
+        self.title.hash(&mut hasher);
+        created.timestamp_nanos_opt().hash(&mut hasher);
+        writer.buffer.len().hash(&mut hasher);
+        let first = hasher.finish();
+        first.hash(&mut hasher);
+        let id = format!("{:016X}{:016X}", first, hasher.finish());
+        Ok(writer.finish(last_id, &id))
+    }
+}
+
+const PRODUCER: &str = "invoice-cli";
+
+pub fn deflate(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+/// Serializes numbered objects and the cross-reference table pointing at them.
+struct ObjectWriter {
+    version: &'static str,
+    buffer: Vec<u8>,
+    offsets: Vec<(usize, usize)>,
+}
+
+impl ObjectWriter {
+    fn new(version: &'static str) -> Self {
+        ObjectWriter { version, buffer: Vec::new(), offsets: Vec::new() }
+    }
+    fn object(&mut self, id: usize, body: &[u8]) {
+        self.begin(id);
+        self.buffer.extend_from_slice(body);
+        self.buffer.extend_from_slice(b"\nendobj\n");
+    }
+    fn stream(&mut self, id: usize, dict: &str, data: &[u8]) {
+        self.begin(id);
+        self.buffer.extend_from_slice(format!("<< {} /Length {} >>\nstream\n", dict, data.len()).as_bytes());
+        self.buffer.extend_from_slice(data);
+        self.buffer.extend_from_slice(b"\nendstream\nendobj\n");
+    }
+    fn begin(&mut self, id: usize) {
+        if self.buffer.is_empty() {
+            self.buffer.extend_from_slice(format!("%PDF-{}\n", self.version).as_bytes());
+            self.buffer.extend_from_slice(b"%\xE2\xE3\xCF\xD3\n");
+        }
+        self.offsets.push((id, self.buffer.len()));
+        self.buffer.extend_from_slice(format!("{} 0 obj\n", id).as_bytes());
+    }
+    fn finish(mut self, last_id: usize, file_id: &str) -> Vec<u8> {
+        self.offsets.sort();
+        let xref = self.buffer.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", last_id + 1);
+        for (_, offset) in &self.offsets {
+            table.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+        table.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R /ID [<{}> <{}>] >>\nstartxref\n{}\n%%EOF\n",
+            last_id + 1,
+            file_id,
+            file_id,
+            xref
+        ));
+        self.buffer.extend_from_slice(table.as_bytes());
+        self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::settings::system_fonts;
+    use crate::render::pdf::archive::Attachment;
+
+    fn contains(pdf: &[u8], needle: &str) -> bool {
+        pdf.windows(needle.len()).any(|window| window == needle.as_bytes())
+    }
+
+    /// Checks that `startxref` leads to a table whose every entry points at
+    /// the start of its object, and returns the object count.
+    fn check_xref(pdf: &[u8]) -> usize {
+        let start = pdf.windows(10).rposition(|window| window == b"startxref\n").expect("no startxref");
+        let tail = std::str::from_utf8(&pdf[start..]).unwrap();
+        let xref: usize = tail.lines().nth(1).unwrap().parse().unwrap();
+        assert_eq!(tail.lines().nth(2), Some("%%EOF"));
+        let mut lines = std::str::from_utf8(&pdf[xref..start]).unwrap().lines();
+        assert_eq!(lines.next(), Some("xref"));
+        let size: usize = lines.next().unwrap().strip_prefix("0 ").unwrap().parse().unwrap();
+        assert_eq!(lines.next(), Some("0000000000 65535 f "));
+        for id in 1..size {
+            let entry = lines.next().unwrap();
+            assert!(entry.ends_with(" 00000 n "), "object {} is not in use: {}", id, entry);
+            let offset: usize = entry[..10].parse().unwrap();
+            let header = format!("{} 0 obj\n", id);
+            assert!(pdf[offset..].starts_with(header.as_bytes()), "object {} is not at offset {}", id, offset);
+        }
+        assert_eq!(lines.next(), Some("trailer"));
+        assert!(lines.next().unwrap().starts_with(&format!("<< /Size {} /Root 1 0 R /Info 5 0 R", size)));
+        size
+    }
+
+    fn document() -> Document {
+        let mut doc = Document::new(595.0, 842.0, 1.0, "Invoice INV-0001");
+        let page = doc.add_page();
+        doc.pages[page].text(72.0, 72.0, Font::Regular, 12.0, Color(0, 0, 0), "Total 1'234.50 €");
+        doc.pages[page].text(72.0, 96.0, Font::Bold, 12.0, Color(0, 0, 0), "Invoice");
+        doc
+    }
+
+    fn embedded_fonts(doc: &mut Document) -> Option<[usize; 2]> {
+        let Some((regular, bold)) = system_fonts() else {
+            eprintln!("No TrueType font installed, skipping the embedded font checks");
+            return None;
+        };
+        let (regular, bold) = (std::fs::read(regular).unwrap(), std::fs::read(bold).unwrap());
+        let lengths = [regular.len(), bold.len()];
+        doc.embed_fonts(TrueType::parse(regular).unwrap(), TrueType::parse(bold).unwrap());
+        Some(lengths)
+    }
+
+    #[test]
+    fn cross_reference_table_points_at_every_object() {
+        let pdf = document().finish().unwrap();
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        // Catalog, pages, two fonts, info, then the page and its content.
+        assert_eq!(check_xref(&pdf), 8);
+        assert!(contains(&pdf, "/BaseFont /Helvetica /Encoding /WinAnsiEncoding"));
+        assert!(contains(&pdf, "/BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding"));
+    }
+
+    #[test]
+    fn embedded_fonts_carry_their_font_files() {
+        let mut doc = document();
+        let Some(lengths) = embedded_fonts(&mut doc) else { return };
+        let pdf = doc.finish().unwrap();
+        check_xref(&pdf);
+        assert!(!contains(&pdf, "/Subtype /Type1"));
+        assert_eq!(String::from_utf8_lossy(&pdf).matches("/Subtype /TrueType").count(), 2);
+        assert_eq!(String::from_utf8_lossy(&pdf).matches("/FontFile2 ").count(), 2);
+        for length in lengths {
+            assert!(contains(&pdf, &format!("/Length1 {} /Filter /FlateDecode", length)));
+        }
+    }
+
+    #[test]
+    fn archive_is_declared_pdf_a_3b() {
+        let mut doc = document();
+        if embedded_fonts(&mut doc).is_none() {
+            assert!(doc.finish().is_ok());
+            return;
+        }
+        doc.archive(Archive {
+            metadata: String::new(),
+            attachments: vec![Attachment {
+                name: "factur-x.xml".to_string(),
+                mime: "text/xml".to_string(),
+                description: "Factur-X invoice".to_string(),
+                relationship: "Data".to_string(),
+                data: b"<rsm:CrossIndustryInvoice/>".to_vec(),
+            }],
+        });
+        let pdf = doc.finish().unwrap();
+        assert!(pdf.starts_with(b"%PDF-1.7\n"));
+        check_xref(&pdf);
+        assert!(contains(&pdf, "/Type /Metadata /Subtype /XML"));
+        assert!(contains(&pdf, "<pdfaid:part>3</pdfaid:part>"));
+        assert!(contains(&pdf, "<pdfaid:conformance>B</pdfaid:conformance>"));
+        assert!(contains(&pdf, "/OutputIntents [<< /Type /OutputIntent /S /GTS_PDFA1"));
+        assert!(contains(&pdf, "/DestOutputProfile "));
+        assert!(contains(&pdf, "/Type /EmbeddedFile /Subtype /text#2Fxml"));
+        assert!(contains(&pdf, "/AFRelationship /Data"));
+        assert!(contains(&pdf, " /AF ["));
+    }
+
+    #[test]
+    fn archive_needs_embedded_fonts() {
+        let mut doc = document();
+        doc.archive(Archive::default());
+        assert!(doc.finish().is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use std::io::Read;
+
+use anyhow::{Error, Result};
+use flate2::read::ZlibDecoder;
+
+use crate::render::pdf::document::deflate;
+
+/// An image XObject ready to be written into a PDF. JPEGs are embedded as
+/// is; PNGs keep their compressed data unless they have an alpha channel,
+/// which has to be split out into a separate soft mask.
+#[derive(Debug)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    color_space: String,
+    bits: u8,
+    filter: &'static str,
+    decode_parms: Option<String>,
+    pub data: Vec<u8>,
+    /// Deflated 8 bit alpha channel.
+    pub smask: Option<Vec<u8>>,
+}
+
+impl Image {
+    /// Reads a PNG or JPEG, the formats a PDF can hold without re-encoding.
+    pub fn decode(data: &[u8]) -> Result<Image> {
+        match infer::get(data).map(|kind| kind.mime_type()) {
+            Some("image/png") => Self::png(data),
+            Some("image/jpeg") => Self::jpeg(data),
+            Some(mime) => Err(Error::msg(format!("Unsupported image type {}, use a PNG or JPEG", mime))),
+            None => Err(Error::msg("Unrecognized image data, use a PNG or JPEG")),
+        }
+    }
+    pub fn dictionary(&self) -> String {
+        let mut dict = format!(
+            "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace {} /BitsPerComponent {} /Filter /{}",
+            self.width, self.height, self.color_space, self.bits, self.filter
+        );
+        if let Some(parms) = &self.decode_parms {
+            dict.push_str(&format!(" /DecodeParms {}", parms));
+        }
+        dict
+    }
+
+    fn jpeg(data: &[u8]) -> Result<Image> {
+        let mut pos = 2;
+        while pos + 4 <= data.len() {
+            if data[pos] != 0xFF {
+                break;
+            }
+            let marker = data[pos + 1];
+            let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+            // Start of frame markers, excluding DHT (C4), JPG (C8) and DAC (CC).
+            if matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+                let frame = data
+                    .get(pos + 4..pos + 10)
+                    .ok_or_else(|| Error::msg("Truncated JPEG header"))?;
+                let color_space = match frame[5] {
+                    1 => "/DeviceGray",
+                    3 => "/DeviceRGB",
+                    4 => "/DeviceCMYK",
+                    components => return Err(Error::msg(format!("Unsupported JPEG with {} components", components))),
+                };
+                return Ok(Image {
+                    width: u16::from_be_bytes([frame[3], frame[4]]) as u32,
+                    height: u16::from_be_bytes([frame[1], frame[2]]) as u32,
+                    color_space: color_space.to_string(),
+                    bits: frame[0],
+                    filter: "DCTDecode",
+                    decode_parms: None,
+                    data: data.to_vec(),
+                    smask: None,
+                });
+            }
+            pos += 2 + length;
+        }
+        Err(Error::msg("JPEG has no frame header"))
+    }
+
+    fn png(data: &[u8]) -> Result<Image> {
+        let mut header = None;
+        let mut palette = Vec::new();
+        let mut compressed = Vec::new();
+        let mut pos = 8;
+        while pos + 8 <= data.len() {
+            let length = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+            let kind = &data[pos + 4..pos + 8];
+            let body = data
+                .get(pos + 8..pos + 8 + length)
+                .ok_or_else(|| Error::msg("Truncated PNG chunk"))?;
+            match kind {
+                b"IHDR" => header = Some(body.to_vec()),
+                b"PLTE" => palette = body.to_vec(),
+                b"IDAT" => compressed.extend_from_slice(body),
+                b"IEND" => break,
+                _ => {}
+            }
+            pos += 12 + length;
+        }
+        let header = header.filter(|header| header.len() >= 13).ok_or_else(|| Error::msg("PNG has no header"))?;
+        let width = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        let height = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+        let (bits, color_type, interlace) = (header[8], header[9], header[12]);
+        if interlace != 0 {
+            return Err(Error::msg("Interlaced PNGs are not supported"));
+        }
+
+        let (color_space, colors) = match color_type {
+            0 => ("/DeviceGray".to_string(), 1),
+            2 => ("/DeviceRGB".to_string(), 3),
+            3 => {
+                let hex: String = palette.iter().map(|byte| format!("{:02X}", byte)).collect();
+                (format!("[/Indexed /DeviceRGB {} <{}>]", (palette.len() / 3).max(1) - 1, hex), 1)
+            }
+            4 | 6 => return Self::png_alpha(width, height, bits, color_type, &compressed),
+            _ => return Err(Error::msg(format!("Unsupported PNG color type {}", color_type))),
+        };
+        // PDF understands PNG's row filters, so the data can be copied across.
+        Ok(Image {
+            width,
+            height,
+            color_space,
+            bits,
+            filter: "FlateDecode",
+            decode_parms: Some(format!(
+                "<< /Predictor 15 /Colors {} /BitsPerComponent {} /Columns {} >>",
+                colors, bits, width
+            )),
+            data: compressed,
+            smask: None,
+        })
+    }
+
+    fn png_alpha(width: u32, height: u32, bits: u8, color_type: u8, compressed: &[u8]) -> Result<Image> {
+        if bits != 8 {
+            return Err(Error::msg("Only 8 bit PNGs with transparency are supported"));
+        }
+        let channels = if color_type == 6 { 4 } else { 2 };
+        let mut raw = Vec::new();
+        ZlibDecoder::new(compressed).read_to_end(&mut raw)?;
+        let pixels = unfilter(&raw, width as usize * channels, height as usize, channels)?;
+        let mut color = Vec::with_capacity(pixels.len());
+        let mut alpha = Vec::with_capacity(pixels.len() / channels);
+        for pixel in pixels.chunks(channels) {
+            color.extend_from_slice(&pixel[..channels - 1]);
+            alpha.push(pixel[channels - 1]);
+        }
+        Ok(Image {
+            width,
+            height,
+            color_space: if channels == 4 { "/DeviceRGB" } else { "/DeviceGray" }.to_string(),
+            bits,
+            filter: "FlateDecode",
+            decode_parms: None,
+            data: deflate(&color)?,
+            smask: Some(deflate(&alpha)?),
+        })
+    }
+}
+
+/// Reverses the per-row PNG filters of 8 bit image data.
+fn unfilter(raw: &[u8], stride: usize, height: usize, bpp: usize) -> Result<Vec<u8>> {
+    if raw.len() < (stride + 1) * height {
+        return Err(Error::msg("Truncated PNG image data"));
+    }
+    let mut pixels = vec![0u8; stride * height];
+    for row in 0..height {
+        let filter = raw[row * (stride + 1)];
+        let line = &raw[row * (stride + 1) + 1..(row + 1) * (stride + 1)];
+        let (done, current) = pixels.split_at_mut(row * stride);
+        let previous = if row == 0 { None } else { Some(&done[(row - 1) * stride..]) };
+        let current = &mut current[..stride];
+        for i in 0..stride {
+            let left = if i >= bpp { current[i - bpp] } else { 0 };
+            let up = previous.map(|previous| previous[i]).unwrap_or(0);
+            let up_left = match previous {
+                Some(previous) if i >= bpp => previous[i - bpp],
+                _ => 0,
+            };
+            let predicted = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(Error::msg(format!("Invalid PNG filter {}", filter))),
+            };
+            current[i] = line[i].wrapping_add(predicted);
+        }
+    }
+    Ok(pixels)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
@@ -0,0 +1,708 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use rust_decimal::Decimal;
+
+use crate::models::client::Client;
+use crate::models::company::Company;
+use crate::models::contact::Contact;
+use crate::models::credit_note::CreditNote;
+use crate::models::currency::Currency;
+use crate::models::invoice::{line_details, Invoice, InvoiceStage, ItemDetail, PaidStatus};
+use crate::models::methods::Methods;
+use crate::models::page::{expand_template, Margins, PageFields, PageSetup};
+use crate::render::payment_qr;
+use crate::render::qrbill::QrBill;
+
+use document::{encodable, Color, Document, Font};
+use image::Image;
+use truetype::TrueType;
+
+pub use archive::{escape_xml, Archive, Attachment};
+
+mod archive;
+mod document;
+mod image;
+mod truetype;
+
+// The native layout draws the same blocks as default.html: company and
+// document details, the bill-to address, the line table, totals, payment
+// methods and notes. Paper size, margins and scale come from the page
+// setup, with 50pt margins unless it sets others. A QR-bill payment part
+// goes on a page of its own at its printed size, whatever the scale.
+const DEFAULT_MARGIN: f32 = 50.0;
+
+const HEADER_FILL: Color = Color(38, 43, 39);
+const ODD_ROW_FILL: Color = Color(242, 242, 242);
+const EVEN_ROW_FILL: Color = Color(221, 221, 221);
+const BALANCE_FILL: Color = Color(169, 169, 169);
+
+/// What turns the native output into a PDF/A-3b: TrueType files for the
+/// regular and bold faces, and the archive's metadata and attachments.
+pub struct Archival {
+    pub regular: Vec<u8>,
+    pub bold: Vec<u8>,
+    pub archive: Archive,
+}
+
+/// Writes `invoice` as a PDF next to `output`, with a `.pdf` extension.
+pub fn write_invoice(invoice: &Invoice, output: &Path, page: &PageSetup, archival: Option<Archival>) -> Result<PathBuf> {
+    write(&Sheet::invoice(invoice), &PageFields::invoice(invoice), page, output, archival)
+}
+
+/// Writes `credit` as a PDF next to `output`, with a `.pdf` extension.
+pub fn write_credit_note(credit: &CreditNote, output: &Path, page: &PageSetup) -> Result<PathBuf> {
+    write(&Sheet::credit_note(credit), &PageFields::credit_note(credit), page, output, None)
+}
+
+fn write(sheet: &Sheet, fields: &PageFields, page: &PageSetup, output: &Path, archival: Option<Archival>) -> Result<PathBuf> {
+    let mut output_file = output.to_path_buf();
+    output_file.set_extension("pdf");
+    let mut layout = Layout::new(&format!("{} {}", sheet.title, sheet.number), page);
+    if let Some(archival) = archival {
+        layout.doc.embed_fonts(TrueType::parse(archival.regular)?, TrueType::parse(archival.bold)?);
+        layout.doc.archive(archival.archive);
+    }
+    sheet.draw(&mut layout);
+    layout.decorate(page, fields);
+    std::fs::write(&output_file, layout.doc.finish()?)?;
+    Ok(output_file)
+}
+
+/// `amount` in `currency`, written with the code when the standard fonts
+/// cannot draw its symbol.
+fn money(currency: &Currency, amount: Decimal) -> String {
+    if encodable(currency.symbol()) {
+        currency.format(amount)
+    } else {
+        currency.format_with(amount, currency.code())
+    }
+}
+
+fn long_date(date: &str) -> String {
+    NaiveDate::parse_from_str(date, "%Y%m%d")
+        .map(|date| date.format("%B %d, %Y").to_string())
+        .unwrap_or_else(|_| date.to_string())
+}
+
+fn contact_lines(contact: &Contact) -> Vec<String> {
+    let mut lines: Vec<String> = [&contact.phone, &contact.email, &contact.addr1, &contact.addr2]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
+    if let Some(city) = &contact.city {
+        let mut line = format!("{},", city);
+        for part in [&contact.state, &contact.zip].into_iter().flatten() {
+            line.push(' ');
+            line.push_str(part);
+        }
+        lines.push(line);
+    }
+    lines.extend(contact.country.clone());
+    lines
+}
+
+/// Flattens Markdown notes into paragraphs, with list items bulleted and
+/// headings in bold.
+fn markdown_paragraphs(markdown: &str) -> Vec<(Font, String)> {
+    let mut paragraphs = Vec::new();
+    let mut current = String::new();
+    let mut font = Font::Regular;
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(Tag::Heading { .. }) => font = Font::Bold,
+            Event::Start(Tag::Item) => current.push_str("• "),
+            Event::Text(text) | Event::Code(text) => current.push_str(&text),
+            Event::SoftBreak => current.push(' '),
+            Event::HardBreak => current.push('\n'),
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item | TagEnd::CodeBlock) => {
+                if !current.trim().is_empty() {
+                    paragraphs.push((font, std::mem::take(&mut current)));
+                }
+                current.clear();
+                font = Font::Regular;
+            }
+            _ => {}
+        }
+    }
+    if !current.trim().is_empty() {
+        paragraphs.push((font, current));
+    }
+    paragraphs
+}
+
+/// Everything the native layout draws, gathered from an invoice or a
+/// credit note.
+struct Sheet<'a> {
+    title: &'static str,
+    number: String,
+    company: &'a Company,
+    client: &'a Client,
+    currency: Currency,
+    details: Vec<(String, String)>,
+    /// Shaded row under the details, e.g. the balance due.
+    highlight: Option<(String, String)>,
+    /// Payment status and its date or check number.
+    status: Option<(String, Vec<String>)>,
+    items: Vec<ItemDetail>,
+    totals: Vec<(String, String, bool)>,
+    /// Shaded final row of the totals.
+    balance: Option<(String, String)>,
+    methods: &'a [Methods],
+    /// Generated payment QR codes, one per method.
+    payment_codes: Vec<Option<Vec<u8>>>,
+    notes: Option<(&'static str, String)>,
+    qr_bill: Option<QrBill>,
+}
+
+impl<'a> Sheet<'a> {
+    fn invoice(invoice: &'a Invoice) -> Self {
+        let stage = match invoice.attributes.stage {
+            InvoiceStage::Quote => "Quote",
+            InvoiceStage::Invoice => "Invoice",
+        };
+        let mut details = vec![
+            ("Date:".to_string(), long_date(&invoice.date)),
+            ("Payment Terms:".to_string(), invoice.template.terms.name.clone()),
+            ("Due Date:".to_string(), invoice.due_date().format("%B %d, %Y").to_string()),
+        ];
+        if let (InvoiceStage::Quote, Some(expires)) = (&invoice.attributes.stage, invoice.expiry_date()) {
+            details.push(("Valid Until:".to_string(), expires.format("%B %d, %Y").to_string()));
+        }
+        let status = match &invoice.attributes.status {
+            PaidStatus::Waiting => None,
+            PaidStatus::PastDue { date } => Some(("Past Due".to_string(), vec![long_date(date)])),
+            PaidStatus::Paid { date, check } => {
+                let mut lines = vec![long_date(date)];
+                if let Some(check) = check {
+                    lines.push(format!("Check {}", check));
+                }
+                Some(("Paid".to_string(), lines))
+            }
+            PaidStatus::Failed { date } => Some(("Failed".to_string(), vec![long_date(date)])),
+            PaidStatus::Refunded { date } => Some(("Refunded".to_string(), vec![long_date(date)])),
+        };
+
+        let currency = &invoice.currency;
+        let taxes = invoice.calculate_taxes();
+        let mut totals = Vec::new();
+        if !taxes.is_empty() || invoice.discount.is_some() {
+            totals.push(("Subtotal:".to_string(), money(currency, invoice.calculate_subtotal()), false));
+        }
+        if let Some(discount) = &invoice.discount {
+            totals.push((
+                format!("Discount ({}):", discount.label(currency)),
+                money(currency, -invoice.calculate_discount()),
+                false,
+            ));
+        }
+        for tax in &taxes {
+            totals.push((format!("{} ({}%):", tax.name, tax.rate), money(currency, tax.amount), false));
+        }
+        totals.push(("Total:".to_string(), money(currency, invoice.calculate_total()), true));
+        for payment in &invoice.payments {
+            totals.push((format!("Paid {}:", long_date(&payment.date)), money(currency, -payment.amount), false));
+        }
+        for credit in &invoice.credits {
+            totals.push((
+                format!("Credit note {} {}:", credit.reference(), long_date(&credit.date)),
+                money(currency, credit.calculate_total()),
+                false,
+            ));
+        }
+        let balance = (!invoice.payments.is_empty() || !invoice.credits.is_empty())
+            .then(|| ("Balance:".to_string(), money(currency, invoice.balance())));
+
+        let methods: &[Methods] = if invoice.attributes.show_methods { &invoice.template.methods } else { &[] };
+        Sheet {
+            title: stage,
+            number: invoice.reference(),
+            company: &invoice.template.company,
+            client: &invoice.template.client,
+            currency: invoice.currency,
+            details,
+            highlight: Some(("Balance Due:".to_string(), money(currency, invoice.balance()))),
+            status,
+            items: line_details(&invoice.items, &invoice.currency),
+            totals,
+            balance,
+            methods,
+            // Problems with a code were already reported when rendering the HTML.
+            payment_codes: methods.iter().map(|method| payment_qr(invoice, method).ok().flatten()).collect(),
+            notes: invoice
+                .notes
+                .clone()
+                .filter(|_| invoice.attributes.show_notes)
+                .map(|notes| ("Notes:", notes)),
+            qr_bill: QrBill::new(invoice).ok().flatten(),
+        }
+    }
+
+    fn credit_note(credit: &'a CreditNote) -> Self {
+        let currency = &credit.currency;
+        let mut totals = vec![("Subtotal:".to_string(), money(currency, credit.calculate_subtotal()), false)];
+        for tax in credit.calculate_taxes() {
+            totals.push((format!("{} ({}%):", tax.name, tax.rate), money(currency, tax.amount), false));
+        }
+        totals.push(("Total:".to_string(), money(currency, credit.calculate_total()), true));
+        Sheet {
+            title: "Credit Note",
+            number: credit.reference(),
+            company: &credit.template.company,
+            client: &credit.template.client,
+            currency: credit.currency,
+            details: vec![
+                ("Date:".to_string(), long_date(&credit.date)),
+                ("Invoice:".to_string(), credit.invoice_reference()),
+                ("Invoice Date:".to_string(), long_date(&credit.invoice_date)),
+            ],
+            highlight: Some(("Amount Credited:".to_string(), money(currency, -credit.calculate_total()))),
+            status: None,
+            items: line_details(&credit.items, &credit.currency),
+            totals,
+            balance: None,
+            methods: &[],
+            payment_codes: Vec::new(),
+            notes: credit.reason.clone().map(|reason| ("Reason:", reason)),
+            qr_bill: None,
+        }
+    }
+
+    fn draw(&self, layout: &mut Layout) {
+        let left = self.draw_company(layout);
+        let right = self.draw_details(layout);
+        layout.y = left.max(right) + 24.0;
+        self.draw_client(layout);
+        self.draw_items(layout);
+        self.draw_totals(layout);
+        self.draw_methods(layout);
+        self.draw_notes(layout);
+        self.draw_qr_bill(layout);
+    }
+
+    /// Logo, company name and contact in the top left, returning the y
+    /// below them.
+    fn draw_company(&self, layout: &mut Layout) -> f32 {
+        let mut y = layout.margins.top;
+        if let Some(logo) = &self.company.logo {
+            match Image::decode(logo) {
+                Ok(image) => {
+                    let (width, height) = fit(image.width, image.height, 180.0, 70.0);
+                    let id = layout.doc.add_image(image);
+                    layout.image(id, layout.margins.left, y, width, height);
+                    y += height + 12.0;
+                }
+                Err(e) => eprintln!("Leaving the logo out of the PDF: {}", e),
+            }
+        }
+        layout.put(layout.margins.left, y, Font::Bold, 14.0, Color::BLACK, &self.company.name);
+        y += 18.0;
+        let mut lines = contact_lines(&self.company.contact);
+        lines.extend(self.company.tax_id.as_ref().map(|tax_id| format!("Tax ID: {}", tax_id)));
+        for line in lines {
+            layout.put(layout.margins.left, y, Font::Regular, 9.0, Color::BLACK, &line);
+            y += 12.0;
+        }
+        y
+    }
+
+    /// Title, number, dates and payment status in the top right, returning
+    /// the y below them.
+    fn draw_details(&self, layout: &mut Layout) -> f32 {
+        let right = layout.right();
+        let label_x = right - 210.0;
+        let mut y = layout.margins.top;
+        layout.put_right(right, y, Font::Bold, 26.0, Color::BLACK, self.title);
+        y += 32.0;
+        layout.put_right(right, y, Font::Regular, 11.0, Color::BLACK, &self.number);
+        y += 22.0;
+        for (label, value) in &self.details {
+            layout.put(label_x, y, Font::Regular, 9.0, Color::BLACK, label);
+            layout.put_right(right, y, Font::Regular, 9.0, Color::BLACK, value);
+            y += 14.0;
+        }
+        if let Some((label, value)) = &self.highlight {
+            layout.fill(label_x - 6.0, y - 4.0, right - label_x + 12.0, 18.0, BALANCE_FILL);
+            layout.put(label_x, y, Font::Bold, 9.0, Color::BLACK, label);
+            layout.put_right(right, y, Font::Bold, 9.0, Color::BLACK, value);
+            y += 22.0;
+        }
+        if let Some((status, lines)) = &self.status {
+            y += 6.0;
+            layout.put_right(right, y, Font::Bold, 18.0, Color::BLACK, status);
+            y += 22.0;
+            for line in lines {
+                layout.put_right(right, y, Font::Regular, 9.0, Color::BLACK, line);
+                y += 12.0;
+            }
+        }
+        y
+    }
+
+    fn draw_client(&self, layout: &mut Layout) {
+        let mut lines = contact_lines(&self.client.contact);
+        lines.extend(self.client.tax_id.as_ref().map(|tax_id| format!("Tax ID: {}", tax_id)));
+        layout.ensure(32.0 + lines.len() as f32 * 12.0);
+        let mut y = layout.y;
+        layout.put(layout.margins.left, y, Font::Bold, 9.0, Color::GREY, "Bill To:");
+        y += 14.0;
+        layout.put(layout.margins.left, y, Font::Bold, 12.0, Color::BLACK, &self.client.name);
+        y += 16.0;
+        for line in lines {
+            layout.put(layout.margins.left, y, Font::Regular, 9.0, Color::BLACK, &line);
+            y += 12.0;
+        }
+        layout.y = y + 20.0;
+    }
+
+    fn draw_items(&self, layout: &mut Layout) {
+        let columns = Columns::new(layout);
+        layout.ensure(60.0);
+        columns.header(layout);
+        for (index, item) in self.items.iter().enumerate() {
+            let mut lines = layout.doc.wrap(Font::Regular, &item.name, 10.0, columns.item_width)
+                .into_iter()
+                .map(|line| (Font::Regular, 10.0, Color::BLACK, line))
+                .collect::<Vec<_>>();
+            if let Some(description) = &item.description {
+                for line in layout.doc.wrap(Font::Regular, description, 8.0, columns.item_width) {
+                    lines.push((Font::Regular, 8.0, Color::GREY, line));
+                }
+            }
+            if let Some(discount) = &item.discount {
+                let text = format!("Discount {}: {}", discount, money(&self.currency, -item.discount_amount));
+                lines.push((Font::Regular, 8.0, Color::GREY, text));
+            }
+            let height = lines.iter().map(|(_, size, _, _)| size + 3.0).sum::<f32>() + 12.0;
+            if layout.ensure(height) {
+                columns.header(layout);
+            }
+            let fill = if index % 2 == 0 { ODD_ROW_FILL } else { EVEN_ROW_FILL };
+            layout.fill(columns.left, layout.y, columns.width, height, fill);
+            let top = layout.y + 6.0;
+            let quantity = match &item.unit {
+                Some(unit) => format!("{} {}", item.quantity, unit),
+                None => item.quantity.to_string(),
+            };
+            layout.put_right(columns.quantity, top, Font::Regular, 10.0, Color::BLACK, &quantity);
+            layout.put_right(columns.rate, top, Font::Regular, 10.0, Color::BLACK, &money(&self.currency, item.rate));
+            layout.put_right(columns.subtotal, top, Font::Regular, 10.0, Color::BLACK, &money(&self.currency, item.subtotal));
+            let mut y = top;
+            for (font, size, color, line) in &lines {
+                layout.put(columns.item, y, *font, *size, *color, line);
+                y += size + 3.0;
+            }
+            layout.y += height;
+        }
+        layout.y += 8.0;
+    }
+
+    fn draw_totals(&self, layout: &mut Layout) {
+        let columns = Columns::new(layout);
+        for (label, value, bold) in &self.totals {
+            layout.ensure(16.0);
+            let font = if *bold { Font::Bold } else { Font::Regular };
+            layout.put_right(columns.rate, layout.y + 2.0, font, 10.0, Color::BLACK, label);
+            layout.put_right(columns.subtotal, layout.y + 2.0, font, 10.0, Color::BLACK, value);
+            layout.y += 16.0;
+        }
+        if let Some((label, value)) = &self.balance {
+            layout.ensure(22.0);
+            let x = columns.rate - layout.doc.width(Font::Bold, label, 10.0) - 12.0;
+            layout.fill(x, layout.y, columns.left + columns.width - x, 20.0, BALANCE_FILL);
+            layout.put_right(columns.rate, layout.y + 5.0, Font::Bold, 10.0, Color::BLACK, label);
+            layout.put_right(columns.subtotal, layout.y + 5.0, Font::Bold, 10.0, Color::BLACK, value);
+            layout.y += 20.0;
+        }
+        layout.y += 24.0;
+    }
+
+    fn draw_methods(&self, layout: &mut Layout) {
+        if self.methods.is_empty() {
+            return;
+        }
+        layout.ensure(40.0);
+        layout.put(layout.margins.left, layout.y, Font::Bold, 12.0, Color::BLACK, "Payment Methods:");
+        layout.y += 20.0;
+        for (method, code) in self.methods.iter().zip(&self.payment_codes) {
+            let image = code.as_ref().or(method.qr.as_ref()).and_then(|qr| match Image::decode(qr) {
+                Ok(image) => Some(image),
+                Err(e) => {
+                    eprintln!("Leaving the QR code for {} out of the PDF: {}", method.name, e);
+                    None
+                }
+            });
+            let (qr_width, qr_height) = image
+                .as_ref()
+                .map(|image| fit(image.width, image.height, 100.0, 100.0))
+                .unwrap_or((0.0, 0.0));
+            let detail_lines: Vec<String> = method
+                .details()
+                .iter()
+                .flat_map(|detail| layout.doc.wrap(Font::Regular, detail, 9.0, 300.0))
+                .collect();
+            let text_height = 14.0 + detail_lines.len() as f32 * 12.0;
+            layout.ensure(text_height.max(qr_height) + 10.0);
+            let top = layout.y;
+            layout.put(layout.margins.left, top, Font::Bold, 10.0, Color::BLACK, &method.name);
+            for (index, line) in detail_lines.iter().enumerate() {
+                layout.put(layout.margins.left, top + 14.0 + index as f32 * 12.0, Font::Regular, 9.0, Color::GREY, line);
+            }
+            if let Some(image) = image {
+                let id = layout.doc.add_image(image);
+                layout.image(id, layout.margins.left + 320.0, top, qr_width, qr_height);
+            }
+            layout.y = top + text_height.max(qr_height) + 10.0;
+        }
+        layout.y += 14.0;
+    }
+
+    fn draw_notes(&self, layout: &mut Layout) {
+        let Some((heading, notes)) = &self.notes else {
+            return;
+        };
+        let width = layout.content_width();
+        layout.ensure(40.0);
+        layout.put(layout.margins.left, layout.y, Font::Bold, 12.0, Color::BLACK, heading);
+        layout.y += 20.0;
+        for (font, paragraph) in markdown_paragraphs(notes) {
+            for line in layout.doc.wrap(font, &paragraph, 10.0, width) {
+                layout.ensure(13.0);
+                layout.put(layout.margins.left, layout.y, font, 10.0, Color::BLACK, &line);
+                layout.y += 13.0;
+            }
+            layout.y += 6.0;
+        }
+    }
+}
+
+impl Sheet<'_> {
+    /// The QR-bill payment part at the bottom of a page of its own: the
+    /// receipt on the left, the payment part with the QR code on the right,
+    /// with the positions and type sizes the standard sets.
+    fn draw_qr_bill(&self, layout: &mut Layout) {
+        let Some(bill) = &self.qr_bill else {
+            return;
+        };
+        let image = match bill.png().and_then(|png| Image::decode(&png)) {
+            Ok(image) => image,
+            Err(e) => {
+                eprintln!("Leaving the QR-bill payment part out of the PDF: {}", e);
+                return;
+            }
+        };
+        let scale = layout.doc.scale;
+        let pt = |points: f32| points / scale;
+        let mm = |millimetres: f32| pt(millimetres * 72.0 / 25.4);
+        layout.page = layout.doc.add_page();
+        layout.plain_pages.push(layout.page);
+        let top = layout.height - mm(105.0);
+        let payment = mm(62.0);
+        layout.line(&[(0.0, top), (mm(210.0), top)], pt(0.5), Some(pt(3.0)));
+        layout.line(&[(payment, top), (payment, layout.height)], pt(0.5), Some(pt(3.0)));
+
+        // The receipt sets headings in 6pt and text in 8pt, the payment part
+        // in 8pt and 10pt, both with 3pt between lines.
+        let info = |layout: &mut Layout, x: f32, y: f32, width: f32, payment_part: bool| {
+            let (heading, text) = if payment_part { (8.0, 10.0) } else { (6.0, 8.0) };
+            let line_height = pt(heading + 3.0);
+            let mut sections = bill.blocks();
+            if payment_part {
+                sections.push(("Additional information", vec![bill.message.clone()]));
+            }
+            match &bill.debtor {
+                Some(debtor) => sections.push(("Payable by", debtor.lines())),
+                None => sections.push(("Payable by (name/address)", Vec::new())),
+            }
+            let mut y = y;
+            for (title, lines) in sections {
+                layout.put(x, y, Font::Bold, pt(heading), Color::BLACK, title);
+                y += line_height;
+                let wrapped: Vec<String> =
+                    lines.iter().flat_map(|line| layout.doc.wrap(Font::Regular, line, pt(text), width)).collect();
+                for line in wrapped {
+                    layout.put(x, y, Font::Regular, pt(text), Color::BLACK, &line);
+                    y += line_height;
+                }
+                y += line_height;
+            }
+            if bill.debtor.is_none() {
+                let (box_width, box_height) = if payment_part { (mm(65.0), mm(25.0)) } else { (mm(52.0), mm(20.0)) };
+                layout.corners(x, y - line_height, box_width, box_height);
+            }
+        };
+        let amount = |layout: &mut Layout, x: f32, offset: f32, heading: f32, text: f32| {
+            let y = top + mm(68.0);
+            layout.put(x, y, Font::Bold, pt(heading), Color::BLACK, "Currency");
+            layout.put(x, y + pt(heading + 3.0), Font::Regular, pt(text), Color::BLACK, bill.currency);
+            layout.put(x + offset, y, Font::Bold, pt(heading), Color::BLACK, "Amount");
+            layout.put(x + offset, y + pt(heading + 3.0), Font::Regular, pt(text), Color::BLACK, &bill.formatted_amount());
+        };
+
+        layout.put(mm(5.0), top + mm(5.0), Font::Bold, pt(11.0), Color::BLACK, "Receipt");
+        info(layout, mm(5.0), top + mm(12.0), mm(52.0), false);
+        amount(layout, mm(5.0), mm(13.0), 6.0, 8.0);
+        layout.put_right(mm(57.0), top + mm(82.0), Font::Bold, pt(6.0), Color::BLACK, "Acceptance point");
+
+        layout.put(payment + mm(5.0), top + mm(5.0), Font::Bold, pt(11.0), Color::BLACK, "Payment part");
+        let id = layout.doc.add_image(image);
+        layout.image(id, payment + mm(5.0), top + mm(17.0), mm(46.0), mm(46.0));
+        amount(layout, payment + mm(5.0), mm(17.0), 8.0, 10.0);
+        info(layout, payment + mm(56.0), top + mm(5.0), mm(87.0), true);
+    }
+}
+
+/// Scales an image of `width` x `height` pixels to fit in a box of
+/// `max_width` x `max_height` points, keeping its aspect ratio.
+fn fit(width: u32, height: u32, max_width: f32, max_height: f32) -> (f32, f32) {
+    let (width, height) = (width.max(1) as f32, height.max(1) as f32);
+    let scale = (max_width / width).min(max_height / height);
+    (width * scale, height * scale)
+}
+
+/// Horizontal positions of the line table: the left edge of the item
+/// column and the right edges of the numeric columns.
+struct Columns {
+    left: f32,
+    width: f32,
+    item: f32,
+    item_width: f32,
+    quantity: f32,
+    rate: f32,
+    subtotal: f32,
+}
+
+impl Columns {
+    fn new(layout: &Layout) -> Self {
+        let left = layout.margins.left;
+        let width = layout.content_width();
+        Columns {
+            left,
+            width,
+            item: left + 12.0,
+            item_width: width * 0.5 - 24.0,
+            quantity: left + width * 0.66,
+            rate: left + width * 0.83,
+            subtotal: left + width - 12.0,
+        }
+    }
+    fn header(&self, layout: &mut Layout) {
+        layout.fill(self.left, layout.y, self.width, 22.0, HEADER_FILL);
+        let y = layout.y + 6.0;
+        layout.put(self.item, y, Font::Bold, 10.0, Color::WHITE, "Item");
+        layout.put_right(self.quantity, y, Font::Bold, 10.0, Color::WHITE, "Quantity");
+        layout.put_right(self.rate, y, Font::Bold, 10.0, Color::WHITE, "Rate");
+        layout.put_right(self.subtotal, y, Font::Bold, 10.0, Color::WHITE, "Subtotal");
+        layout.y += 22.0;
+    }
+}
+
+/// A document being filled from the top down. `y` is the distance of the
+/// cursor from the top of the current page. Sizes are in points divided by
+/// the page scale, so a scale below 1 fits more on a page.
+struct Layout {
+    doc: Document,
+    width: f32,
+    height: f32,
+    margins: Margins,
+    page: usize,
+    /// Pages left without the header and footer.
+    plain_pages: Vec<usize>,
+    y: f32,
+}
+
+impl Layout {
+    fn new(title: &str, setup: &PageSetup) -> Self {
+        let (width, height) = setup.page_size();
+        let scale = setup.scale();
+        let margins = setup.margins.unwrap_or(Margins::uniform(DEFAULT_MARGIN));
+        let mut doc = Document::new(width, height, scale, title);
+        let page = doc.add_page();
+        Layout {
+            doc,
+            width: width / scale,
+            height: height / scale,
+            margins: Margins {
+                top: margins.top / scale,
+                right: margins.right / scale,
+                bottom: margins.bottom / scale,
+                left: margins.left / scale,
+            },
+            page,
+            plain_pages: Vec::new(),
+            y: margins.top / scale,
+        }
+    }
+    fn right(&self) -> f32 {
+        self.width - self.margins.right
+    }
+    fn content_width(&self) -> f32 {
+        self.right() - self.margins.left
+    }
+    /// Starts a new page unless `height` still fits on this one, returning
+    /// whether it did.
+    fn ensure(&mut self, height: f32) -> bool {
+        if self.y + height <= self.height - self.margins.bottom {
+            return false;
+        }
+        self.page = self.doc.add_page();
+        self.y = self.margins.top;
+        true
+    }
+    /// Draws the header and footer centered in the top and bottom margins
+    /// of every page, once the page count is known.
+    fn decorate(&mut self, setup: &PageSetup, fields: &PageFields) {
+        let pages = self.doc.pages.len();
+        for page in 0..pages {
+            if self.plain_pages.contains(&page) {
+                continue;
+            }
+            self.page = page;
+            let center = (self.margins.left + self.right()) / 2.0;
+            let number = (page + 1).to_string();
+            if let Some(header) = &setup.header {
+                let text = expand_template(&header.0, fields, &number, &pages.to_string(), str::to_string);
+                let width = self.doc.width(Font::Regular, &text, 9.0);
+                self.put(center - width / 2.0, (self.margins.top - 9.0) / 2.0, Font::Regular, 9.0, Color::GREY, &text);
+            }
+            if let Some(footer) = &setup.footer {
+                let text = expand_template(&footer.0, fields, &number, &pages.to_string(), str::to_string);
+                let width = self.doc.width(Font::Regular, &text, 9.0);
+                let y = self.height - (self.margins.bottom + 9.0) / 2.0;
+                self.put(center - width / 2.0, y, Font::Regular, 9.0, Color::GREY, &text);
+            }
+        }
+    }
+    /// Text with its top at `y`.
+    fn put(&mut self, x: f32, y: f32, font: Font, size: f32, color: Color, text: &str) {
+        self.doc.pages[self.page].text(x, self.height - y - size * 0.8, font, size, color, text);
+    }
+    /// Text ending at `right`, with its top at `y`.
+    fn put_right(&mut self, right: f32, y: f32, font: Font, size: f32, color: Color, text: &str) {
+        self.put(right - self.doc.width(font, text, size), y, font, size, color, text);
+    }
+    fn fill(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        self.doc.pages[self.page].rect(x, self.height - y - height, width, height, color);
+    }
+    fn image(&mut self, image: usize, x: f32, y: f32, width: f32, height: f32) {
+        self.doc.pages[self.page].image(image, x, self.height - y - height, width, height);
+    }
+    fn line(&mut self, points: &[(f32, f32)], width: f32, dash: Option<f32>) {
+        let points: Vec<(f32, f32)> = points.iter().map(|&(x, y)| (x, self.height - y)).collect();
+        self.doc.pages[self.page].line(&points, width, dash);
+    }
+    /// Corner marks around a box for the payer to write in, with its top
+    /// left corner at `x`, `y`.
+    fn corners(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        let mark = 3.0 * 72.0 / 25.4 / self.doc.scale;
+        let thickness = 0.75 / self.doc.scale;
+        let (right, bottom) = (x + width, y + height);
+        self.line(&[(x, y + mark), (x, y), (x + mark, y)], thickness, None);
+        self.line(&[(right - mark, y), (right, y), (right, y + mark)], thickness, None);
+        self.line(&[(right, bottom - mark), (right, bottom), (right - mark, bottom)], thickness, None);
+        self.line(&[(x + mark, bottom), (x, bottom), (x, bottom - mark)], thickness, None);
+    }
+}
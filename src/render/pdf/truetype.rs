@@ -0,0 +1,199 @@
+use anyhow::{anyhow, bail, Result};
+
+use crate::render::pdf::document::decode;
+
+/// A TrueType font file embedded whole, with the metrics its PDF font
+/// dictionary and descriptor need. Text is set in WinAnsiEncoding through
+/// the font's Windows Unicode `cmap`, so only that subtable is read.
+#[derive(Debug)]
+pub struct TrueType {
+    pub data: Vec<u8>,
+    pub name: String,
+    /// Advance widths of WinAnsi codes 0 to 255, in 1/1000 em.
+    pub widths: [u16; 256],
+    pub bbox: [i32; 4],
+    pub ascent: i32,
+    pub descent: i32,
+    pub cap_height: i32,
+    pub italic_angle: f32,
+    pub stem_v: i32,
+    pub flags: u32,
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| anyhow!("Truncated font file"))
+}
+
+fn i16_at(data: &[u8], offset: usize) -> Result<i16> {
+    u16_at(data, offset).map(|value| value as i16)
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| anyhow!("Truncated font file"))
+}
+
+impl TrueType {
+    pub fn parse(data: Vec<u8>) -> Result<Self> {
+        match u32_at(&data, 0)? {
+            0x0001_0000 | 0x7472_7565 => {}
+            0x4F54_544F => bail!("Only TrueType outlines can be embedded, not OpenType CFF fonts"),
+            _ => bail!("Not a TrueType font file"),
+        }
+        let table = |tag: &[u8; 4]| -> Result<usize> {
+            let count = u16_at(&data, 4)? as usize;
+            for index in 0..count {
+                let record = 12 + index * 16;
+                if data.get(record..record + 4) == Some(&tag[..]) {
+                    return Ok(u32_at(&data, record + 8)? as usize);
+                }
+            }
+            Err(anyhow!("Font has no {} table", String::from_utf8_lossy(tag)))
+        };
+        let head = table(b"head")?;
+        let hhea = table(b"hhea")?;
+        let hmtx = table(b"hmtx")?;
+        let cmap = table(b"cmap")?;
+        let post = table(b"post")?;
+        let os2 = table(b"OS/2").ok();
+
+        if let Some(os2) = os2 {
+            // fsType bit 1 marks fonts licensed for installation only.
+            if u16_at(&data, os2 + 8)? & 0x000F == 0x0002 {
+                bail!("The font's license does not allow embedding it");
+            }
+        }
+
+        let units = u16_at(&data, head + 18)? as i32;
+        if units == 0 {
+            bail!("Font has no units per em");
+        }
+        let scale = |value: i32| value * 1000 / units;
+        let bbox = [
+            scale(i16_at(&data, head + 36)? as i32),
+            scale(i16_at(&data, head + 38)? as i32),
+            scale(i16_at(&data, head + 40)? as i32),
+            scale(i16_at(&data, head + 42)? as i32),
+        ];
+        let ascent = scale(i16_at(&data, hhea + 4)? as i32);
+        let descent = scale(i16_at(&data, hhea + 6)? as i32);
+        let metrics = u16_at(&data, hhea + 34)? as usize;
+        if metrics == 0 {
+            bail!("Font has no horizontal metrics");
+        }
+        let italic_angle = u32_at(&data, post + 4)? as i32 as f32 / 65536.0;
+        let fixed_pitch = u32_at(&data, post + 12)? != 0;
+        let (weight, cap_height) = match os2 {
+            Some(os2) => {
+                let version = u16_at(&data, os2)?;
+                let cap_height = if version >= 2 { scale(i16_at(&data, os2 + 88)? as i32) } else { ascent };
+                (u16_at(&data, os2 + 4)? as i32, cap_height)
+            }
+            None => (400, ascent),
+        };
+
+        let subtable = Self::unicode_cmap(&data, cmap)?;
+        let mut widths = [0u16; 256];
+        for (code, width) in widths.iter_mut().enumerate() {
+            let Some(c) = decode(code as u8) else {
+                continue;
+            };
+            let glyph = Self::glyph(&data, subtable, c as u32)?;
+            let advance = u16_at(&data, hmtx + 4 * (glyph as usize).min(metrics - 1))?;
+            *width = scale(advance as i32) as u16;
+        }
+
+        let mut flags = 32;
+        if fixed_pitch {
+            flags |= 1;
+        }
+        if italic_angle != 0.0 {
+            flags |= 64;
+        }
+        let name = Self::postscript_name(&data, table(b"name").ok()).unwrap_or_else(|| "EmbeddedFont".to_string());
+        Ok(TrueType {
+            data,
+            name,
+            widths,
+            bbox,
+            ascent,
+            descent,
+            cap_height,
+            italic_angle,
+            stem_v: 10 + 220 * (weight - 50).max(0) / 900,
+            flags,
+        })
+    }
+
+    /// Offset of the format 4 Windows Unicode subtable of the `cmap` at `cmap`.
+    fn unicode_cmap(data: &[u8], cmap: usize) -> Result<usize> {
+        let count = u16_at(data, cmap + 2)? as usize;
+        for index in 0..count {
+            let record = cmap + 4 + index * 8;
+            let (platform, encoding) = (u16_at(data, record)?, u16_at(data, record + 2)?);
+            let subtable = cmap + u32_at(data, record + 4)? as usize;
+            if (platform, encoding) == (3, 1) && u16_at(data, subtable)? == 4 {
+                return Ok(subtable);
+            }
+        }
+        bail!("Font has no Windows Unicode cmap")
+    }
+
+    fn glyph(data: &[u8], subtable: usize, c: u32) -> Result<u16> {
+        let segments = u16_at(data, subtable + 6)? as usize / 2;
+        let ends = subtable + 14;
+        let starts = ends + segments * 2 + 2;
+        let deltas = starts + segments * 2;
+        let range_offsets = deltas + segments * 2;
+        for segment in 0..segments {
+            let end = u16_at(data, ends + segment * 2)? as u32;
+            if end < c {
+                continue;
+            }
+            let start = u16_at(data, starts + segment * 2)? as u32;
+            if start > c {
+                return Ok(0);
+            }
+            let delta = u16_at(data, deltas + segment * 2)?;
+            let range_offset = u16_at(data, range_offsets + segment * 2)? as usize;
+            if range_offset == 0 {
+                return Ok((c as u16).wrapping_add(delta));
+            }
+            let address = range_offsets + segment * 2 + range_offset + 2 * (c - start) as usize;
+            let glyph = u16_at(data, address)?;
+            return Ok(if glyph == 0 { 0 } else { glyph.wrapping_add(delta) });
+        }
+        Ok(0)
+    }
+
+    fn postscript_name(data: &[u8], name: Option<usize>) -> Option<String> {
+        let name = name?;
+        let count = u16_at(data, name + 2).ok()? as usize;
+        let strings = name + u16_at(data, name + 4).ok()? as usize;
+        for index in 0..count {
+            let record = name + 6 + index * 12;
+            if u16_at(data, record + 6).ok()? != 6 {
+                continue;
+            }
+            let platform = u16_at(data, record).ok()?;
+            let length = u16_at(data, record + 8).ok()? as usize;
+            let offset = strings + u16_at(data, record + 10).ok()? as usize;
+            let bytes = data.get(offset..offset + length)?;
+            let text = match platform {
+                0 | 3 => String::from_utf16_lossy(
+                    &bytes.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect::<Vec<_>>(),
+                ),
+                _ => bytes.iter().map(|&byte| byte as char).collect(),
+            };
+            // PDF names cannot hold spaces or delimiters.
+            let text: String = text.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-').collect();
+            if !text.is_empty() {
+                return Some(text);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,403 @@
+use anyhow::{bail, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rust_decimal::Decimal;
+
+use crate::models::contact::Contact;
+use crate::models::invoice::{Invoice, InvoiceStage};
+use crate::models::methods::{is_qr_iban, MethodKind, Methods, ReferenceType};
+use crate::render::escape_html;
+use crate::render::qr::QrCode;
+
+// Swiss QR-bills follow the Swiss Payment Standards' implementation
+// guidelines, version 2.3: a "SPC" payload in a QR code with the Swiss
+// cross, printed on a 210 x 105mm payment part at the bottom of an A4 page
+// with the receipt on its left. The labels are the English ones.
+
+/// A name and structured address, as a QR-bill states the creditor and the
+/// debtor.
+pub struct Party {
+    pub name: String,
+    pub street: Option<String>,
+    pub postcode: String,
+    pub town: String,
+    pub country: String,
+}
+
+impl Party {
+    /// The client billed, when its address has what a QR-bill requires.
+    fn debtor(name: &str, contact: &Contact) -> Option<Self> {
+        let (Some(postcode), Some(town), Some(country)) = (&contact.zip, &contact.city, &contact.country) else {
+            return None;
+        };
+        Some(Party {
+            name: truncate(name, 70),
+            street: contact.addr1.as_deref().map(|street| truncate(street, 70)),
+            postcode: truncate(postcode, 16),
+            town: truncate(town, 35),
+            country: country.clone(),
+        })
+    }
+    /// The seven payload lines of the party, with a structured address.
+    fn payload(&self) -> [&str; 7] {
+        ["S", &self.name, self.street.as_deref().unwrap_or(""), "", &self.postcode, &self.town, &self.country]
+    }
+    /// The party as printed: name, street and town, the town prefixed by
+    /// the country outside Switzerland and Liechtenstein.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![self.name.clone()];
+        lines.extend(self.street.clone());
+        if matches!(self.country.as_str(), "CH" | "LI") {
+            lines.push(format!("{} {}", self.postcode, self.town));
+        } else {
+            lines.push(format!("{}-{} {}", self.country, self.postcode, self.town));
+        }
+        lines
+    }
+}
+
+/// The payment part of an invoice paid by QR-bill.
+pub struct QrBill {
+    pub iban: String,
+    pub creditor: Party,
+    /// None leaves a box for the payer to fill in.
+    pub debtor: Option<Party>,
+    pub amount: Decimal,
+    pub currency: &'static str,
+    pub reference_type: ReferenceType,
+    /// Empty with the NON reference type.
+    pub reference: String,
+    pub message: String,
+}
+
+impl QrBill {
+    /// The QR-bill for what is left to pay on `invoice`, from its first
+    /// QR-bill payment method. None when it has none, is a quote, hides
+    /// its payment methods or leaves nothing to pay.
+    pub fn new(invoice: &Invoice) -> Result<Option<Self>> {
+        let Some(method) = invoice.template.methods.iter().find(|method| method.kind == MethodKind::Qrbill) else {
+            return Ok(None);
+        };
+        let currency = invoice.currency.code();
+        let amount = invoice.currency.round(invoice.balance());
+        if invoice.attributes.stage != InvoiceStage::Invoice || !invoice.attributes.show_methods || amount <= Decimal::ZERO {
+            return Ok(None);
+        }
+        if currency != "CHF" && currency != "EUR" {
+            bail!("QR-bills request CHF or EUR, the invoice is in {}", currency);
+        }
+        if amount > Decimal::new(99_999_999_999, 2) {
+            bail!("{:.2} is more than a QR-bill can request", amount);
+        }
+        let (iban, creditor) = creditor(method)?;
+        let qr_iban = is_qr_iban(&iban);
+        let reference_type = method.reference_type.unwrap_or(if qr_iban { ReferenceType::Qrr } else { ReferenceType::Scor });
+        let reference = match (reference_type, qr_iban) {
+            (ReferenceType::Qrr, true) => qr_reference(invoice.id),
+            (ReferenceType::Qrr, false) => bail!("a QRR reference needs a QR-IBAN, {} is a regular IBAN", iban),
+            (_, true) => bail!("the QR-IBAN {} can only be used with a QRR reference", iban),
+            (ReferenceType::Scor, false) => creditor_reference(&invoice.reference(), invoice.id),
+            (ReferenceType::Non, false) => String::new(),
+        };
+        let client = &invoice.template.client;
+        Ok(Some(QrBill {
+            iban,
+            creditor,
+            debtor: Party::debtor(&client.name, &client.contact),
+            amount,
+            currency,
+            reference_type,
+            reference,
+            message: truncate(&format!("Invoice {}", invoice.reference()), 140),
+        }))
+    }
+
+    /// The text encoded in the QR code.
+    pub fn payload(&self) -> String {
+        let mut lines = vec!["SPC", "0200", "1", &self.iban];
+        lines.extend(self.creditor.payload());
+        // The ultimate creditor is reserved for future use and left empty.
+        lines.extend([""; 7]);
+        let amount = format!("{:.2}", self.amount);
+        lines.extend([amount.as_str(), self.currency]);
+        match &self.debtor {
+            Some(debtor) => lines.extend(debtor.payload()),
+            None => lines.extend([""; 7]),
+        }
+        let reference_type = self.reference_type.to_string();
+        lines.extend([reference_type.as_str(), &self.reference, &self.message, "EPD"]);
+        lines.join("\n")
+    }
+
+    /// The QR code with the Swiss cross, as a PNG without a quiet zone.
+    pub fn png(&self) -> Result<Vec<u8>> {
+        QrCode::encode(self.payload().as_bytes(), 25)?.swiss_png()
+    }
+
+    /// The IBAN in groups of four.
+    pub fn formatted_iban(&self) -> String {
+        groups(&self.iban, 0, 4)
+    }
+
+    /// A QR reference in groups of five from the right, a creditor
+    /// reference in groups of four from the left.
+    pub fn formatted_reference(&self) -> String {
+        match self.reference_type {
+            ReferenceType::Qrr => groups(&self.reference, self.reference.len() % 5, 5),
+            _ => groups(&self.reference, 0, 4),
+        }
+    }
+
+    /// The amount with a space between thousands, e.g. `1 949.75`.
+    pub fn formatted_amount(&self) -> String {
+        let amount = format!("{:.2}", self.amount);
+        let (units, cents) = amount.split_at(amount.len() - 3);
+        let mut grouped = String::new();
+        for (index, digit) in units.chars().enumerate() {
+            if index > 0 && (units.len() - index) % 3 == 0 {
+                grouped.push(' ');
+            }
+            grouped.push(digit);
+        }
+        grouped + cents
+    }
+
+    /// The headings and lines of the receipt's information section, and of
+    /// the payment part's without its additional information.
+    pub fn blocks(&self) -> Vec<(&'static str, Vec<String>)> {
+        let mut account = vec![self.formatted_iban()];
+        account.extend(self.creditor.lines());
+        let mut blocks = vec![("Account / Payable to", account)];
+        if !self.reference.is_empty() {
+            blocks.push(("Reference", vec![self.formatted_reference()]));
+        }
+        blocks
+    }
+
+    /// The payment part as an HTML page of its own, to go at the end of a
+    /// rendered invoice. It sets its own A4 page without margins.
+    pub fn html(&self) -> Result<String> {
+        let code = format!("data:image/png;base64,{}", STANDARD.encode(self.png()?));
+        let section = |heading: &str, lines: &[String]| {
+            let lines: Vec<String> = lines.iter().map(|line| escape_html(line)).collect();
+            format!(
+                "<div class=\"qr-bill-section\"><div class=\"qr-bill-heading\">{}</div><div>{}</div></div>",
+                heading,
+                lines.join("<br>")
+            )
+        };
+        let blank = |width: u32, height: u32| {
+            format!(
+                concat!(
+                    "<div class=\"qr-bill-section\"><div class=\"qr-bill-heading\">Payable by (name/address)</div>",
+                    "<svg width=\"{w}mm\" height=\"{h}mm\" viewBox=\"0 0 {w} {h}\" fill=\"none\" stroke=\"#000\" stroke-width=\"0.26\">",
+                    "<path d=\"M0 3V0H3M{r} 0H{w}V3M{w} {b}V{h}H{r}M3 {h}H0V{b}\"/></svg></div>"
+                ),
+                w = width,
+                h = height,
+                r = width - 3,
+                b = height - 3
+            )
+        };
+        let amount = |amount_left: &str| {
+            format!(
+                concat!(
+                    "<div class=\"qr-bill-amount\"><div class=\"qr-bill-heading\">Currency</div><div>{}</div></div>\n",
+                    "<div class=\"qr-bill-amount\" style=\"left: {}\"><div class=\"qr-bill-heading\">Amount</div><div>{}</div></div>"
+                ),
+                self.currency,
+                amount_left,
+                self.formatted_amount()
+            )
+        };
+        let mut receipt = String::new();
+        let mut payment = String::new();
+        for (heading, lines) in self.blocks() {
+            receipt.push_str(&section(heading, &lines));
+            payment.push_str(&section(heading, &lines));
+        }
+        payment.push_str(&section("Additional information", std::slice::from_ref(&self.message)));
+        match &self.debtor {
+            Some(debtor) => {
+                receipt.push_str(&section("Payable by", &debtor.lines()));
+                payment.push_str(&section("Payable by", &debtor.lines()));
+            }
+            None => {
+                receipt.push_str(&blank(52, 20));
+                payment.push_str(&blank(65, 25));
+            }
+        }
+        Ok(format!(
+            concat!(
+                "<style>\n",
+                "@page qr-bill {{ size: A4 portrait; margin: 0; }}\n",
+                ".qr-bill-page {{ page: qr-bill; break-before: page; position: relative; width: 210mm; height: 296mm; margin: 0; }}\n",
+                ".qr-bill {{ position: absolute; left: 0; bottom: 0; width: 210mm; height: 105mm; border-top: 0.2mm dashed #000;",
+                " font-family: Helvetica, Arial, sans-serif; color: #000; background: #fff; text-align: left; }}\n",
+                ".qr-bill div {{ margin: 0; padding: 0; box-sizing: border-box; }}\n",
+                ".qr-bill-receipt, .qr-bill-payment {{ position: absolute; top: 0; height: 105mm; }}\n",
+                ".qr-bill-receipt {{ left: 0; width: 62mm; border-right: 0.2mm dashed #000; font-size: 8pt; line-height: 9pt; }}\n",
+                ".qr-bill-payment {{ left: 62mm; width: 148mm; font-size: 10pt; line-height: 11pt; }}\n",
+                ".qr-bill-receipt .qr-bill-heading {{ font-size: 6pt; font-weight: bold; }}\n",
+                ".qr-bill-payment .qr-bill-heading {{ font-size: 8pt; font-weight: bold; }}\n",
+                ".qr-bill-receipt .qr-bill-section {{ margin-bottom: 9pt; }}\n",
+                ".qr-bill-payment .qr-bill-section {{ margin-bottom: 11pt; }}\n",
+                ".qr-bill-title {{ position: absolute; left: 5mm; top: 5mm; font-size: 11pt; font-weight: bold; }}\n",
+                ".qr-bill-info {{ position: absolute; left: 5mm; top: 12mm; width: 52mm; }}\n",
+                ".qr-bill-payment .qr-bill-info {{ left: 56mm; top: 5mm; width: 87mm; }}\n",
+                ".qr-bill-amount {{ position: absolute; left: 5mm; top: 68mm; }}\n",
+                ".qr-bill-acceptance {{ position: absolute; right: 5mm; top: 82mm; font-size: 6pt; font-weight: bold; }}\n",
+                ".qr-bill-code {{ position: absolute; left: 5mm; top: 17mm; width: 46mm; height: 46mm; }}\n",
+                "</style>\n",
+                "<div class=\"qr-bill-page\"><div class=\"qr-bill\">\n",
+                "<div class=\"qr-bill-receipt\">\n",
+                "<div class=\"qr-bill-title\">Receipt</div>\n",
+                "<div class=\"qr-bill-info\">{}</div>\n",
+                "{}\n",
+                "<div class=\"qr-bill-acceptance\">Acceptance point</div>\n",
+                "</div>\n",
+                "<div class=\"qr-bill-payment\">\n",
+                "<div class=\"qr-bill-title\">Payment part</div>\n",
+                "<img class=\"qr-bill-code\" src=\"{}\" alt=\"Swiss QR code\">\n",
+                "{}\n",
+                "<div class=\"qr-bill-info\">{}</div>\n",
+                "</div>\n",
+                "</div></div>\n"
+            ),
+            receipt,
+            amount("18mm"),
+            code,
+            amount("22mm"),
+            payment
+        ))
+    }
+}
+
+/// The creditor's account and address from a QR-bill payment method.
+fn creditor(method: &Methods) -> Result<(String, Party)> {
+    let (Some(iban), Some(name), Some(postcode), Some(town), Some(country)) =
+        (&method.iban, &method.beneficiary, &method.postcode, &method.town, &method.country)
+    else {
+        bail!("QR-bill method {} needs an IBAN, a beneficiary, a postcode, a town and a country", method.name);
+    };
+    if !(iban.starts_with("CH") || iban.starts_with("LI")) || iban.len() != 21 {
+        bail!("QR-bills are paid to Swiss or Liechtenstein IBANs, not {}", iban);
+    }
+    let party = Party {
+        name: name.clone(),
+        street: method.street.clone(),
+        postcode: postcode.clone(),
+        town: town.clone(),
+        country: country.clone(),
+    };
+    Ok((iban.clone(), party))
+}
+
+/// The 27 digit QR reference of invoice `id`: the ID padded to 26 digits
+/// and its check digit.
+fn qr_reference(id: i64) -> String {
+    let digits = format!("{:026}", id.unsigned_abs());
+    format!("{}{}", digits, check_digit(&digits))
+}
+
+/// The recursive modulo 10 check digit of `digits`.
+fn check_digit(digits: &str) -> u32 {
+    const TABLE: [u32; 10] = [0, 9, 4, 6, 8, 2, 7, 1, 3, 5];
+    let carry = digits.chars().fold(0, |carry, c| TABLE[((carry + c.to_digit(10).unwrap_or_default()) % 10) as usize]);
+    (10 - carry) % 10
+}
+
+/// The ISO 11649 creditor reference of an invoice: RF, two check digits
+/// and the letters and digits of `reference`, or its ID when that has none.
+fn creditor_reference(reference: &str, id: i64) -> String {
+    let mut body: String = reference.chars().filter(char::is_ascii_alphanumeric).collect::<String>().to_uppercase();
+    if body.is_empty() {
+        body = id.to_string();
+    }
+    let body = body[body.len().saturating_sub(21)..].to_string();
+    // Like an IBAN: moved to the end with letters as numbers, the reference is 1 mod 97.
+    let remainder = body.chars().chain("RF00".chars()).fold(0u32, |remainder, c| {
+        let value = c.to_digit(36).unwrap_or_default();
+        if value < 10 {
+            (remainder * 10 + value) % 97
+        } else {
+            (remainder * 100 + value) % 97
+        }
+    });
+    format!("RF{:02}{}", 98 - remainder, body)
+}
+
+/// `text` split by spaces into groups of `size`, after a first group of
+/// `first` when that is not zero.
+fn groups(text: &str, first: usize, size: usize) -> String {
+    let (head, rest) = text.split_at(first.min(text.len()));
+    let mut parts: Vec<&str> = Vec::new();
+    if !head.is_empty() {
+        parts.push(head);
+    }
+    let mut rest = rest;
+    while !rest.is_empty() {
+        let (part, tail) = rest.split_at(size.min(rest.len()));
+        parts.push(part);
+        rest = tail;
+    }
+    parts.join(" ")
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    text.trim().chars().take(max).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn creditor_reference_matches_iso_11649() {
+        assert_eq!(creditor_reference("539007547034", 1), "RF18539007547034");
+        assert_eq!(creditor_reference("g72u-ur", 1), "RF45G72UUR");
+        // Invoices without letters or digits in their number use the ID.
+        assert_eq!(creditor_reference("#", 539007547034), "RF18539007547034");
+    }
+
+    #[test]
+    fn qr_reference_has_a_recursive_mod_10_check_digit() {
+        // The QR reference of the SIX sample bills.
+        assert_eq!(check_digit("21000000000313947143000901"), 7);
+        assert_eq!(qr_reference(42), "000000000000000000000000420");
+    }
+
+    #[test]
+    fn payload_follows_the_six_sample() {
+        let bill = QrBill {
+            iban: "CH4431999123000889012".to_string(),
+            creditor: Party {
+                name: "Robert Schneider AG".to_string(),
+                street: Some("Rue du Lac 1268".to_string()),
+                postcode: "2501".to_string(),
+                town: "Biel".to_string(),
+                country: "CH".to_string(),
+            },
+            debtor: Some(Party {
+                name: "Pia-Maria Rutschmann-Schnyder".to_string(),
+                street: Some("Grosse Marktgasse 28".to_string()),
+                postcode: "9400".to_string(),
+                town: "Rorschach".to_string(),
+                country: "CH".to_string(),
+            }),
+            amount: Decimal::new(194975, 2),
+            currency: "CHF",
+            reference_type: ReferenceType::Qrr,
+            reference: "210000000003139471430009017".to_string(),
+            message: "Order from 15.10.2020".to_string(),
+        };
+        let expected = [
+            "SPC", "0200", "1", "CH4431999123000889012",
+            "S", "Robert Schneider AG", "Rue du Lac 1268", "", "2501", "Biel", "CH",
+            "", "", "", "", "", "", "",
+            "1949.75", "CHF",
+            "S", "Pia-Maria Rutschmann-Schnyder", "Grosse Marktgasse 28", "", "9400", "Rorschach", "CH",
+            "QRR", "210000000003139471430009017", "Order from 15.10.2020", "EPD",
+        ];
+        assert_eq!(bill.payload(), expected.join("\n"));
+    }
+}
@@ -0,0 +1,219 @@
+use rust_decimal::Decimal;
+
+use crate::models::client::Client;
+use crate::models::company::Company;
+use crate::models::contact::Contact;
+use crate::models::credit_note::CreditNote;
+use crate::models::currency::{Currency, Locale};
+use crate::models::invoice::{Discount, Invoice, InvoiceAttrs, InvoiceLine, InvoiceStage, PaidStatus};
+use crate::models::methods::{MethodKind, Methods, ReferenceType};
+use crate::models::payments::Payments;
+use crate::models::taxes::Taxes;
+use crate::models::template::Template;
+use crate::models::terms::Terms;
+
+// Fixed documents used by `layouts validate`, filled in so that every
+// optional block of a layout is exercised.
+
+fn contact(name: &str) -> Contact {
+    Contact {
+        phone: Some("555-0100".to_string()),
+        email: Some(format!("billing@{}.example", name.to_lowercase())),
+        addr1: Some("1 Main Street".to_string()),
+        addr2: Some("Suite 2".to_string()),
+        city: Some("Springfield".to_string()),
+        state: Some("IL".to_string()),
+        zip: Some("62701".to_string()),
+        country: Some("US".to_string()),
+    }
+}
+
+fn tax() -> Taxes {
+    Taxes {
+        id: 1,
+        name: "Sales Tax".to_string(),
+        rate: Decimal::new(825, 2),
+    }
+}
+
+fn template() -> Template {
+    Template {
+        id: 1,
+        name: "Sample".to_string(),
+        company: Company {
+            id: 1,
+            name: "Sample Company".to_string(),
+            logo: None,
+            contact: contact("Company"),
+            number_pattern: None,
+            page_setup: None,
+            tax_id: Some("US12-3456789".to_string()),
+        },
+        client: Client {
+            id: 1,
+            name: "Sample Client".to_string(),
+            contact: contact("Client"),
+            tax_id: Some("US98-7654321".to_string()),
+            currency: None,
+        },
+        terms: Terms {
+            id: 1,
+            name: "Net 30".to_string(),
+            due: 30,
+        },
+        methods: vec![
+            Methods {
+                id: 1,
+                name: "Online Payment".to_string(),
+                link: Some("https://pay.example/sample".to_string()),
+                instructions: Some("Pay online within 30 days".to_string()),
+                ..Default::default()
+            },
+            Methods {
+                id: 2,
+                name: "Bank Transfer".to_string(),
+                kind: MethodKind::Sepa,
+                iban: Some("DE89370400440532013000".to_string()),
+                bic: Some("COBADEFFXXX".to_string()),
+                beneficiary: Some("Sample Company".to_string()),
+                ..Default::default()
+            },
+            Methods {
+                id: 3,
+                name: "QR-bill".to_string(),
+                kind: MethodKind::Qrbill,
+                iban: Some("CH4431999123000889012".to_string()),
+                beneficiary: Some("Sample Company".to_string()),
+                reference_type: Some(ReferenceType::Qrr),
+                street: Some("Musterstrasse 1".to_string()),
+                postcode: Some("8000".to_string()),
+                town: Some("Zurich".to_string()),
+                country: Some("CH".to_string()),
+                ..Default::default()
+            },
+            Methods {
+                id: 4,
+                name: "Wire".to_string(),
+                kind: MethodKind::Bank,
+                beneficiary: Some("Sample Company".to_string()),
+                bank_name: Some("Sample Bank".to_string()),
+                account_number: Some("000123456789".to_string()),
+                routing_number: Some("021000021".to_string()),
+                bic: Some("CHASUS33".to_string()),
+                ..Default::default()
+            },
+            Methods {
+                id: 5,
+                name: "Card".to_string(),
+                kind: MethodKind::Card,
+                link: Some("https://pay.example/card".to_string()),
+                ..Default::default()
+            },
+            Methods {
+                id: 6,
+                name: "PayPal".to_string(),
+                kind: MethodKind::Paypal,
+                paypal: Some("https://paypal.me/sample".to_string()),
+                ..Default::default()
+            },
+            Methods {
+                id: 7,
+                name: "Check".to_string(),
+                kind: MethodKind::Check,
+                beneficiary: Some("Sample Company".to_string()),
+                street: Some("1 Sample Street".to_string()),
+                postcode: Some("12345".to_string()),
+                town: Some("Springfield".to_string()),
+                country: Some("US".to_string()),
+                ..Default::default()
+            },
+            Methods {
+                id: 8,
+                name: "Cash".to_string(),
+                kind: MethodKind::Cash,
+                instructions: Some("At the front desk".to_string()),
+                ..Default::default()
+            },
+            Methods {
+                id: 9,
+                name: "Bitcoin".to_string(),
+                kind: MethodKind::Crypto,
+                network: Some("BTC".to_string()),
+                wallet: Some("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq".to_string()),
+                ..Default::default()
+            },
+        ],
+        taxes: vec![tax()],
+        layout: None,
+    }
+}
+
+fn line(name: &str, rate: i64, quantity: i64) -> InvoiceLine {
+    InvoiceLine {
+        item: Some(1),
+        name: name.to_string(),
+        description: Some("Sample description".to_string()),
+        rate: Decimal::new(rate, 2),
+        quantity: Decimal::from(quantity),
+        unit: Some("hours".to_string()),
+        taxes: vec![tax()],
+        discount: None,
+    }
+}
+
+fn currency() -> Currency {
+    Currency::new("EUR", Locale::default()).expect("EUR is an ISO 4217 currency")
+}
+
+pub fn invoice() -> Invoice {
+    let mut discounted = line("Design", 7500, 4);
+    discounted.discount = Some(Discount::Percent(Decimal::TEN));
+    Invoice {
+        id: 1,
+        template: template(),
+        attributes: InvoiceAttrs {
+            show_methods: true,
+            show_notes: true,
+            stage: InvoiceStage::Invoice,
+            status: PaidStatus::Paid {
+                date: "20260115".to_string(),
+                check: Some("1042".to_string()),
+            },
+        },
+        date: "20260101".to_string(),
+        notes: Some("<p>Thank you for your business.</p>".to_string()),
+        items: vec![line("Consulting", 12000, 10), discounted],
+        discount: Some(Discount::Fixed(Decimal::from(50))),
+        payments: vec![Payments {
+            id: 1,
+            invoice_id: 1,
+            amount: Decimal::from(500),
+            date: "20260115".to_string(),
+            method_id: Some(1),
+            method: Some("Bank Transfer".to_string()),
+            reference: Some("1042".to_string()),
+            currency: currency(),
+        }],
+        credits: vec![credit_note()],
+        source_quote: None,
+        quote_status: None,
+        expires: None,
+        number: Some("INV-2026-0001".to_string()),
+        currency: currency(),
+    }
+}
+
+pub fn credit_note() -> CreditNote {
+    CreditNote {
+        id: 1,
+        number: 1,
+        invoice_id: 1,
+        invoice_date: "20260101".to_string(),
+        invoice_number: Some("INV-2026-0001".to_string()),
+        template: template(),
+        date: "20260110".to_string(),
+        reason: Some("Returned goods".to_string()),
+        items: vec![line("Consulting", 12000, -2)],
+        currency: currency(),
+    }
+}